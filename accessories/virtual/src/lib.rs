@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use houseflow_accessory_hal::Accessory;
 use houseflow_api::hub::hive::HiveClient;
+use houseflow_config::accessory::characteristics::BinaryState;
 use houseflow_config::accessory::Services;
 use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics;
@@ -17,8 +18,8 @@ pub struct VirtualAccessory {
 
 impl VirtualAccessory {
     pub fn new(client: HiveClient<VirtualAccessory>, services: Services) -> Self {
-        let Services { temperature_sensor } = services.clone();
-        if let Some(service) = temperature_sensor {
+        if let Some(service) = services.temperature_sensor.clone() {
+            let client = client.clone();
             tokio::spawn(async move {
                 loop {
                     let temperature = service.current_temperature.command.execute().unwrap();
@@ -33,8 +34,92 @@ impl VirtualAccessory {
                 }
             });
         };
+        for (service_name, characteristic_name, state) in services.binary_states() {
+            let client = client.clone();
+            let state = state.clone();
+            tokio::spawn(async move {
+                let mut last_value = None;
+                loop {
+                    match read_binary_state(&state) {
+                        // Only the changes are reported, so automations don't trigger repeatedly
+                        Ok(value) if last_value != Some(value) => {
+                            let characteristic = binary_characteristic(characteristic_name, value);
+                            client.update(service_name, characteristic).await;
+                            last_value = Some(value);
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            tracing::error!(%service_name, %characteristic_name, "{}", err)
+                        }
+                    }
+                    tokio::time::sleep(state.interval).await;
+                }
+            });
+        }
         Self { services }
     }
+
+    fn binary_state(
+        &self,
+        service_name: ServiceName,
+        characteristic_name: CharacteristicName,
+    ) -> Option<&BinaryState> {
+        self.services
+            .binary_states()
+            .into_iter()
+            .find(|(service, characteristic, _)| {
+                *service == service_name && *characteristic == characteristic_name
+            })
+            .map(|(_, _, state)| state)
+    }
+}
+
+fn read_binary_state(state: &BinaryState) -> Result<bool, accessory::Error> {
+    let output = state.command.execute().map_err(|err| {
+        tracing::error!("executing binary state command: {}", err);
+        accessory::Error::ReadFailed
+    })?;
+    match std::str::from_utf8(&output).map(str::trim) {
+        Ok("1" | "true") => Ok(true),
+        Ok("0" | "false") => Ok(false),
+        output => {
+            tracing::error!("unexpected binary state: {:?}", output);
+            Err(accessory::Error::ReadFailed)
+        }
+    }
+}
+
+fn binary_characteristic(characteristic_name: CharacteristicName, value: bool) -> Characteristic {
+    match characteristic_name {
+        CharacteristicName::MotionDetected => {
+            Characteristic::MotionDetected(characteristics::MotionDetected { detected: value })
+        }
+        CharacteristicName::ContactSensorState => {
+            Characteristic::ContactSensorState(characteristics::ContactSensorState {
+                contact: value,
+            })
+        }
+        CharacteristicName::LeakDetected => {
+            Characteristic::LeakDetected(characteristics::LeakDetected { detected: value })
+        }
+        CharacteristicName::SmokeDetected => {
+            Characteristic::SmokeDetected(characteristics::SmokeDetected { detected: value })
+        }
+        CharacteristicName::OccupancyDetected => {
+            Characteristic::OccupancyDetected(characteristics::OccupancyDetected {
+                detected: value,
+            })
+        }
+        CharacteristicName::StatusTampered => {
+            Characteristic::StatusTampered(characteristics::StatusTampered { tampered: value })
+        }
+        CharacteristicName::StatusLowBattery => {
+            Characteristic::StatusLowBattery(characteristics::StatusLowBattery {
+                low_battery: value,
+            })
+        }
+        _ => unreachable!(),
+    }
 }
 
 #[async_trait]
@@ -53,6 +138,13 @@ impl Accessory for VirtualAccessory {
                     _ => return Err(accessory::Error::CharacteristicNotSupported),
                 }
             }
+            service_name if self.services.names().contains(&service_name) => {
+                let characteristic_name = CharacteristicName::from(&characteristic);
+                match self.binary_state(service_name, characteristic_name) {
+                    Some(_) => return Err(accessory::Error::CharacteristicReadOnly),
+                    None => return Err(accessory::Error::CharacteristicNotSupported),
+                }
+            }
             _ => return Err(accessory::Error::ServiceNotSupported),
         };
     }
@@ -77,6 +169,15 @@ impl Accessory for VirtualAccessory {
                     _ => return Err(accessory::Error::CharacteristicNotSupported),
                 }
             }
+            service_name if self.services.names().contains(&service_name) => {
+                match self.binary_state(service_name, characteristic_name) {
+                    Some(state) => Ok(binary_characteristic(
+                        characteristic_name,
+                        read_binary_state(state)?,
+                    )),
+                    None => Err(accessory::Error::CharacteristicNotSupported),
+                }
+            }
             _ => return Err(accessory::Error::ServiceNotSupported),
         }
     }
@@ -23,6 +23,14 @@ impl Client {
         self.post(url, request).await
     }
 
+    pub async fn register(
+        &self,
+        request: &auth::register::Request,
+    ) -> Result<Result<auth::register::Response, ServerError>, Error> {
+        let url = self.auth_url("register");
+        self.post(url, request).await
+    }
+
     pub async fn refresh_token(
         &self,
         refresh_token: &RefreshToken,
@@ -32,6 +40,15 @@ impl Client {
             .await
     }
 
+    pub async fn logout(
+        &self,
+        request: &auth::logout::Request,
+        refresh_token: &RefreshToken,
+    ) -> Result<Result<auth::logout::Response, ServerError>, Error> {
+        let url = self.auth_url("logout");
+        self.post_with_token(url, request, refresh_token).await
+    }
+
     pub async fn whoami(
         &self,
         access_token: &AccessToken,
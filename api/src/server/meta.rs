@@ -5,6 +5,9 @@ use houseflow_types::accessory::characteristics::Characteristic;
 use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
 use houseflow_types::errors::ServerError;
+use houseflow_types::meta;
+use houseflow_types::token::AccessToken;
+use reqwest::StatusCode;
 use reqwest::Url;
 
 impl Client {
@@ -18,6 +21,7 @@ impl Client {
 
     pub async fn read_characteristics(
         &self,
+        access_token: &AccessToken,
         accessory_id: &accessory::ID,
         service_name: &ServiceName,
         characteristic_name: &CharacteristicName,
@@ -26,16 +30,124 @@ impl Client {
             "characteristic/{}/{}/{}",
             accessory_id, service_name, characteristic_name
         ));
-        self.get(url, &()).await
+        self.get_with_token(url, &(), access_token).await
     }
 
     pub async fn write_characteristics(
         &self,
+        access_token: &AccessToken,
         accessory_id: &accessory::ID,
         service_name: &ServiceName,
         characteristic: &Characteristic,
     ) -> Result<Result<(), ServerError>, Error> {
         let url = self.meta_url(&format!("characteristic/{}/{}", accessory_id, service_name));
-        self.get(url, characteristic).await
+        self.post_with_token(url, characteristic, access_token)
+            .await
     }
+
+    pub async fn get_accessories(
+        &self,
+        access_token: &AccessToken,
+    ) -> Result<Result<meta::get_accessories::Response, ServerError>, Error> {
+        let url = self.meta_url("accessories");
+        self.get_with_token(url, &(), access_token).await
+    }
+
+    /// Subscribes to the stream of accessory events.
+    ///
+    /// If `last_event_id` is specified, events sent after it are replayed first.
+    pub async fn events(
+        &self,
+        access_token: &AccessToken,
+        last_event_id: Option<u64>,
+    ) -> Result<Result<EventStream, ServerError>, Error> {
+        let url = self.meta_url("events");
+        let mut request = self.client.get(url).bearer_auth(access_token);
+        if let Some(last_event_id) = last_event_id {
+            request = request.header("last-event-id", last_event_id.to_string());
+        }
+        let response = request.send().await?;
+        let status_code = response.status();
+        if !status_code.is_success() {
+            let bytes = response.bytes().await?;
+            let error =
+                serde_json::from_slice(&bytes).map_err(|err| Error::InvalidResponseBody {
+                    error: Box::new(err),
+                    status_code,
+                    body: String::from_utf8_lossy(&bytes).into_owned(),
+                })?;
+            return Ok(Err(error));
+        }
+        Ok(Ok(EventStream {
+            response,
+            buffer: Vec::new(),
+        }))
+    }
+}
+
+/// Event received from the event stream
+#[derive(Debug, Clone)]
+pub struct ReceivedEvent {
+    /// ID of the event, used to resume the stream after reconnecting
+    pub id: Option<u64>,
+    pub event: meta::Event,
+}
+
+/// Stream of the server-sent events
+#[derive(Debug)]
+pub struct EventStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    /// Waits for the next event, returns `None` if the server has closed the stream
+    pub async fn next(&mut self) -> Result<Option<ReceivedEvent>, Error> {
+        loop {
+            if let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+                let message = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+                let message = String::from_utf8_lossy(&message);
+                match parse_event(&message) {
+                    Ok(Some(event)) => return Ok(Some(event)),
+                    Ok(None) => continue,
+                    Err(err) => {
+                        return Err(Error::InvalidResponseBody {
+                            error: Box::new(err),
+                            status_code: StatusCode::OK,
+                            body: message.into_owned(),
+                        })
+                    }
+                }
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+fn parse_event(message: &str) -> Result<Option<ReceivedEvent>, serde_json::Error> {
+    let mut id = None;
+    let mut data = String::new();
+    for line in message.lines() {
+        // Lines without a field name are comments, e.g. keep-alive messages
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "id" => id = value.parse().ok(),
+            "data" => {
+                if !data.is_empty() {
+                    data.push('\n');
+                }
+                data.push_str(value);
+            }
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        return Ok(None);
+    }
+    let event = serde_json::from_str(&data)?;
+    Ok(Some(ReceivedEvent { id, event }))
 }
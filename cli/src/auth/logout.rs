@@ -1,11 +1,33 @@
 use crate::CommandContext;
 use async_trait::async_trait;
+use houseflow_types::errors::AuthError;
+use houseflow_types::errors::ServerError;
 
-pub struct Command {}
+pub struct Command {
+    pub all_sessions: bool,
+}
 
 #[async_trait]
 impl crate::Command for Command {
-    async fn run(self, ctx: CommandContext) -> anyhow::Result<()> {
+    async fn run(self, mut ctx: CommandContext) -> anyhow::Result<()> {
+        use houseflow_types::auth::logout;
+
+        match ctx.refresh_token() {
+            Ok(refresh_token) => {
+                let request = logout::Request {
+                    all_sessions: self.all_sessions,
+                };
+                let response = ctx
+                    .server_client()?
+                    .logout(&request, &refresh_token)
+                    .await?;
+                match response {
+                    Ok(_) | Err(ServerError::AuthError(AuthError::RefreshTokenBlacklisted)) => {}
+                    Err(err) => return Err(err.into()),
+                };
+            }
+            Err(err) => tracing::warn!("Refresh token could not be revoked: {:#}", err),
+        };
         ctx.tokens.remove()?;
         tracing::info!("✔ Succesfully logged out");
 
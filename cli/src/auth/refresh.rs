@@ -11,7 +11,7 @@ impl crate::Command for Command {
         let refresh_token = ctx.refresh_token()?;
         let response = ctx.server_client()?.refresh_token(&refresh_token).await??;
         let tokens = Tokens {
-            refresh: tokens.refresh,
+            refresh: response.refresh_token.unwrap_or(tokens.refresh),
             access: response.access_token,
         };
         ctx.tokens.save(&tokens)?;
@@ -0,0 +1,40 @@
+use crate::CommandContext;
+use async_trait::async_trait;
+use houseflow_types::code::VerificationCode;
+
+pub struct Command {
+    pub email: lettre::Address,
+    pub username: String,
+    pub code: Option<VerificationCode>,
+}
+
+#[async_trait]
+impl crate::Command for Command {
+    async fn run(self, ctx: CommandContext) -> anyhow::Result<()> {
+        use houseflow_types::auth::register;
+
+        let request = register::Request {
+            email: self.email,
+            username: self.username,
+            verification_code: self.code,
+        };
+        let response = ctx.server_client()?.register(&request).await??;
+        match response {
+            register::Response::Registered { user_id } => {
+                tracing::info!(
+                    "✔ Registered as {} with ID {}. You can now log in with `houseflow auth login`",
+                    request.email,
+                    user_id
+                );
+            }
+            register::Response::VerificationCodeSent => {
+                tracing::info!(
+                    "✔ Verification code sent to {}. Please copy the code and re-run the command with --code <code-from-email>",
+                    request.email
+                );
+            }
+        };
+
+        Ok(())
+    }
+}
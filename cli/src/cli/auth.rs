@@ -24,8 +24,41 @@ fn login() -> Command<'static> {
         )
 }
 
+fn register() -> Command<'static> {
+    Command::new("register")
+        .about("Create a new Houseflow account")
+        .arg(
+            Arg::new("email")
+                .help("Email of the new account")
+                .long("email")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("username")
+                .help("Name of the new account")
+                .long("username")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("code")
+                .help("Verification code confirming the registration")
+                .long("code")
+                .validator(|s| match VerificationCode::from_str(s) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(err.to_string()),
+                })
+                .takes_value(true),
+        )
+}
+
 fn logout() -> Command<'static> {
-    Command::new("logout").about("Log out from currently logged account")
+    Command::new("logout")
+        .about("Log out from currently logged account")
+        .arg(
+            Arg::new("all-sessions")
+                .help("Log out from all sessions of the account, on every device")
+                .long("all-sessions"),
+        )
 }
 
 fn refresh() -> Command<'static> {
@@ -44,8 +77,9 @@ fn status() -> Command<'static> {
 
 pub(super) fn subcommand() -> clap::Command<'static> {
     Command::new("auth")
-        .about("Login, Logout, and refresh your authentication")
+        .about("Register, Login, Logout, and refresh your authentication")
         .subcommand(login())
+        .subcommand(register())
         .subcommand(logout())
         .subcommand(refresh())
         .subcommand(status())
@@ -0,0 +1,13 @@
+use clap::Arg;
+use clap::Command;
+
+pub(super) fn subcommand() -> Command<'static> {
+    Command::new("hash-password")
+        .about("Generate password hash for the hub configuration of the server")
+        .arg(
+            Arg::new("password")
+                .help("Password to hash, prompted for if not specified")
+                .long("password")
+                .takes_value(true),
+        )
+}
@@ -1,64 +1,117 @@
 use clap::Arg;
 use clap::Command;
-use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::Characteristic;
 use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
-use std::str::FromStr;
+
+fn accessory_arg() -> Arg<'static> {
+    Arg::new("accessory")
+        .help("ID, name or room of the accessory")
+        .long("accessory")
+        .takes_value(true)
+}
+
+pub(super) fn service_name_arg(help: &'static str) -> Arg<'static> {
+    Arg::new("service-name")
+        .help(help)
+        .long("service")
+        .validator(
+            |s| match ServiceName::VARIANTS.iter().find(|v| v.to_string() == s) {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "variant {} not found. Available variants: [{}]",
+                    s,
+                    ServiceName::VARIANTS.join(",")
+                )),
+            },
+        )
+        .takes_value(true)
+}
+
+pub(super) fn characteristic_name_arg(help: &'static str) -> Arg<'static> {
+    Arg::new("characteristic-name")
+        .help(help)
+        .long("characteristic")
+        .validator(|s| {
+            match CharacteristicName::VARIANTS
+                .iter()
+                .find(|v| v.to_string() == s)
+            {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "variant {} not found. Available variants: [{}]",
+                    s,
+                    CharacteristicName::VARIANTS.join(",")
+                )),
+            }
+        })
+        .takes_value(true)
+}
+
+fn format_arg() -> Arg<'static> {
+    Arg::new("format")
+        .help("Output format")
+        .long("format")
+        .possible_values(["table", "json"])
+        .default_value("table")
+        .takes_value(true)
+}
 
 fn read() -> Command<'static> {
     Command::new("read")
         .about("Read characteristic of the accessory")
+        .arg(accessory_arg())
+        .arg(characteristic_name_arg(
+            "Name of the characteristic to read",
+        ))
+        .arg(service_name_arg("Name of the service to read"))
+        .arg(format_arg())
+}
+
+fn write() -> Command<'static> {
+    Command::new("write")
+        .about("Write characteristic of the accessory")
+        .arg(accessory_arg())
+        .arg(service_name_arg("Name of the service to write"))
+        .arg(characteristic_name_arg(
+            "Name of the characteristic to write",
+        ))
         .arg(
-            Arg::new("accessory-id")
-                .help("ID of the accessory")
-                .long("accessory")
-                .validator(|s| match accessory::ID::from_str(s) {
-                    Ok(_) => Ok(()),
-                    Err(err) => Err(err.to_string()),
+            Arg::new("value")
+                .help("Value of the characteristic field, e.g. `on=true`")
+                .long("value")
+                .validator(|s| match s.split_once('=') {
+                    Some(_) => Ok(()),
+                    None => Err(String::from("expected `key=value`")),
                 })
-                .takes_value(true),
+                .takes_value(true)
+                .multiple_occurrences(true),
         )
         .arg(
-            Arg::new("characteristic-name")
-                .help("Name of the characteristic to read")
-                .long("characteristic")
-                .validator(|s| {
-                    match CharacteristicName::VARIANTS
-                        .iter()
-                        .find(|v| v.to_string() == s)
-                    {
-                        Some(_) => Ok(()),
-                        None => Err(format!(
-                            "variant {} not found. Available variants: [{}]",
-                            s,
-                            CharacteristicName::VARIANTS.join(",")
-                        )),
-                    }
+            Arg::new("json")
+                .help("Characteristic in JSON format, e.g. `{\"name\": \"on\", \"on\": true}`")
+                .long("json")
+                .validator(|s| match serde_json::from_str::<Characteristic>(s) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(err.to_string()),
                 })
+                .conflicts_with_all(&["characteristic-name", "value"])
                 .takes_value(true),
         )
-        .arg(
-            Arg::new("service-name")
-                .help("Name of the service to read")
-                .long("service")
-                .validator(
-                    |s| match ServiceName::VARIANTS.iter().find(|v| v.to_string() == s) {
-                        Some(_) => Ok(()),
-                        None => Err(format!(
-                            "variant {} not found. Available variants: [{}]",
-                            s,
-                            ServiceName::VARIANTS.join(",")
-                        )),
-                    },
-                )
-                .takes_value(true),
-        )
+}
+
+fn list() -> Command<'static> {
+    Command::new("list")
+        .about("List accessories available to the user")
+        .arg(format_arg())
 }
 
 pub(super) fn subcommand() -> Command<'static> {
     Command::new("meta")
-        .about("Read or write characteristic of the accessory")
+        .about("Read or write characteristics of the accessories")
         .subcommand(read())
+        .subcommand(write())
+        .subcommand(list())
         .subcommand_required(true)
         .arg_required_else_help(true)
 }
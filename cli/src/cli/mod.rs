@@ -1,6 +1,8 @@
 mod auth;
 mod completions;
+mod hash_password;
 mod meta;
+mod watch;
 
 use clap::Arg;
 use clap::Command;
@@ -25,6 +27,8 @@ pub fn app(default_config_path: &'static std::ffi::OsStr) -> Command<'_> {
         )
         .subcommand(auth::subcommand())
         .subcommand(meta::subcommand())
+        .subcommand(watch::subcommand())
+        .subcommand(hash_password::subcommand())
         .subcommand(completions::subcommand())
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -36,3 +40,11 @@ pub fn get_input(prompt: impl Into<String>) -> String {
         .interact_text()
         .unwrap()
 }
+
+pub fn get_password(prompt: impl Into<String>) -> String {
+    dialoguer::Password::with_theme(&dialoguer_theme())
+        .with_prompt(prompt)
+        .with_confirmation("Repeat password", "Error: the passwords don't match.")
+        .interact()
+        .unwrap()
+}
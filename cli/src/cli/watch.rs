@@ -0,0 +1,32 @@
+use super::meta::characteristic_name_arg;
+use super::meta::service_name_arg;
+use clap::Arg;
+use clap::Command;
+
+pub(super) fn subcommand() -> Command<'static> {
+    Command::new("watch")
+        .about("Print accessory events as they happen")
+        .arg(
+            Arg::new("accessory")
+                .help("Show only events of the accessory with this ID or name")
+                .long("accessory")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("room")
+                .help("Show only events of the accessories in this room")
+                .long("room")
+                .takes_value(true),
+        )
+        .arg(service_name_arg(
+            "Show only updates of characteristics of this service",
+        ))
+        .arg(characteristic_name_arg(
+            "Show only updates of this characteristic",
+        ))
+        .arg(
+            Arg::new("json")
+                .help("Print events as JSON, one per line")
+                .long("json"),
+        )
+}
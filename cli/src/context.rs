@@ -2,11 +2,14 @@ use anyhow::Context;
 use houseflow_api::server::Client as ServerClient;
 use houseflow_config::client::Config;
 use houseflow_config::Config as _;
+use houseflow_types::accessory;
 use houseflow_types::accessory::Accessory;
+use houseflow_types::meta::AccessoryInfo;
 use houseflow_types::token::AccessToken;
 use houseflow_types::token::RefreshToken;
 use serde::Deserialize;
 use serde::Serialize;
+use std::str::FromStr;
 use szafka::Szafka;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -89,15 +92,15 @@ impl CommandContext {
             Err(err) => {
                 tracing::debug!("token verify returned error: {}", err);
                 tracing::debug!("cached access token is expired, fetching new one");
-                let raw_fetched_access_token = self
+                let response = self
                     .server_client()?
                     .refresh_token(&refresh_token)
-                    .await??
-                    .access_token;
-                let fetched_access_token = AccessToken::decode_insecure(&raw_fetched_access_token)?;
+                    .await??;
+                let fetched_access_token = AccessToken::decode_insecure(&response.access_token)?;
+                // The server rotates the refresh token, the previous one can't be used anymore
                 let tokens = Tokens {
-                    refresh: tokens.refresh,
-                    access: raw_fetched_access_token,
+                    refresh: response.refresh_token.unwrap_or(tokens.refresh),
+                    access: response.access_token,
                 };
 
                 self.tokens.save(&tokens)?;
@@ -106,9 +109,76 @@ impl CommandContext {
         }
     }
 
+    /// Fetches accessories available to the user and stores them in the cache
+    pub async fn fetch_accessories(&mut self) -> anyhow::Result<Vec<AccessoryInfo>> {
+        let access_token = self.access_token().await?;
+        let accessories = self
+            .server_client()?
+            .get_accessories(&access_token)
+            .await??;
+        let devices = accessories
+            .iter()
+            .map(|info| info.accessory.clone())
+            .collect::<Vec<_>>();
+        self.devices.save(&devices).context("save accessories")?;
+        Ok(accessories)
+    }
+
+    /// Resolves ID of the accessory specified by its ID, name or room.
+    ///
+    /// Cached accessories are looked up first, and fetched again if none of them match.
+    pub async fn accessory_id(&mut self, query: &str) -> anyhow::Result<accessory::ID> {
+        if let Ok(accessory_id) = accessory::ID::from_str(query) {
+            return Ok(accessory_id);
+        }
+        // Missing or outdated cache is fetched again below
+        let devices = self.devices.get().unwrap_or_default();
+        if let Some(accessory_id) = find_accessory(&devices, query)? {
+            return Ok(accessory_id);
+        }
+        tracing::debug!("accessory not found in the cache, fetching accessories");
+        let devices = self
+            .fetch_accessories()
+            .await?
+            .into_iter()
+            .map(|info| info.accessory)
+            .collect::<Vec<_>>();
+        find_accessory(&devices, query)?
+            .ok_or_else(|| anyhow::anyhow!("accessory `{}` not found", query))
+    }
+
     pub fn refresh_token(&mut self) -> anyhow::Result<RefreshToken> {
         let tokens = self.tokens.get().with_context(|| "get tokens")?;
         RefreshToken::decode_insecure(&tokens.refresh)
             .with_context(|| "you may need to log in again using `houseflow auth login`")
     }
 }
+
+/// Finds accessory by its name, or by its room if no accessory has such name
+fn find_accessory(accessories: &[Accessory], query: &str) -> anyhow::Result<Option<accessory::ID>> {
+    let by_name = accessories
+        .iter()
+        .filter(|accessory| accessory.name.eq_ignore_ascii_case(query))
+        .collect::<Vec<_>>();
+    let matching = if by_name.is_empty() {
+        accessories
+            .iter()
+            .filter(|accessory| accessory.room_name.eq_ignore_ascii_case(query))
+            .collect::<Vec<_>>()
+    } else {
+        by_name
+    };
+    match matching.as_slice() {
+        [] => Ok(None),
+        [accessory] => Ok(Some(accessory.id)),
+        accessories => Err(anyhow::anyhow!(
+            "`{}` is ambiguous, it matches accessories: {}",
+            query,
+            accessories
+                .iter()
+                .map(|accessory| format!("{} ({})", accessory.name, accessory.id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
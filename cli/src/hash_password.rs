@@ -0,0 +1,16 @@
+use crate::CommandContext;
+use async_trait::async_trait;
+use houseflow_types::password;
+
+pub struct Command {
+    pub password: String,
+}
+
+#[async_trait]
+impl crate::Command for Command {
+    async fn run(self, _ctx: CommandContext) -> anyhow::Result<()> {
+        let hash = password::hash(&self.password)?;
+        println!("{}", hash);
+        Ok(())
+    }
+}
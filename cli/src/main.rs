@@ -1,11 +1,14 @@
 mod auth;
 mod cli;
 mod context;
+mod hash_password;
 mod meta;
+mod watch;
 
 use anyhow::Context;
 use async_trait::async_trait;
 use cli::get_input;
+use cli::get_password;
 use context::CommandContext;
 use context::Tokens;
 use houseflow_config::client::Config;
@@ -44,7 +47,24 @@ async fn main() -> anyhow::Result<()> {
                 .run(ctx)
                 .await
             }
-            ("logout", _) => auth::logout::Command {}.run(ctx).await,
+            ("register", matches) => {
+                auth::register::Command {
+                    email: get_value(matches, get_input, "email")?,
+                    username: get_value(matches, get_input, "username")?,
+                    code: matches
+                        .value_of("code")
+                        .map(|str| VerificationCode::from_str(str).unwrap()),
+                }
+                .run(ctx)
+                .await
+            }
+            ("logout", matches) => {
+                auth::logout::Command {
+                    all_sessions: matches.is_present("all-sessions"),
+                }
+                .run(ctx)
+                .await
+            }
             ("refresh", _) => auth::refresh::Command {}.run(ctx).await,
             ("status", matches) => {
                 auth::status::Command {
@@ -58,15 +78,65 @@ async fn main() -> anyhow::Result<()> {
         ("meta", matches) => match matches.subcommand().unwrap() {
             ("read", matches) => {
                 meta::read::Command {
-                    accessory_id: get_value(matches, get_input, "accessory-id")?,
+                    accessory: get_value(matches, get_input, "accessory")?,
                     service_name: get_value(matches, get_input, "service-name")?,
                     characteristic_name: get_value(matches, get_input, "characteristic-name")?,
+                    format: get_value(matches, get_input, "format")?,
+                }
+                .run(ctx)
+                .await
+            }
+            ("write", matches) => {
+                let accessory = get_value(matches, get_input, "accessory")?;
+                let service_name = get_value(matches, get_input, "service-name")?;
+                let characteristic = match matches.value_of("json") {
+                    Some(json) => serde_json::from_str(json).context("Json")?,
+                    None => meta::write::parse_characteristic(
+                        get_value(matches, get_input, "characteristic-name")?,
+                        matches.values_of("value").into_iter().flatten(),
+                    )?,
+                };
+                meta::write::Command {
+                    accessory,
+                    service_name,
+                    characteristic,
+                }
+                .run(ctx)
+                .await
+            }
+            ("list", matches) => {
+                meta::list::Command {
+                    format: get_value(matches, get_input, "format")?,
                 }
                 .run(ctx)
                 .await
             }
             _ => unreachable!(),
         },
+        ("watch", matches) => {
+            watch::Command {
+                accessory: matches.value_of("accessory").map(ToString::to_string),
+                room: matches.value_of("room").map(ToString::to_string),
+                service_name: matches
+                    .value_of("service-name")
+                    .map(FromStr::from_str)
+                    .transpose()?,
+                characteristic_name: matches
+                    .value_of("characteristic-name")
+                    .map(FromStr::from_str)
+                    .transpose()?,
+                json: matches.is_present("json"),
+            }
+            .run(ctx)
+            .await
+        }
+        ("hash-password", matches) => {
+            hash_password::Command {
+                password: get_value(matches, get_password, "password")?,
+            }
+            .run(ctx)
+            .await
+        }
         ("completions", matches) => {
             use clap_complete::Shell;
             let mut app = cli::app(DEFAULT_CONFIG_PATH.as_os_str());
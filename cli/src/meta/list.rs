@@ -0,0 +1,52 @@
+use super::print_table;
+use super::Format;
+use crate::CommandContext;
+use async_trait::async_trait;
+
+pub struct Command {
+    pub format: Format,
+}
+
+#[async_trait]
+impl crate::Command for Command {
+    async fn run(self, mut ctx: CommandContext) -> anyhow::Result<()> {
+        let accessories = ctx.fetch_accessories().await?;
+        match self.format {
+            Format::Table => {
+                let rows = accessories
+                    .iter()
+                    .map(|info| -> Result<Vec<String>, serde_json::Error> {
+                        let r#type = serde_json::to_value(&info.accessory.r#type)?;
+                        let r#type = r#type
+                            .as_object()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|(_, value)| value.as_str())
+                            .collect::<Vec<_>>()
+                            .join("/");
+                        let services = info
+                            .services
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        Ok(vec![
+                            info.accessory.id.to_string(),
+                            info.accessory.name.clone(),
+                            info.accessory.room_name.clone(),
+                            r#type,
+                            services,
+                            String::from(if info.connected { "yes" } else { "no" }),
+                        ])
+                    })
+                    .collect::<Result<Vec<_>, serde_json::Error>>()?;
+                print_table(
+                    &["ID", "NAME", "ROOM", "TYPE", "SERVICES", "CONNECTED"],
+                    &rows,
+                );
+            }
+            Format::Json => println!("{}", serde_json::to_string_pretty(&accessories)?),
+        };
+        Ok(())
+    }
+}
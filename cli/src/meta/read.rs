@@ -1,27 +1,44 @@
+use super::print_table;
+use super::Format;
 use crate::CommandContext;
 use async_trait::async_trait;
-use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
 
 pub struct Command {
-    pub accessory_id: accessory::ID,
+    pub accessory: String,
     pub service_name: ServiceName,
     pub characteristic_name: CharacteristicName,
+    pub format: Format,
 }
 
 #[async_trait]
 impl crate::Command for Command {
     async fn run(self, mut ctx: CommandContext) -> anyhow::Result<()> {
+        let accessory_id = ctx.accessory_id(&self.accessory).await?;
+        let access_token = ctx.access_token().await?;
         let characteristic = ctx
             .server_client()?
             .read_characteristics(
-                &self.accessory_id,
+                &access_token,
+                &accessory_id,
                 &self.service_name,
                 &self.characteristic_name,
             )
             .await??;
-        tracing::info!("Characteristic: {:?}", characteristic);
+        match self.format {
+            Format::Table => {
+                let value = serde_json::to_value(&characteristic)?;
+                let rows = value
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .map(|(key, value)| vec![key.to_owned(), value.to_string()])
+                    .collect::<Vec<_>>();
+                print_table(&["FIELD", "VALUE"], &rows);
+            }
+            Format::Json => println!("{}", serde_json::to_string_pretty(&characteristic)?),
+        };
         Ok(())
     }
 }
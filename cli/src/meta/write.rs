@@ -0,0 +1,51 @@
+use crate::CommandContext;
+use anyhow::Context;
+use async_trait::async_trait;
+use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::accessory::characteristics::CharacteristicName;
+use houseflow_types::accessory::services::ServiceName;
+
+pub struct Command {
+    pub accessory: String,
+    pub service_name: ServiceName,
+    pub characteristic: Characteristic,
+}
+
+#[async_trait]
+impl crate::Command for Command {
+    async fn run(self, mut ctx: CommandContext) -> anyhow::Result<()> {
+        let accessory_id = ctx.accessory_id(&self.accessory).await?;
+        let access_token = ctx.access_token().await?;
+        ctx.server_client()?
+            .write_characteristics(
+                &access_token,
+                &accessory_id,
+                &self.service_name,
+                &self.characteristic,
+            )
+            .await??;
+        println!("✔ Characteristic written");
+        Ok(())
+    }
+}
+
+/// Builds characteristic from its name and `key=value` pairs of its fields.
+///
+/// Values that aren't valid JSON, e.g. unquoted strings, are treated as strings.
+pub fn parse_characteristic<'a>(
+    name: CharacteristicName,
+    values: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<Characteristic> {
+    use serde_json::Value;
+
+    let mut object = serde_json::Map::new();
+    object.insert(String::from("name"), Value::String(name.to_string()));
+    for value in values {
+        let (key, value) = value
+            .split_once('=')
+            .with_context(|| format!("expected `key=value`, got `{}`", value))?;
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+        object.insert(key.to_owned(), value);
+    }
+    serde_json::from_value(Value::Object(object)).context("invalid characteristic")
+}
@@ -0,0 +1,183 @@
+use crate::CommandContext;
+use async_trait::async_trait;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::CharacteristicName;
+use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+use houseflow_types::meta::Event;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub struct Command {
+    pub accessory: Option<String>,
+    pub room: Option<String>,
+    pub service_name: Option<ServiceName>,
+    pub characteristic_name: Option<CharacteristicName>,
+    pub json: bool,
+}
+
+struct Filter {
+    accessory_id: Option<accessory::ID>,
+    room: Option<String>,
+    service_name: Option<ServiceName>,
+    characteristic_name: Option<CharacteristicName>,
+}
+
+impl Filter {
+    fn matches(&self, event: &Event, accessories: &HashMap<accessory::ID, Accessory>) -> bool {
+        let (accessory_id, updated) = match event {
+            Event::AccessoryConnected(accessory) => (accessory.id, None),
+            Event::AccessoryDisconnected { accessory_id } => (*accessory_id, None),
+            Event::CharacteristicUpdated(updated) => (updated.accessory_id, Some(updated)),
+            Event::Resync => return false,
+        };
+        if self.accessory_id.map_or(false, |id| id != accessory_id) {
+            return false;
+        }
+        if let Some(room) = &self.room {
+            let in_room = accessories.get(&accessory_id).map_or(false, |accessory| {
+                accessory.room_name.eq_ignore_ascii_case(room)
+            });
+            if !in_room {
+                return false;
+            }
+        }
+        // Connection events don't concern any particular service or characteristic
+        if self.service_name.is_none() && self.characteristic_name.is_none() {
+            return true;
+        }
+        match updated {
+            Some(updated) => {
+                self.service_name
+                    .map_or(true, |service_name| service_name == updated.service_name)
+                    && self
+                        .characteristic_name
+                        .map_or(true, |characteristic_name| {
+                            characteristic_name == CharacteristicName::from(&updated.characteristic)
+                        })
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl crate::Command for Command {
+    async fn run(self, mut ctx: CommandContext) -> anyhow::Result<()> {
+        let filter = Filter {
+            accessory_id: match &self.accessory {
+                Some(accessory) => Some(ctx.accessory_id(accessory).await?),
+                None => None,
+            },
+            room: self.room,
+            service_name: self.service_name,
+            characteristic_name: self.characteristic_name,
+        };
+        // Used to find names and rooms of the accessories referenced only by ID
+        let mut accessories = ctx
+            .fetch_accessories()
+            .await?
+            .into_iter()
+            .map(|info| (info.accessory.id, info.accessory))
+            .collect::<HashMap<_, _>>();
+        let mut last_event_id = None;
+        let mut subscribed = false;
+        loop {
+            // Refreshes the access token if it has expired in the meantime
+            let access_token = ctx.access_token().await?;
+            let result = ctx
+                .server_client()?
+                .events(&access_token, last_event_id)
+                .await;
+            let mut stream = match result {
+                Ok(stream) => stream?,
+                Err(err) if subscribed => {
+                    tracing::warn!("reconnecting failed: {}", err);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            subscribed = true;
+            tracing::debug!("subscribed to the event stream");
+            loop {
+                let received = match stream.next().await {
+                    Ok(Some(received)) => received,
+                    Ok(None) => {
+                        tracing::warn!("event stream closed by the server, reconnecting");
+                        break;
+                    }
+                    Err(err) => {
+                        tracing::warn!("event stream failed: {}, reconnecting", err);
+                        break;
+                    }
+                };
+                last_event_id = received.id.or(last_event_id);
+                if let Event::Resync = received.event {
+                    tracing::warn!("some events have been missed, fetching the accessories again");
+                    accessories = ctx
+                        .fetch_accessories()
+                        .await?
+                        .into_iter()
+                        .map(|info| (info.accessory.id, info.accessory))
+                        .collect();
+                    continue;
+                }
+                if let Event::AccessoryConnected(accessory) = &received.event {
+                    accessories.insert(accessory.id, accessory.clone());
+                }
+                if !filter.matches(&received.event, &accessories) {
+                    continue;
+                }
+                if self.json {
+                    println!("{}", serde_json::to_string(&received.event)?);
+                } else {
+                    print_event(&received.event, &accessories)?;
+                }
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+}
+
+fn print_event(
+    event: &Event,
+    accessories: &HashMap<accessory::ID, Accessory>,
+) -> anyhow::Result<()> {
+    let name = |accessory_id: &accessory::ID| match accessories.get(accessory_id) {
+        Some(accessory) => format!("{} ({})", accessory.name, accessory.room_name),
+        None => accessory_id.to_string(),
+    };
+    let time = chrono::Local::now().format("%H:%M:%S");
+    match event {
+        Event::AccessoryConnected(accessory) => {
+            println!("{} {} connected", time, name(&accessory.id))
+        }
+        Event::AccessoryDisconnected { accessory_id } => {
+            println!("{} {} disconnected", time, name(accessory_id))
+        }
+        Event::CharacteristicUpdated(updated) => {
+            let value = serde_json::to_value(&updated.characteristic)?;
+            let fields = value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(key, _)| key.as_str() != "name")
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!(
+                "{} {} {}/{} {}",
+                time,
+                name(&updated.accessory_id),
+                updated.service_name,
+                CharacteristicName::from(&updated.characteristic),
+                fields
+            );
+        }
+        Event::Resync => println!("{} some events have been missed", time),
+    };
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::defaults;
 use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
 use serde::Deserialize;
 use serde::Serialize;
@@ -35,17 +36,109 @@ pub struct Credentials {
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct Services {
     pub temperature_sensor: Option<services::TemperatureSensor>,
+    pub motion_sensor: Option<services::MotionSensor>,
+    pub contact_sensor: Option<services::ContactSensor>,
+    pub leak_sensor: Option<services::LeakSensor>,
+    pub smoke_sensor: Option<services::SmokeSensor>,
+    pub occupancy_sensor: Option<services::OccupancySensor>,
 }
 
 impl Services {
     pub fn names(&self) -> Vec<ServiceName> {
-        let Self { temperature_sensor } = &self;
         let mut names = vec![];
-        if temperature_sensor.is_some() {
+        if self.temperature_sensor.is_some() {
             names.push(ServiceName::TemperatureSensor);
         }
+        for (service_name, _, _) in self.binary_states() {
+            if !names.contains(&service_name) {
+                names.push(service_name);
+            }
+        }
         names
     }
+
+    /// Binary characteristics of the sensor services, with the commands reporting their state
+    pub fn binary_states(
+        &self,
+    ) -> Vec<(
+        ServiceName,
+        CharacteristicName,
+        &characteristics::BinaryState,
+    )> {
+        let mut states = vec![];
+        if let Some(service) = &self.motion_sensor {
+            push_binary_states(
+                &mut states,
+                ServiceName::MotionSensor,
+                (CharacteristicName::MotionDetected, &service.motion_detected),
+                &service.status_tampered,
+                &service.status_low_battery,
+            );
+        }
+        if let Some(service) = &self.contact_sensor {
+            push_binary_states(
+                &mut states,
+                ServiceName::ContactSensor,
+                (
+                    CharacteristicName::ContactSensorState,
+                    &service.contact_sensor_state,
+                ),
+                &service.status_tampered,
+                &service.status_low_battery,
+            );
+        }
+        if let Some(service) = &self.leak_sensor {
+            push_binary_states(
+                &mut states,
+                ServiceName::LeakSensor,
+                (CharacteristicName::LeakDetected, &service.leak_detected),
+                &service.status_tampered,
+                &service.status_low_battery,
+            );
+        }
+        if let Some(service) = &self.smoke_sensor {
+            push_binary_states(
+                &mut states,
+                ServiceName::SmokeSensor,
+                (CharacteristicName::SmokeDetected, &service.smoke_detected),
+                &service.status_tampered,
+                &service.status_low_battery,
+            );
+        }
+        if let Some(service) = &self.occupancy_sensor {
+            push_binary_states(
+                &mut states,
+                ServiceName::OccupancySensor,
+                (
+                    CharacteristicName::OccupancyDetected,
+                    &service.occupancy_detected,
+                ),
+                &service.status_tampered,
+                &service.status_low_battery,
+            );
+        }
+        states
+    }
+}
+
+fn push_binary_states<'a>(
+    states: &mut Vec<(
+        ServiceName,
+        CharacteristicName,
+        &'a characteristics::BinaryState,
+    )>,
+    service_name: ServiceName,
+    (characteristic_name, state): (CharacteristicName, &'a characteristics::BinaryState),
+    status_tampered: &'a Option<characteristics::BinaryState>,
+    status_low_battery: &'a Option<characteristics::BinaryState>,
+) {
+    states.push((service_name, characteristic_name, state));
+    if let Some(state) = status_tampered {
+        states.push((service_name, CharacteristicName::StatusTampered, state));
+    }
+    if let Some(state) = status_low_battery {
+        states.push((service_name, CharacteristicName::StatusLowBattery, state));
+    }
 }
 
 pub mod services {
@@ -58,6 +151,46 @@ pub mod services {
     pub struct TemperatureSensor {
         pub current_temperature: characteristics::CurrentTemperature,
     }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct MotionSensor {
+        pub motion_detected: characteristics::BinaryState,
+        pub status_tampered: Option<characteristics::BinaryState>,
+        pub status_low_battery: Option<characteristics::BinaryState>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct ContactSensor {
+        pub contact_sensor_state: characteristics::BinaryState,
+        pub status_tampered: Option<characteristics::BinaryState>,
+        pub status_low_battery: Option<characteristics::BinaryState>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct LeakSensor {
+        pub leak_detected: characteristics::BinaryState,
+        pub status_tampered: Option<characteristics::BinaryState>,
+        pub status_low_battery: Option<characteristics::BinaryState>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct SmokeSensor {
+        pub smoke_detected: characteristics::BinaryState,
+        pub status_tampered: Option<characteristics::BinaryState>,
+        pub status_low_battery: Option<characteristics::BinaryState>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct OccupancySensor {
+        pub occupancy_detected: characteristics::BinaryState,
+        pub status_tampered: Option<characteristics::BinaryState>,
+        pub status_low_battery: Option<characteristics::BinaryState>,
+    }
 }
 
 pub mod characteristics {
@@ -75,6 +208,16 @@ pub mod characteristics {
         #[serde_as(as = "DurationSeconds<u64>")]
         pub interval: Duration,
     }
+
+    /// Command printing `1` or `true` when the state is detected, and `0` or `false` otherwise
+    #[serde_with::serde_as]
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct BinaryState {
+        pub command: Command,
+        #[serde_as(as = "DurationSeconds<u64>")]
+        pub interval: Duration,
+    }
 }
 
 impl crate::Config for Config {
@@ -109,6 +252,7 @@ mod tests {
     use super::*;
     use crate::Config as _;
     use houseflow_types::accessory;
+    use std::time::Duration;
     use url::Url;
 
     #[test]
@@ -121,7 +265,20 @@ mod tests {
             hub: Hub {
                 url: Url::parse("wss://example.com:1234/hello/world").unwrap(),
             },
-            services: Services::default(),
+            services: Services {
+                motion_sensor: Some(services::MotionSensor {
+                    motion_detected: characteristics::BinaryState {
+                        command: crate::Command::from("cat /sys/class/gpio/gpio17/value"),
+                        interval: Duration::from_secs(1),
+                    },
+                    status_tampered: Some(characteristics::BinaryState {
+                        command: crate::Command::from("cat /sys/class/gpio/gpio27/value"),
+                        interval: Duration::from_secs(60),
+                    }),
+                    status_low_battery: None,
+                }),
+                ..Default::default()
+            },
         };
 
         std::env::set_var("HUB_PORT", expected.hub.url.port().unwrap().to_string());
@@ -16,3 +16,9 @@ impl Command {
         command
     }
 }
+
+impl From<&str> for Command {
+    fn from(command: &str) -> Self {
+        Self(command.to_string())
+    }
+}
@@ -25,6 +25,64 @@ pub fn server_http_url() -> Url {
     Url::parse(&url).unwrap()
 }
 
+pub fn google_jwks_url() -> Url {
+    Url::parse("https://www.googleapis.com/oauth2/v3/certs").unwrap()
+}
+
+pub const fn request_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(10)
+}
+
+/// Four fifths of the request timeout, so the result of a confirmed write reaches the server before
+/// it gives up on the request
+pub const fn confirmation_timeout() -> std::time::Duration {
+    std::time::Duration::from_millis(request_timeout().as_millis() as u64 * 4 / 5)
+}
+
+pub const fn reconnect_initial_delay() -> std::time::Duration {
+    std::time::Duration::from_secs(1)
+}
+
+pub const fn reconnect_max_delay() -> std::time::Duration {
+    std::time::Duration::from_secs(60)
+}
+
+pub const fn update_batch_interval() -> std::time::Duration {
+    std::time::Duration::from_millis(100)
+}
+
+pub const fn cache_max_age() -> std::time::Duration {
+    std::time::Duration::from_secs(30)
+}
+
+pub const fn webhook_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(10)
+}
+
+#[cfg(any(test, feature = "server"))]
+pub const fn ip_rate_limit() -> crate::server::RateLimit {
+    crate::server::RateLimit {
+        burst: 30,
+        period: std::time::Duration::from_secs(2),
+    }
+}
+
+#[cfg(any(test, feature = "server"))]
+pub const fn account_rate_limit() -> crate::server::RateLimit {
+    crate::server::RateLimit {
+        burst: 5,
+        period: std::time::Duration::from_secs(60),
+    }
+}
+
+pub const fn lockout_attempts() -> u32 {
+    5
+}
+
+pub const fn lockout_duration() -> std::time::Duration {
+    std::time::Duration::from_secs(15 * 60)
+}
+
 pub const fn listen_address() -> IpAddr {
     IpAddr::V4(Ipv4Addr::LOCALHOST)
 }
@@ -53,6 +111,18 @@ pub fn clerk_path() -> std::path::PathBuf {
     data_home().join("clerk.sled")
 }
 
+pub fn audit_path() -> std::path::PathBuf {
+    data_home().join("audit.jsonl")
+}
+
+pub fn sled_store_path() -> std::path::PathBuf {
+    data_home().join("store.sled")
+}
+
+pub fn sqlite_store_path() -> std::path::PathBuf {
+    data_home().join("store.sqlite")
+}
+
 pub fn smtp_port() -> u16 {
     587
 }
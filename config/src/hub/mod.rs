@@ -3,6 +3,8 @@ use houseflow_types::accessory;
 use houseflow_types::hub;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_with::DurationSeconds;
+use std::time::Duration;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -16,6 +18,8 @@ pub struct Config {
     pub providers: Providers,
     #[serde(default)]
     pub controllers: Controllers,
+    #[serde(default)]
+    pub audit: Audit,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -49,6 +53,12 @@ pub struct Accessory {
     #[serde(default)]
     // Only some accessories require this
     pub mac_address: Option<String>,
+    /// Argon2 hash of the accessory password in the PHC string format, required to connect through Hive
+    #[serde(default)]
+    pub password_hash: Option<accessory::PasswordHash>,
+    /// Report writes to the target state of a lock as done only once the accessory reaches it
+    #[serde(default)]
+    pub requires_confirmation: bool,
 }
 
 impl From<Accessory> for accessory::Accessory {
@@ -71,9 +81,15 @@ pub struct Providers {
     pub mijia: Option<MijiaProvider>,
 }
 
+#[serde_with::serde_as]
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
-pub struct HiveProvider {}
+pub struct HiveProvider {
+    /// Time in seconds to wait for the accessory to respond to a request
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "defaults::request_timeout")]
+    pub request_timeout: Duration,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -91,8 +107,12 @@ pub struct Controllers {
 }
 
 pub mod controllers {
+    use crate::defaults;
     use serde::Deserialize;
     use serde::Serialize;
+    use serde_with::DurationMilliSeconds;
+    use serde_with::DurationSeconds;
+    use std::time::Duration;
     use url::Url;
 
     #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -104,11 +124,24 @@ pub mod controllers {
         pub name: String,
     }
 
+    #[serde_with::serde_as]
     #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct Lighthouse {
         pub password: String,
         pub url: Url,
+        /// Time in seconds to wait before the first reconnect attempt, doubled on each failed attempt
+        #[serde_as(as = "DurationSeconds<u64>")]
+        #[serde(default = "defaults::reconnect_initial_delay")]
+        pub reconnect_initial_delay: Duration,
+        /// Maximum time in seconds to wait between reconnect attempts
+        #[serde_as(as = "DurationSeconds<u64>")]
+        #[serde(default = "defaults::reconnect_max_delay")]
+        pub reconnect_max_delay: Duration,
+        /// Time in milliseconds to collect characteristic updates before sending them in a single batch
+        #[serde_as(as = "DurationMilliSeconds<u64>")]
+        #[serde(default = "defaults::update_batch_interval")]
+        pub update_batch_interval: Duration,
     }
 
     #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -116,6 +149,21 @@ pub mod controllers {
     pub struct Meta {}
 }
 
+/// Audit trail of writes to the target state of locks
+#[serde_with::serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Audit {
+    /// Path to the file to which the writes are appended, one JSON object per line
+    #[serde(default = "defaults::audit_path")]
+    pub path: std::path::PathBuf,
+    /// Time in seconds within which a write to a lock must complete, including the confirmation of
+    /// reaching the target state, below the server's request timeout
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "defaults::confirmation_timeout")]
+    pub confirmation_timeout: Duration,
+}
+
 impl crate::Config for Config {
     const DEFAULT_TOML: &'static str = include_str!("default.toml");
 
@@ -126,6 +174,15 @@ impl crate::Config for Config {
     }
 }
 
+impl Default for Audit {
+    fn default() -> Self {
+        Self {
+            path: defaults::audit_path(),
+            confirmation_timeout: defaults::confirmation_timeout(),
+        }
+    }
+}
+
 impl Default for Network {
     fn default() -> Self {
         Self {
@@ -154,18 +211,46 @@ mod tests {
                 address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
                 port: 1234,
             },
-            accessories: vec![Accessory {
-                id: accessory::ID::parse_str("37c6a8bd-264c-4653-a641-c9b574207be5").unwrap(),
-                name: String::from("Thermometer"),
-                r#type: accessory::Type::XiaomiMijia(
-                    accessory::manufacturers::XiaomiMijia::HygroThermometer,
-                ),
-                mac_address: Some(String::from("A4:C1:38:EF:77:51")),
-                room_name: "Bedroom".to_string(),
-            }],
+            accessories: vec![
+                Accessory {
+                    id: accessory::ID::parse_str("37c6a8bd-264c-4653-a641-c9b574207be5").unwrap(),
+                    name: String::from("Thermometer"),
+                    r#type: accessory::Type::XiaomiMijia(
+                        accessory::manufacturers::XiaomiMijia::HygroThermometer,
+                    ),
+                    mac_address: Some(String::from("A4:C1:38:EF:77:51")),
+                    password_hash: None,
+                    requires_confirmation: false,
+                    room_name: "Bedroom".to_string(),
+                },
+                Accessory {
+                    id: accessory::ID::parse_str("8e9a3d7c-1b55-4f0e-a3f4-6c2d0b1e5a77").unwrap(),
+                    name: String::from("Gate"),
+                    r#type: accessory::Type::Houseflow(accessory::manufacturers::Houseflow::Gate),
+                    mac_address: None,
+                    password_hash: Some(String::from(
+                        "$argon2id$v=19$m=4096,t=3,p=1$faqaqROTdIOIA7oIKRHo4w$hPo2Z6mBrHw/WybGmSlkaZtTKxzRzRetCj2WBJQ8JJw",
+                    )),
+                    requires_confirmation: false,
+                    room_name: "Garden".to_string(),
+                },
+                Accessory {
+                    id: accessory::ID::parse_str("d3f1c0a2-5b7e-4c1d-9e8f-2a6b4c8d0e13").unwrap(),
+                    name: String::from("Front door"),
+                    r#type: accessory::Type::Houseflow(accessory::manufacturers::Houseflow::Lock),
+                    mac_address: None,
+                    password_hash: Some(String::from(
+                        "$argon2id$v=19$m=4096,t=3,p=1$faqaqROTdIOIA7oIKRHo4w$hPo2Z6mBrHw/WybGmSlkaZtTKxzRzRetCj2WBJQ8JJw",
+                    )),
+                    requires_confirmation: true,
+                    room_name: "Hall".to_string(),
+                },
+            ],
             providers: Providers {
                 mijia: Some(MijiaProvider {}),
-                hive: Some(HiveProvider {}),
+                hive: Some(HiveProvider {
+                    request_timeout: Duration::from_secs(5),
+                }),
             },
             controllers: Controllers {
                 hap: Some(controllers::Hap {
@@ -175,9 +260,16 @@ mod tests {
                 lighthouse: Some(controllers::Lighthouse {
                     url: Url::parse("http://lighthouse").unwrap(),
                     password: String::from("hard-password"),
+                    reconnect_initial_delay: Duration::from_secs(2),
+                    reconnect_max_delay: Duration::from_secs(120),
+                    update_batch_interval: Duration::from_millis(250),
                 }),
                 meta: Some(controllers::Meta {}),
             },
+            audit: Audit {
+                path: std::path::PathBuf::from("/var/lib/houseflow/audit.jsonl"),
+                confirmation_timeout: Duration::from_secs(6),
+            },
         };
 
         println!(
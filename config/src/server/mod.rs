@@ -1,6 +1,8 @@
 use crate::defaults;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_with::DurationSeconds;
+use std::time::Duration;
 
 use houseflow_types::permission;
 use houseflow_types::structure;
@@ -31,6 +33,12 @@ pub struct Config {
     /// Configuration for login options
     #[serde(default)]
     pub logins: Logins,
+    /// Storage of users, structures, permissions and hubs
+    #[serde(default)]
+    pub store: Store,
+    /// Limits of requests to the authentication endpoints
+    #[serde(default)]
+    pub rate_limits: RateLimits,
     /// Structures
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub structures: Vec<Structure>,
@@ -88,12 +96,17 @@ pub struct Mailers {
     #[serde(default)]
     pub smtp: Option<mailers::Smtp>,
     #[serde(default)]
+    pub webhook: Option<mailers::Webhook>,
+    #[serde(default)]
     pub dummy: Option<mailers::Dummy>,
 }
 
 pub mod mailers {
+    use crate::defaults;
     use serde::Deserialize;
     use serde::Serialize;
+    use serde_with::DurationSeconds;
+    use std::time::Duration;
     use url::Url;
 
     #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -103,6 +116,18 @@ pub mod mailers {
         pub from: String,
     }
 
+    #[serde_with::serde_as]
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct Webhook {
+        /// URL to which the verification codes are POSTed as JSON
+        pub url: Url,
+        /// Time in seconds after which a request to the webhook is abandoned
+        #[serde_as(as = "DurationSeconds<u64>")]
+        #[serde(default = "defaults::webhook_timeout")]
+        pub timeout: Duration,
+    }
+
     #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct Dummy {}
@@ -112,15 +137,48 @@ pub mod mailers {
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct Controllers {
     pub meta: Option<controllers::Meta>,
+    pub google_home: Option<controllers::GoogleHome>,
 }
 
 pub mod controllers {
+    use crate::defaults;
     use serde::Deserialize;
     use serde::Serialize;
+    use serde_with::DurationSeconds;
+    use std::time::Duration;
+
+    #[serde_with::serde_as]
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    pub struct Meta {
+        /// Allow only managers of the structure to write characteristics.
+        #[serde(default)]
+        pub write_requires_manager: bool,
+        /// Time in seconds for which the last known value of a characteristic is served from the cache.
+        #[serde_as(as = "DurationSeconds<u64>")]
+        #[serde(default = "defaults::cache_max_age")]
+        pub cache_max_age: Duration,
+    }
 
-    #[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+    impl Default for Meta {
+        fn default() -> Self {
+            Self {
+                write_requires_manager: false,
+                cache_max_age: defaults::cache_max_age(),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
-    pub struct Meta {}
+    pub struct GoogleHome {
+        /// OAuth2 Client ID, set in Actions Console -> Account Linking -> Client ID.
+        pub client_id: String,
+        /// OAuth2 Client Secret, set in Actions Console -> Account Linking -> Client Secret.
+        pub client_secret: String,
+        /// Project ID set during creation of the project in Actions Console.
+        pub project_id: String,
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -130,17 +188,34 @@ pub struct Providers {
 }
 
 pub mod providers {
+    use crate::defaults;
     use houseflow_types::hub;
     use houseflow_types::structure;
     use serde::Deserialize;
     use serde::Serialize;
+    use serde_with::DurationSeconds;
+    use std::time::Duration;
 
-    #[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+    #[serde_with::serde_as]
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "kebab-case", deny_unknown_fields)]
     pub struct Lighthouse {
         /// Hubs
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         pub hubs: Vec<LighthouseHub>,
+        /// Time in seconds to wait for the hub to respond to a request
+        #[serde_as(as = "DurationSeconds<u64>")]
+        #[serde(default = "defaults::request_timeout")]
+        pub request_timeout: Duration,
+    }
+
+    impl Default for Lighthouse {
+        fn default() -> Self {
+            Self {
+                hubs: Default::default(),
+                request_timeout: defaults::request_timeout(),
+            }
+        }
     }
 
     #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -148,6 +223,7 @@ pub mod providers {
     pub struct LighthouseHub {
         pub id: hub::ID,
         pub name: String,
+        /// Argon2 hash of the hub password in the PHC string format, can be generated with `houseflow hash-password`
         pub password_hash: String,
         pub structure_id: structure::ID,
     }
@@ -166,6 +242,90 @@ pub struct Logins {
 pub struct GoogleLogin {
     /// OAuth2 Client ID identifying your service to Google.
     pub client_id: String,
+    /// Location of the JSON Web Key Set used to verify ID tokens issued by Google.
+    #[serde(default)]
+    pub jwks: JwksSource,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JwksSource {
+    /// JWKS is fetched from the URL, and cached for the `max-age` of the response
+    Url(Url),
+    /// JWKS is read from the local file, and read again once a token is signed with an unknown key
+    Path(std::path::PathBuf),
+}
+
+impl Default for JwksSource {
+    fn default() -> Self {
+        Self::Url(defaults::google_jwks_url())
+    }
+}
+
+/// Database of users, structures, permissions and hubs.
+///
+/// Entries defined in the configuration are added to it on startup.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Store {
+    Sled {
+        #[serde(default = "defaults::sled_store_path")]
+        path: std::path::PathBuf,
+    },
+    Sqlite {
+        #[serde(default = "defaults::sqlite_store_path")]
+        path: std::path::PathBuf,
+    },
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::Sled {
+            path: defaults::sled_store_path(),
+        }
+    }
+}
+
+#[serde_with::serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RateLimits {
+    /// Requests allowed from a single IP address
+    #[serde(default = "defaults::ip_rate_limit")]
+    pub ip: RateLimit,
+    /// Requests allowed for a single account, e.g. to send a verification code
+    #[serde(default = "defaults::account_rate_limit")]
+    pub account: RateLimit,
+    /// Number of invalid verification codes after which the account gets locked
+    #[serde(default = "defaults::lockout_attempts")]
+    pub lockout_attempts: u32,
+    /// Time in seconds for which the account stays locked
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "defaults::lockout_duration")]
+    pub lockout_duration: Duration,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            ip: defaults::ip_rate_limit(),
+            account: defaults::account_rate_limit(),
+            lockout_attempts: defaults::lockout_attempts(),
+            lockout_duration: defaults::lockout_duration(),
+        }
+    }
+}
+
+/// Token bucket, allows a burst of requests and then one request per period
+#[serde_with::serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RateLimit {
+    /// Maximum number of requests made at once
+    pub burst: u32,
+    /// Time in seconds after which one more request is allowed
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period: Duration,
 }
 
 impl crate::Config for Config {
@@ -246,41 +406,6 @@ impl Default for Network {
 }
 
 impl Config {
-    pub fn get_user(&self, user_id: &user::ID) -> Option<&User> {
-        self.users.iter().find(|user| user.id == *user_id)
-    }
-
-    pub fn get_user_by_email(&self, user_email: &lettre::Address) -> Option<&User> {
-        self.users.iter().find(|user| user.email == *user_email)
-    }
-
-    pub fn get_structure(&self, id: &structure::ID) -> Option<&Structure> {
-        self.structures.iter().find(|structure| structure.id == *id)
-    }
-
-    pub fn get_permission(
-        &self,
-        structure_id: &structure::ID,
-        user_id: &user::ID,
-    ) -> Option<&Permission> {
-        self.permissions.iter().find(|permission| {
-            permission.structure_id == *structure_id && permission.user_id == *user_id
-        })
-    }
-
-    pub fn get_user_structures(&self, user_id: &user::ID) -> Vec<&Structure> {
-        self.permissions
-            .iter()
-            .filter(|permission| permission.user_id == *user_id)
-            .map(|permission| {
-                self
-                    .get_structure(&permission.structure_id)
-                    .unwrap_or_else(|| panic!(
-                        "dangling permission reference to a structure with id = {}, and user id = {}", permission.structure_id, user_id
-                    ))
-            }).collect()
-    }
-
     pub fn get_base_url(&self) -> Url {
         self.network.base_url.clone().unwrap_or_else(|| {
             let (scheme, address, port) = if let Some(tls) = &self.tls {
@@ -337,28 +462,61 @@ mod tests {
                     .unwrap(),
                     from: String::from("houseflow@gbaranski.com"),
                 }),
+                webhook: Some(mailers::Webhook {
+                    url: Url::from_str("https://chat.gbaranski.com/hooks/houseflow").unwrap(),
+                    timeout: std::time::Duration::from_secs(5),
+                }),
                 dummy: Some(mailers::Dummy {}),
             },
             controllers: Controllers {
-                meta: Some(controllers::Meta {}),
+                meta: Some(controllers::Meta {
+                    write_requires_manager: true,
+                    cache_max_age: std::time::Duration::from_secs(60),
+                }),
+                google_home: Some(controllers::GoogleHome {
+                    client_id: String::from("google-home-client-id"),
+                    client_secret: String::from("google-home-client-secret"),
+                    project_id: String::from("google-home-project-id"),
+                }),
             },
             providers: Providers {
                 lighthouse: Some(providers::Lighthouse {
                     hubs: [providers::LighthouseHub {
                         id: hub::ID::from_str("c3b846ed-74f1-4fd9-90d2-e6c2669dfaa6").unwrap(),
                         name: String::from("Simple Hub"),
-                        password_hash: String::from("some-password-hash"),
+                        password_hash: String::from(
+                            "$argon2id$v=19$m=4096,t=3,p=1$/EGN7eUQC3jGMdZCfn8AkQ$+JZNeJUadhUMcJJ4yG9457ZNoA0OnjzERF9qkzTBFJg"
+                        ),
                         structure_id: structure::ID::from_str("bd7feab5033940e296ed7fcdc700ba65")
                             .unwrap(),
                     }]
                     .to_vec(),
+                    request_timeout: std::time::Duration::from_secs(5),
                 }),
             },
             logins: Logins {
                 google: Some(GoogleLogin {
                     client_id: String::from("google-login-client-id"),
+                    jwks: JwksSource::Path(std::path::PathBuf::from(
+                        "/etc/houseflow/google-jwks.json",
+                    )),
                 }),
             },
+            store: Store::Sqlite {
+                path: std::path::PathBuf::from("/var/lib/houseflow/store.sqlite"),
+            },
+            rate_limits: RateLimits {
+                ip: RateLimit {
+                    burst: 10,
+                    period: std::time::Duration::from_secs(1),
+                },
+                account: RateLimit {
+                    burst: 3,
+                    period: std::time::Duration::from_secs(120),
+                },
+                lockout_attempts: 10,
+                lockout_duration: std::time::Duration::from_secs(3600),
+            },
             structures: [Structure {
                 id: structure::ID::from_str("bd7feab5033940e296ed7fcdc700ba65").unwrap(),
                 name: String::from("Zukago"),
@@ -66,3 +66,32 @@ pub struct OnOff {
 pub struct OpenClose {
     pub open_percent: u8,
 }
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SetFanSpeed {
+    /// Requested speed setting of the fan, one of the available fan speeds.
+    Setting {
+        #[serde(rename = "fanSpeed")]
+        fan_speed: String,
+    },
+    /// Requested speed setting percentage.
+    Percent {
+        #[serde(rename = "fanSpeedPercent")]
+        fan_speed_percent: u8,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThermostatTemperatureSetpoint {
+    /// Target temperature setpoint in degrees Celsius.
+    pub thermostat_temperature_setpoint: f64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThermostatSetMode {
+    /// One of the modes from the availableThermostatModes attribute.
+    pub thermostat_mode: String,
+}
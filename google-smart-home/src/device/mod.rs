@@ -21,4 +21,10 @@ pub enum Command {
     OnOff(commands::OnOff),
     #[serde(rename = "action.devices.commands.OpenClose")]
     OpenClose(commands::OpenClose),
+    #[serde(rename = "action.devices.commands.SetFanSpeed")]
+    SetFanSpeed(commands::SetFanSpeed),
+    #[serde(rename = "action.devices.commands.ThermostatTemperatureSetpoint")]
+    ThermostatTemperatureSetpoint(commands::ThermostatTemperatureSetpoint),
+    #[serde(rename = "action.devices.commands.ThermostatSetMode")]
+    ThermostatSetMode(commands::ThermostatSetMode),
 }
@@ -86,6 +86,18 @@ pub mod response {
         #[serde(skip_serializing_if = "Option::is_none")]
         pub color: Option<Color>,
 
+        // States for FanSpeed trait.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub current_fan_speed_percent: Option<u8>,
+
+        // States for OpenClose trait.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub open_percent: Option<u8>,
+
+        // States for SensorState trait.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub current_sensor_state_data: Option<Vec<CurrentSensorState>>,
+
         // States for TemperatureSetting trait.
         #[serde(skip_serializing_if = "Option::is_none")]
         pub active_thermostat_mode: Option<String>,
@@ -117,6 +129,19 @@ pub mod response {
         },
     }
 
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct CurrentSensorState {
+        /// Sensor type, as in the supported sensor states of the SYNC attributes.
+        pub name: String,
+        /// Current descriptive state of the sensor.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub current_sensor_state: Option<String>,
+        /// Current numeric value of the sensor.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub raw_value: Option<f64>,
+    }
+
     /// Result of the query operation.
     #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
     #[repr(u8)]
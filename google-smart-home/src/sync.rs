@@ -91,6 +91,24 @@ pub mod response {
         #[serde(skip_serializing_if = "Option::is_none")]
         pub command_only_color_setting: Option<bool>,
 
+        // Attributes for FanSpeed trait.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub supports_fan_speed_percent: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub reversible: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub command_only_fan_speed: Option<bool>,
+
+        // Attributes for OpenClose trait.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub discrete_only_open_close: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub query_only_open_close: Option<bool>,
+
+        // Attributes for SensorState trait.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sensor_states_supported: Option<Vec<SensorStateSupported>>,
+
         // Attributes for TemperatureSetting trait.
         #[serde(skip_serializing_if = "Option::is_none")]
         pub available_thermostat_modes: Option<Vec<String>>,
@@ -120,6 +138,23 @@ pub mod response {
         Hsv,
     }
 
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SensorStateSupported {
+        /// Supported sensor type, e.g. "SmokeLevel".
+        pub name: String,
+        /// Sensor states that can be reported.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub descriptive_capabilities: Option<SensorStateDescriptiveCapabilities>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SensorStateDescriptiveCapabilities {
+        /// List of the possible descriptive states for the sensor.
+        pub available_states: Vec<String>,
+    }
+
     #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct ThermostatTemperatureRange {
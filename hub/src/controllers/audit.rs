@@ -0,0 +1,351 @@
+use crate::providers::ProviderExt;
+use crate::ConfiguredAccessories;
+use chrono::DateTime;
+use chrono::Utc;
+use houseflow_config::hub::Audit as Config;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics;
+use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::accessory::characteristics::CharacteristicName;
+use houseflow_types::accessory::characteristics::LockState;
+use houseflow_types::accessory::characteristics::LockTarget;
+use houseflow_types::accessory::services::ServiceName;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::time::Instant;
+
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Controller through which the write has been requested
+#[derive(Debug, Clone, Copy, Serialize, strum::Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Source {
+    Hap,
+    Meta,
+    Lighthouse,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Entry<'a> {
+    timestamp: DateTime<Utc>,
+    source: Source,
+    accessory_id: accessory::ID,
+    service_name: ServiceName,
+    characteristic: &'a Characteristic,
+    result: accessory::Result<()>,
+}
+
+/// Writes the characteristic through the provider. Writes to the target state of a lock are
+/// recorded in the audit trail, and if the accessory requires confirmation, reported as done only
+/// once the lock reaches the target state. Both the write and the confirmation must complete within
+/// the confirmation timeout.
+pub async fn write_characteristic(
+    provider: &(impl ProviderExt + Sync),
+    configured_accessories: &ConfiguredAccessories,
+    config: &Config,
+    source: Source,
+    accessory_id: accessory::ID,
+    service_name: ServiceName,
+    characteristic: Characteristic,
+) -> Result<(), accessory::Error> {
+    let target_state = match &characteristic {
+        Characteristic::LockTargetState(characteristics::LockTargetState { state }) => *state,
+        _ => {
+            return provider
+                .write_characteristic(accessory_id, service_name, characteristic)
+                .await
+        }
+    };
+    let requires_confirmation = configured_accessories
+        .load()
+        .iter()
+        .find(|accessory| accessory.id == accessory_id)
+        .map(|accessory| accessory.requires_confirmation)
+        .unwrap_or(false);
+    let deadline = Instant::now() + config.confirmation_timeout;
+    let write = async {
+        provider
+            .write_characteristic(accessory_id, service_name, characteristic.clone())
+            .await?;
+        if requires_confirmation {
+            wait_for_lock_state(provider, accessory_id, service_name, target_state).await?;
+        }
+        Ok(())
+    };
+    let result = tokio::time::timeout_at(deadline, write)
+        .await
+        .unwrap_or(Err(accessory::Error::Timeout));
+    let entry = Entry {
+        timestamp: Utc::now(),
+        source,
+        accessory_id,
+        service_name,
+        characteristic: &characteristic,
+        result: result.clone().into(),
+    };
+    tracing::info!(%source, %accessory_id, ?target_state, ?result, "Lock target state written");
+    if let Err(err) = record(&config.path, &entry).await {
+        tracing::error!("recording audit trail entry: {}", err);
+    }
+    result
+}
+
+/// Polls the current state of the lock until it reaches the target state
+async fn wait_for_lock_state(
+    provider: &(impl ProviderExt + Sync),
+    accessory_id: accessory::ID,
+    service_name: ServiceName,
+    target_state: LockTarget,
+) -> Result<(), accessory::Error> {
+    loop {
+        let characteristic = provider
+            .read_characteristic(
+                accessory_id,
+                service_name,
+                CharacteristicName::LockCurrentState,
+            )
+            .await?;
+        match characteristic {
+            Characteristic::LockCurrentState(characteristics::LockCurrentState { state })
+                if state == LockState::from(target_state) =>
+            {
+                return Ok(());
+            }
+            _ => tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// Appends the entry to the audit trail file, one JSON object per line
+async fn record(path: &Path, entry: &Entry<'_>) -> Result<(), anyhow::Error> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&line).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers;
+    use acu::MasterExt;
+    use arc_swap::ArcSwap;
+    use houseflow_config::hub::Accessory;
+    use std::sync::Arc;
+
+    /// Provider of a lock, which reaches the target state only if it is `responsive`. Writes take
+    /// `write_duration` to complete.
+    async fn get_provider(responsive: bool, write_duration: Duration) -> providers::MasterHandle {
+        let (sender, mut receiver) = acu::channel(providers::Name::Hive);
+        tokio::spawn(async move {
+            let mut current_state = LockState::Unsecured;
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    providers::Message::IsConnected { respond_to, .. } => {
+                        respond_to.send(true).ok();
+                    }
+                    providers::Message::WriteCharacteristic {
+                        characteristic,
+                        respond_to,
+                        ..
+                    } => {
+                        if let Characteristic::LockTargetState(characteristics::LockTargetState {
+                            state,
+                        }) = characteristic
+                        {
+                            if responsive {
+                                current_state = state.into();
+                            }
+                        }
+                        tokio::time::sleep(write_duration).await;
+                        respond_to.send(Ok(())).ok();
+                    }
+                    providers::Message::ReadCharacteristic { respond_to, .. } => {
+                        let characteristic =
+                            Characteristic::LockCurrentState(characteristics::LockCurrentState {
+                                state: current_state,
+                            });
+                        respond_to.send(Ok(characteristic)).ok();
+                    }
+                    providers::Message::GetAccessoryConfiguration { respond_to, .. } => {
+                        respond_to.send(None).ok();
+                    }
+                }
+            }
+        });
+        let provider = providers::MasterHandle::new();
+        provider.push(providers::Handle { sender }).await;
+        provider
+    }
+
+    fn get_configured_accessories(
+        accessory_id: accessory::ID,
+        requires_confirmation: bool,
+    ) -> ConfiguredAccessories {
+        let accessory = Accessory {
+            id: accessory_id,
+            name: String::from("Front door"),
+            room_name: String::from("Hall"),
+            r#type: accessory::Type::Houseflow(accessory::manufacturers::Houseflow::Lock),
+            mac_address: None,
+            password_hash: None,
+            requires_confirmation,
+        };
+        Arc::new(ArcSwap::from(Arc::new(vec![accessory])))
+    }
+
+    fn get_config() -> Config {
+        Config {
+            path: std::env::temp_dir().join(format!(
+                "houseflow-audit-test-{}.jsonl",
+                rand::random::<u32>()
+            )),
+            confirmation_timeout: Duration::from_millis(100),
+        }
+    }
+
+    async fn read_entries(config: &Config) -> Vec<serde_json::Value> {
+        let content = tokio::fs::read_to_string(&config.path)
+            .await
+            .unwrap_or_default();
+        content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn lock_target_state(state: LockTarget) -> Characteristic {
+        Characteristic::LockTargetState(characteristics::LockTargetState { state })
+    }
+
+    #[tokio::test]
+    async fn lock_target_state_recorded() {
+        let accessory_id = accessory::ID::new_v4();
+        let provider = get_provider(true, Duration::ZERO).await;
+        let config = get_config();
+        let characteristic = lock_target_state(LockTarget::Secured);
+        let result = write_characteristic(
+            &provider,
+            &get_configured_accessories(accessory_id, false),
+            &config,
+            Source::Meta,
+            accessory_id,
+            ServiceName::LockMechanism,
+            characteristic.clone(),
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+
+        let entries = read_entries(&config).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["source"], "meta");
+        assert_eq!(entries[0]["accessory-id"], accessory_id.to_string());
+        assert_eq!(
+            entries[0]["characteristic"],
+            serde_json::to_value(&characteristic).unwrap()
+        );
+        assert_eq!(entries[0]["result"]["status"], "success");
+    }
+
+    #[tokio::test]
+    async fn other_characteristics_not_recorded() {
+        let accessory_id = accessory::ID::new_v4();
+        let provider = get_provider(true, Duration::ZERO).await;
+        let config = get_config();
+        let result = write_characteristic(
+            &provider,
+            &get_configured_accessories(accessory_id, true),
+            &config,
+            Source::Lighthouse,
+            accessory_id,
+            ServiceName::LockMechanism,
+            Characteristic::LockCurrentState(characteristics::LockCurrentState {
+                state: LockState::Secured,
+            }),
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+        assert!(read_entries(&config).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn confirmed() {
+        let accessory_id = accessory::ID::new_v4();
+        let provider = get_provider(true, Duration::ZERO).await;
+        let config = get_config();
+        let result = write_characteristic(
+            &provider,
+            &get_configured_accessories(accessory_id, true),
+            &config,
+            Source::Lighthouse,
+            accessory_id,
+            ServiceName::LockMechanism,
+            lock_target_state(LockTarget::Secured),
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+
+        let entries = read_entries(&config).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["source"], "lighthouse");
+        assert_eq!(entries[0]["result"]["status"], "success");
+    }
+
+    #[tokio::test]
+    async fn confirmation_timeout() {
+        let accessory_id = accessory::ID::new_v4();
+        let provider = get_provider(false, Duration::ZERO).await;
+        let config = get_config();
+        let result = write_characteristic(
+            &provider,
+            &get_configured_accessories(accessory_id, true),
+            &config,
+            Source::Lighthouse,
+            accessory_id,
+            ServiceName::LockMechanism,
+            lock_target_state(LockTarget::Secured),
+        )
+        .await;
+        assert_eq!(result, Err(accessory::Error::Timeout));
+
+        let entries = read_entries(&config).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0]["result"],
+            serde_json::to_value(accessory::Result::<()>::Err(accessory::Error::Timeout)).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn write_timeout() {
+        let accessory_id = accessory::ID::new_v4();
+        let config = get_config();
+        // The write completes after the timeout, so does the confirmation
+        let provider = get_provider(true, config.confirmation_timeout * 2).await;
+        let write = write_characteristic(
+            &provider,
+            &get_configured_accessories(accessory_id, true),
+            &config,
+            Source::Hap,
+            accessory_id,
+            ServiceName::LockMechanism,
+            lock_target_state(LockTarget::Secured),
+        );
+        let result = tokio::time::timeout(config.confirmation_timeout * 3 / 2, write)
+            .await
+            .unwrap();
+        assert_eq!(result, Err(accessory::Error::Timeout));
+    }
+}
@@ -1,13 +1,19 @@
 pub use super::Handle;
 
+use super::audit;
 use super::Message;
 use super::Name;
 use crate::providers;
 use crate::providers::ProviderExt;
+use crate::ConfiguredAccessories;
 use futures::lock::Mutex;
 use futures::FutureExt;
 use hap::accessory::garage_door_opener::GarageDoorOpenerAccessory;
 use hap::accessory::lightbulb::LightbulbAccessory;
+use hap::accessory::lock::LockAccessory;
+use hap::accessory::outlet::OutletAccessory;
+use hap::accessory::switch::SwitchAccessory;
+use hap::accessory::thermostat::ThermostatAccessory;
 use hap::accessory::AccessoryCategory;
 use hap::accessory::AccessoryInformation;
 use hap::accessory::HapAccessory;
@@ -15,18 +21,33 @@ use hap::characteristic::AsyncCharacteristicCallbacks;
 use hap::characteristic::CharacteristicCallbacks;
 use hap::server::IpServer;
 use hap::server::Server;
+use hap::service::accessory_information::AccessoryInformationService;
 use hap::service::battery::BatteryService;
+use hap::service::contact_sensor::ContactSensorService;
+use hap::service::fan::FanService;
 use hap::service::humidity_sensor::HumiditySensorService;
+use hap::service::leak_sensor::LeakSensorService;
+use hap::service::motion_sensor::MotionSensorService;
+use hap::service::occupancy_sensor::OccupancySensorService;
+use hap::service::smoke_sensor::SmokeSensorService;
 use hap::service::temperature_sensor::TemperatureSensorService;
+use hap::service::HapService;
 use hap::storage::FileStorage;
 use hap::storage::Storage;
 use hap::HapType;
 use hap::MacAddress;
 use hap::Pin;
 use houseflow_config::hub::controllers::Hap as HapConfig;
+use houseflow_config::hub::Audit as AuditConfig;
 use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics;
 use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::accessory::characteristics::Direction;
+use houseflow_types::accessory::characteristics::HeatingCoolingMode;
+use houseflow_types::accessory::characteristics::HeatingCoolingState;
+use houseflow_types::accessory::characteristics::LockState;
+use houseflow_types::accessory::characteristics::LockTarget;
+use houseflow_types::accessory::characteristics::TemperatureUnits;
 use houseflow_types::accessory::services::ServiceName;
 use mac_address::get_mac_address;
 use serde::ser::SerializeStruct;
@@ -39,6 +60,8 @@ pub struct HapController<P: ProviderExt + Clone> {
     receiver: acu::Receiver<Message, Name>,
     ip_server: IpServer,
     provider: P,
+    configured_accessories: ConfiguredAccessories,
+    audit_config: AuditConfig,
     accessory_pointers: HashMap<accessory::ID, Arc<Mutex<Box<dyn HapAccessory>>>>,
     accessory_instance_id: u64,
 }
@@ -46,6 +69,8 @@ pub struct HapController<P: ProviderExt + Clone> {
 pub async fn new(
     config: HapConfig,
     provider: providers::MasterHandle,
+    configured_accessories: ConfiguredAccessories,
+    audit_config: AuditConfig,
 ) -> Result<Handle, anyhow::Error> {
     let (sender, receiver) = acu::channel(Name::Hap);
     let mut storage =
@@ -82,6 +107,8 @@ pub async fn new(
         receiver,
         ip_server,
         provider,
+        configured_accessories,
+        audit_config,
         accessory_pointers: Default::default(),
         accessory_instance_id: 1,
     };
@@ -229,8 +256,7 @@ impl<P: ProviderExt + Clone + Send + Sync + 'static> HapController<P> {
                                                             },
                                             });
 
-                                            provider.write_characteristic(accessory_id, service_name, characteristic).await.unwrap();
-                                            Ok(())
+                                            write_characteristic(&provider, accessory_id, service_name, characteristic).await
                                         }
                                         .boxed()
                                     }));
@@ -258,37 +284,481 @@ impl<P: ProviderExt + Clone + Send + Sync + 'static> HapController<P> {
                                         software_revision: None,
                                     },
                                 )?;
+                                let accessory_id = accessory.id;
+                                let provider = self.provider.clone();
+                                let power_state = &mut lightbulb.lightbulb.power_state;
+                                power_state.on_read(Some(|| Ok(None)));
+                                power_state.on_update_async(Some(move |current: bool, new: bool| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("lightbulb power state characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::On(characteristics::On { on: new });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Light, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                let provider = self.provider.clone();
                                 let brightness = lightbulb.lightbulb.brightness.as_mut().unwrap();
                                 brightness.on_read(Some(|| Ok(None)));
+                                brightness.on_update_async(Some(move |current: i32, new: i32| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("lightbulb brightness characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::Brightness(characteristics::Brightness {
+                                            percentage: new as u8,
+                                        });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Light, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
 
                                 let provider = self.provider.clone();
+                                let hue = lightbulb.lightbulb.hue.as_mut().unwrap();
+                                hue.on_read(Some(|| Ok(None)));
+                                hue.on_update_async(Some(move |current: f32, new: f32| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!(
+                                            "lightbulb hue characteristic updated from {} to {}",
+                                            current,
+                                            new
+                                        );
+                                        let characteristic =
+                                            Characteristic::Hue(characteristics::Hue { hue: new });
+
+                                        write_characteristic(
+                                            &provider,
+                                            accessory_id,
+                                            ServiceName::Light,
+                                            characteristic,
+                                        )
+                                        .await
+                                    }
+                                    .boxed()
+                                }));
+
+                                let provider = self.provider.clone();
+                                let saturation = lightbulb.lightbulb.saturation.as_mut().unwrap();
+                                saturation.on_read(Some(|| Ok(None)));
+                                saturation.on_update_async(Some(move |current: f32, new: f32| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("lightbulb saturation characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::Saturation(characteristics::Saturation {
+                                            saturation: new,
+                                        });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Light, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                let provider = self.provider.clone();
+                                let color_temperature =
+                                    lightbulb.lightbulb.color_temperature.as_mut().unwrap();
+                                color_temperature.on_read(Some(|| Ok(None)));
+                                color_temperature.on_update_async(Some(move |current: u32, new: u32| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("lightbulb color temperature characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::ColorTemperature(characteristics::ColorTemperature {
+                                            kelvin: mireds_to_kelvin(new),
+                                        });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Light, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                tracing::info!("registering new lightbulb accessory");
+                                self.ip_server.add_accessory(lightbulb).await?
+                            }
+                            Manufacturer::Thermostat => {
+                                let mut thermostat = ThermostatAccessory::new(
+                                    self.accessory_instance_id,
+                                    AccessoryInformation {
+                                        manufacturer,
+                                        model: "houseflow-thermostat".to_string(),
+                                        name: "Thermostat".to_string(),
+                                        serial_number: accessory.id.to_string(),
+                                        accessory_flags: None,
+                                        application_matching_identifier: None,
+                                        // configured_name: Some(configured_accessory.name.clone()), For some reason it causes the Home app to break
+                                        configured_name: None,
+                                        firmware_revision: None,
+                                        hardware_finish: None,
+                                        hardware_revision: None,
+                                        product_data: None,
+                                        software_revision: None,
+                                    },
+                                )?;
+                                thermostat
+                                    .thermostat
+                                    .current_temperature
+                                    .on_read(Some(|| Ok(None)));
+                                thermostat
+                                    .thermostat
+                                    .current_heating_cooling_state
+                                    .on_read(Some(|| Ok(None)));
 
                                 let accessory_id = accessory.id;
-                                brightness
-                                    .on_update_async(Some(move |current: i32, new: i32| {
+                                let provider = self.provider.clone();
+                                thermostat
+                                    .thermostat
+                                    .target_temperature
+                                    .on_update_async(Some(move |current: f32, new: f32| {
                                         let provider = provider.clone();
 
                                         async move {
-                                            println!("lightbulb target door state characteristic updated from {} to {}", current, new);
-                                            let service_name = ServiceName::GarageDoorOpener;
-                                            let characteristic = Characteristic::TargetDoorState(characteristics::TargetDoorState {
-                                                open_percent: if new == 1 {
-                                                                100
-                                                            } else if new == 0 {
-                                                                0
-                                                            } else {
-                                                                unreachable!()
-                                                            },
+                                            tracing::debug!("thermostat target temperature characteristic updated from {} to {}", current, new);
+                                            let characteristic = Characteristic::TargetTemperature(characteristics::TargetTemperature {
+                                                temperature: new,
                                             });
 
-                                            provider.write_characteristic(accessory_id, service_name, characteristic).await.unwrap();
-                                            Ok(())
+                                            write_characteristic(&provider, accessory_id, ServiceName::Thermostat, characteristic).await
                                         }
                                         .boxed()
                                     }));
 
-                                tracing::info!("registering new lightbulb accessory");
-                                self.ip_server.add_accessory(lightbulb).await?
+                                let provider = self.provider.clone();
+                                thermostat
+                                    .thermostat
+                                    .target_heating_cooling_state
+                                    .on_update_async(Some(move |current: u8, new: u8| {
+                                        let provider = provider.clone();
+
+                                        async move {
+                                            tracing::debug!("thermostat target heating cooling state characteristic updated from {} to {}", current, new);
+                                            let state = heating_cooling_mode(new).map_err(|err| {
+                                                tracing::error!("thermostat target heating cooling state: {}", err);
+                                                err
+                                            })?;
+                                            let characteristic = Characteristic::TargetHeatingCoolingState(characteristics::TargetHeatingCoolingState {
+                                                state,
+                                            });
+
+                                            write_characteristic(&provider, accessory_id, ServiceName::Thermostat, characteristic).await
+                                        }
+                                        .boxed()
+                                    }));
+
+                                let provider = self.provider.clone();
+                                thermostat
+                                    .thermostat
+                                    .temperature_display_units
+                                    .on_update_async(Some(move |current: u8, new: u8| {
+                                        let provider = provider.clone();
+
+                                        async move {
+                                            tracing::debug!("thermostat temperature display units characteristic updated from {} to {}", current, new);
+                                            let units = temperature_units(new).map_err(|err| {
+                                                tracing::error!("thermostat temperature display units: {}", err);
+                                                err
+                                            })?;
+                                            let characteristic = Characteristic::TemperatureDisplayUnits(characteristics::TemperatureDisplayUnits {
+                                                units,
+                                            });
+
+                                            write_characteristic(&provider, accessory_id, ServiceName::Thermostat, characteristic).await
+                                        }
+                                        .boxed()
+                                    }));
+
+                                tracing::info!("registering new thermostat accessory");
+                                self.ip_server.add_accessory(thermostat).await?
+                            }
+                            Manufacturer::Lock => {
+                                let mut lock = LockAccessory::new(
+                                    self.accessory_instance_id,
+                                    AccessoryInformation {
+                                        manufacturer,
+                                        model: "houseflow-lock".to_string(),
+                                        name: "Lock".to_string(),
+                                        serial_number: accessory.id.to_string(),
+                                        accessory_flags: None,
+                                        application_matching_identifier: None,
+                                        // configured_name: Some(configured_accessory.name.clone()), For some reason it causes the Home app to break
+                                        configured_name: None,
+                                        firmware_revision: None,
+                                        hardware_finish: None,
+                                        hardware_revision: None,
+                                        product_data: None,
+                                        software_revision: None,
+                                    },
+                                )?;
+                                lock.lock_mechanism
+                                    .lock_current_state
+                                    .on_read(Some(|| Ok(None)));
+
+                                let provider = self.provider.clone();
+                                let configured_accessories = self.configured_accessories.clone();
+                                let audit_config = self.audit_config.clone();
+
+                                let accessory_id = accessory.id;
+                                lock.lock_mechanism
+                                    .lock_target_state
+                                    .on_update_async(Some(move |current: u8, new: u8| {
+                                        let provider = provider.clone();
+                                        let configured_accessories = configured_accessories.clone();
+                                        let audit_config = audit_config.clone();
+
+                                        async move {
+                                            tracing::debug!("lock target state characteristic updated from {} to {}", current, new);
+                                            let state = lock_target(new).map_err(|err| {
+                                                tracing::error!("lock target state: {}", err);
+                                                err
+                                            })?;
+                                            let characteristic = Characteristic::LockTargetState(characteristics::LockTargetState {
+                                                state,
+                                            });
+
+                                            audit::write_characteristic(
+                                                &provider,
+                                                &configured_accessories,
+                                                &audit_config,
+                                                audit::Source::Hap,
+                                                accessory_id,
+                                                ServiceName::LockMechanism,
+                                                characteristic,
+                                            )
+                                            .await
+                                            .map_err(|err| Box::new(err) as CallbackError)
+                                        }
+                                        .boxed()
+                                    }));
+
+                                tracing::info!("registering new lock accessory");
+                                self.ip_server.add_accessory(lock).await?
+                            }
+                            Manufacturer::MotionSensor => {
+                                let mut motion_sensor = MotionSensorService::new(
+                                    FIRST_SERVICE_IID,
+                                    self.accessory_instance_id,
+                                );
+                                motion_sensor.motion_detected.on_read(Some(|| Ok(None)));
+                                let motion_sensor = self.single_service_accessory(
+                                    accessory.id,
+                                    "houseflow-motion-sensor",
+                                    "Motion Sensor",
+                                    motion_sensor,
+                                )?;
+                                tracing::info!("registering new motion sensor accessory");
+                                self.ip_server.add_accessory(motion_sensor).await?
+                            }
+                            Manufacturer::ContactSensor => {
+                                let mut contact_sensor = ContactSensorService::new(
+                                    FIRST_SERVICE_IID,
+                                    self.accessory_instance_id,
+                                );
+                                contact_sensor
+                                    .contact_sensor_state
+                                    .on_read(Some(|| Ok(None)));
+                                let contact_sensor = self.single_service_accessory(
+                                    accessory.id,
+                                    "houseflow-contact-sensor",
+                                    "Contact Sensor",
+                                    contact_sensor,
+                                )?;
+                                tracing::info!("registering new contact sensor accessory");
+                                self.ip_server.add_accessory(contact_sensor).await?
+                            }
+                            Manufacturer::LeakSensor => {
+                                let mut leak_sensor = LeakSensorService::new(
+                                    FIRST_SERVICE_IID,
+                                    self.accessory_instance_id,
+                                );
+                                leak_sensor.leak_detected.on_read(Some(|| Ok(None)));
+                                let leak_sensor = self.single_service_accessory(
+                                    accessory.id,
+                                    "houseflow-leak-sensor",
+                                    "Leak Sensor",
+                                    leak_sensor,
+                                )?;
+                                tracing::info!("registering new leak sensor accessory");
+                                self.ip_server.add_accessory(leak_sensor).await?
+                            }
+                            Manufacturer::SmokeSensor => {
+                                let mut smoke_sensor = SmokeSensorService::new(
+                                    FIRST_SERVICE_IID,
+                                    self.accessory_instance_id,
+                                );
+                                smoke_sensor.smoke_detected.on_read(Some(|| Ok(None)));
+                                let smoke_sensor = self.single_service_accessory(
+                                    accessory.id,
+                                    "houseflow-smoke-sensor",
+                                    "Smoke Sensor",
+                                    smoke_sensor,
+                                )?;
+                                tracing::info!("registering new smoke sensor accessory");
+                                self.ip_server.add_accessory(smoke_sensor).await?
+                            }
+                            Manufacturer::OccupancySensor => {
+                                let mut occupancy_sensor = OccupancySensorService::new(
+                                    FIRST_SERVICE_IID,
+                                    self.accessory_instance_id,
+                                );
+                                occupancy_sensor
+                                    .occupancy_detected
+                                    .on_read(Some(|| Ok(None)));
+                                let occupancy_sensor = self.single_service_accessory(
+                                    accessory.id,
+                                    "houseflow-occupancy-sensor",
+                                    "Occupancy Sensor",
+                                    occupancy_sensor,
+                                )?;
+                                tracing::info!("registering new occupancy sensor accessory");
+                                self.ip_server.add_accessory(occupancy_sensor).await?
+                            }
+                            Manufacturer::Switch => {
+                                let mut switch = SwitchAccessory::new(
+                                    self.accessory_instance_id,
+                                    AccessoryInformation {
+                                        manufacturer,
+                                        model: "houseflow-switch".to_string(),
+                                        name: "Switch".to_string(),
+                                        serial_number: accessory.id.to_string(),
+                                        accessory_flags: None,
+                                        application_matching_identifier: None,
+                                        // configured_name: Some(configured_accessory.name.clone()), For some reason it causes the Home app to break
+                                        configured_name: None,
+                                        firmware_revision: None,
+                                        hardware_finish: None,
+                                        hardware_revision: None,
+                                        product_data: None,
+                                        software_revision: None,
+                                    },
+                                )?;
+                                let accessory_id = accessory.id;
+                                let provider = self.provider.clone();
+                                let power_state = &mut switch.switch.power_state;
+                                power_state.on_read(Some(|| Ok(None)));
+                                power_state.on_update_async(Some(move |current: bool, new: bool| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("switch power state characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::On(characteristics::On { on: new });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Switch, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                tracing::info!("registering new switch accessory");
+                                self.ip_server.add_accessory(switch).await?
+                            }
+                            Manufacturer::Outlet => {
+                                let mut outlet = OutletAccessory::new(
+                                    self.accessory_instance_id,
+                                    AccessoryInformation {
+                                        manufacturer,
+                                        model: "houseflow-outlet".to_string(),
+                                        name: "Outlet".to_string(),
+                                        serial_number: accessory.id.to_string(),
+                                        accessory_flags: None,
+                                        application_matching_identifier: None,
+                                        // configured_name: Some(configured_accessory.name.clone()), For some reason it causes the Home app to break
+                                        configured_name: None,
+                                        firmware_revision: None,
+                                        hardware_finish: None,
+                                        hardware_revision: None,
+                                        product_data: None,
+                                        software_revision: None,
+                                    },
+                                )?;
+                                let accessory_id = accessory.id;
+                                let provider = self.provider.clone();
+                                let power_state = &mut outlet.outlet.power_state;
+                                power_state.on_read(Some(|| Ok(None)));
+                                power_state.on_update_async(Some(move |current: bool, new: bool| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("outlet power state characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::On(characteristics::On { on: new });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Outlet, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+                                outlet.outlet.outlet_in_use.on_read(Some(|| Ok(None)));
+
+                                tracing::info!("registering new outlet accessory");
+                                self.ip_server.add_accessory(outlet).await?
+                            }
+                            Manufacturer::Fan => {
+                                let mut fan =
+                                    FanService::new(FIRST_SERVICE_IID, self.accessory_instance_id);
+                                let accessory_id = accessory.id;
+                                let provider = self.provider.clone();
+                                let power_state = &mut fan.power_state;
+                                power_state.on_read(Some(|| Ok(None)));
+                                power_state.on_update_async(Some(move |current: bool, new: bool| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("fan power state characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::On(characteristics::On { on: new });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Fan, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                let provider = self.provider.clone();
+                                let rotation_speed = fan.rotation_speed.as_mut().unwrap();
+                                rotation_speed.on_read(Some(|| Ok(None)));
+                                rotation_speed.on_update_async(Some(move |current: f32, new: f32| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("fan rotation speed characteristic updated from {} to {}", current, new);
+                                        let characteristic = Characteristic::RotationSpeed(characteristics::RotationSpeed {
+                                            percentage: new.round() as u8,
+                                        });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Fan, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                let provider = self.provider.clone();
+                                let rotation_direction = fan.rotation_direction.as_mut().unwrap();
+                                rotation_direction.on_read(Some(|| Ok(None)));
+                                rotation_direction.on_update_async(Some(move |current: i32, new: i32| {
+                                    let provider = provider.clone();
+
+                                    async move {
+                                        tracing::debug!("fan rotation direction characteristic updated from {} to {}", current, new);
+                                        let direction = direction(new).map_err(|err| {
+                                            tracing::error!("fan rotation direction: {}", err);
+                                            err
+                                        })?;
+                                        let characteristic = Characteristic::RotationDirection(characteristics::RotationDirection {
+                                            direction,
+                                        });
+
+                                        write_characteristic(&provider, accessory_id, ServiceName::Fan, characteristic).await
+                                    }
+                                    .boxed()
+                                }));
+
+                                let fan = self.single_service_accessory(
+                                    accessory.id,
+                                    "houseflow-fan",
+                                    "Fan",
+                                    fan,
+                                )?;
+                                tracing::info!("registering new fan accessory");
+                                self.ip_server.add_accessory(fan).await?
                             }
                             _ => unimplemented!(),
                         }
@@ -315,6 +785,16 @@ impl<P: ProviderExt + Clone + Send + Sync + 'static> HapController<P> {
                     ServiceName::GarageDoorOpener => HapType::GarageDoorOpener,
                     ServiceName::Battery => HapType::Battery,
                     ServiceName::Light => HapType::Lightbulb,
+                    ServiceName::Thermostat => HapType::Thermostat,
+                    ServiceName::LockMechanism => HapType::LockMechanism,
+                    ServiceName::MotionSensor => HapType::MotionSensor,
+                    ServiceName::ContactSensor => HapType::ContactSensor,
+                    ServiceName::LeakSensor => HapType::LeakSensor,
+                    ServiceName::SmokeSensor => HapType::SmokeSensor,
+                    ServiceName::OccupancySensor => HapType::OccupancySensor,
+                    ServiceName::Switch => HapType::Switch,
+                    ServiceName::Outlet => HapType::Outlet,
+                    ServiceName::Fan => HapType::Fan,
                 };
                 let service = accessory.get_mut_service(service_hap_type).unwrap();
                 match characteristic {
@@ -382,11 +862,349 @@ impl<P: ProviderExt + Clone + Send + Sync + 'static> HapController<P> {
                             .set_value(JsonValue::Bool(on))
                             .await?;
                     }
+                    Characteristic::Brightness(characteristics::Brightness { percentage }) => {
+                        service
+                            .get_mut_characteristic(HapType::Brightness)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(percentage)))
+                            .await?
+                    }
+                    Characteristic::Hue(characteristics::Hue { hue }) => {
+                        service
+                            .get_mut_characteristic(HapType::Hue)
+                            .unwrap()
+                            .set_value(JsonValue::Number(
+                                serde_json::Number::from_f64(hue as f64).unwrap(),
+                            ))
+                            .await?
+                    }
+                    Characteristic::Saturation(characteristics::Saturation { saturation }) => {
+                        service
+                            .get_mut_characteristic(HapType::Saturation)
+                            .unwrap()
+                            .set_value(JsonValue::Number(
+                                serde_json::Number::from_f64(saturation as f64).unwrap(),
+                            ))
+                            .await?
+                    }
+                    Characteristic::ColorTemperature(characteristics::ColorTemperature {
+                        kelvin,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::ColorTemperature)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                kelvin_to_mireds(kelvin),
+                            )))
+                            .await?
+                    }
+                    Characteristic::TargetTemperature(characteristics::TargetTemperature {
+                        temperature,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::TargetTemperature)
+                            .unwrap()
+                            .set_value(JsonValue::Number(
+                                serde_json::Number::from_f64(temperature as f64).unwrap(),
+                            ))
+                            .await?
+                    }
+                    Characteristic::CurrentHeatingCoolingState(
+                        characteristics::CurrentHeatingCoolingState { state },
+                    ) => {
+                        service
+                            .get_mut_characteristic(HapType::CurrentHeatingCoolingState)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                heating_cooling_state_value(state),
+                            )))
+                            .await?
+                    }
+                    Characteristic::TargetHeatingCoolingState(
+                        characteristics::TargetHeatingCoolingState { state },
+                    ) => {
+                        service
+                            .get_mut_characteristic(HapType::TargetHeatingCoolingState)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                heating_cooling_mode_value(state),
+                            )))
+                            .await?
+                    }
+                    Characteristic::TemperatureDisplayUnits(
+                        characteristics::TemperatureDisplayUnits { units },
+                    ) => {
+                        service
+                            .get_mut_characteristic(HapType::TemperatureDisplayUnits)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                temperature_units_value(units),
+                            )))
+                            .await?
+                    }
+                    Characteristic::LockCurrentState(characteristics::LockCurrentState {
+                        state,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::LockCurrentState)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                lock_state_value(state),
+                            )))
+                            .await?
+                    }
+                    Characteristic::LockTargetState(characteristics::LockTargetState { state }) => {
+                        service
+                            .get_mut_characteristic(HapType::LockTargetState)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                lock_target_value(state),
+                            )))
+                            .await?
+                    }
+                    Characteristic::MotionDetected(characteristics::MotionDetected {
+                        detected,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::MotionDetected)
+                            .unwrap()
+                            .set_value(JsonValue::Bool(detected))
+                            .await?
+                    }
+                    Characteristic::ContactSensorState(characteristics::ContactSensorState {
+                        contact,
+                    }) => {
+                        // HAP reports 0 when the contact is detected
+                        service
+                            .get_mut_characteristic(HapType::ContactSensorState)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(u8::from(
+                                !contact,
+                            ))))
+                            .await?
+                    }
+                    Characteristic::LeakDetected(characteristics::LeakDetected { detected }) => {
+                        service
+                            .get_mut_characteristic(HapType::LeakDetected)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(u8::from(
+                                detected,
+                            ))))
+                            .await?
+                    }
+                    Characteristic::SmokeDetected(characteristics::SmokeDetected { detected }) => {
+                        service
+                            .get_mut_characteristic(HapType::SmokeDetected)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(u8::from(
+                                detected,
+                            ))))
+                            .await?
+                    }
+                    Characteristic::OccupancyDetected(characteristics::OccupancyDetected {
+                        detected,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::OccupancyDetected)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(u8::from(
+                                detected,
+                            ))))
+                            .await?
+                    }
+                    Characteristic::StatusTampered(characteristics::StatusTampered {
+                        tampered,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::StatusTampered)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(u8::from(
+                                tampered,
+                            ))))
+                            .await?
+                    }
+                    Characteristic::OutletInUse(characteristics::OutletInUse { in_use }) => {
+                        service
+                            .get_mut_characteristic(HapType::OutletInUse)
+                            .unwrap()
+                            .set_value(JsonValue::Bool(in_use))
+                            .await?
+                    }
+                    Characteristic::RotationSpeed(characteristics::RotationSpeed {
+                        percentage,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::RotationSpeed)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(percentage)))
+                            .await?
+                    }
+                    Characteristic::RotationDirection(characteristics::RotationDirection {
+                        direction,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::RotationDirection)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(
+                                direction_value(direction),
+                            )))
+                            .await?
+                    }
+                    Characteristic::StatusLowBattery(characteristics::StatusLowBattery {
+                        low_battery,
+                    }) => {
+                        service
+                            .get_mut_characteristic(HapType::StatusLowBattery)
+                            .unwrap()
+                            .set_value(JsonValue::Number(serde_json::Number::from(u8::from(
+                                low_battery,
+                            ))))
+                            .await?
+                    }
                 };
             }
         };
         Ok(())
     }
+
+    fn single_service_accessory<S: HapService>(
+        &self,
+        accessory_id: accessory::ID,
+        model: &str,
+        name: &str,
+        service: S,
+    ) -> Result<SingleServiceAccessory<S>, anyhow::Error> {
+        let accessory_information = AccessoryInformation {
+            manufacturer: "Houseflow".to_string(),
+            model: model.to_string(),
+            name: name.to_string(),
+            serial_number: accessory_id.to_string(),
+            ..Default::default()
+        }
+        .to_service(1, self.accessory_instance_id)?;
+        Ok(SingleServiceAccessory {
+            id: self.accessory_instance_id,
+            accessory_information,
+            service,
+        })
+    }
+}
+
+/// Error returned from characteristic callbacks to the HAP client
+type CallbackError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Writes the characteristic updated by a HAP client to the accessory, the error is returned to the
+/// client
+async fn write_characteristic(
+    provider: &(impl ProviderExt + Sync),
+    accessory_id: accessory::ID,
+    service_name: ServiceName,
+    characteristic: Characteristic,
+) -> Result<(), CallbackError> {
+    provider
+        .write_characteristic(accessory_id, service_name, characteristic)
+        .await
+        .map_err(|err| {
+            tracing::error!(%accessory_id, "writing characteristic failed: {}", err);
+            err.into()
+        })
+}
+
+fn heating_cooling_state_value(state: HeatingCoolingState) -> u8 {
+    match state {
+        HeatingCoolingState::Off => 0,
+        HeatingCoolingState::Heat => 1,
+        HeatingCoolingState::Cool => 2,
+    }
+}
+
+/// Maps the value of HAP target heating/cooling state characteristic
+fn heating_cooling_mode(value: u8) -> Result<HeatingCoolingMode, anyhow::Error> {
+    match value {
+        0 => Ok(HeatingCoolingMode::Off),
+        1 => Ok(HeatingCoolingMode::Heat),
+        2 => Ok(HeatingCoolingMode::Cool),
+        3 => Ok(HeatingCoolingMode::Auto),
+        _ => Err(anyhow::anyhow!("invalid heating/cooling mode: {}", value)),
+    }
+}
+
+fn heating_cooling_mode_value(mode: HeatingCoolingMode) -> u8 {
+    match mode {
+        HeatingCoolingMode::Off => 0,
+        HeatingCoolingMode::Heat => 1,
+        HeatingCoolingMode::Cool => 2,
+        HeatingCoolingMode::Auto => 3,
+    }
+}
+
+/// Maps the value of HAP temperature display units characteristic
+fn temperature_units(value: u8) -> Result<TemperatureUnits, anyhow::Error> {
+    match value {
+        0 => Ok(TemperatureUnits::Celsius),
+        1 => Ok(TemperatureUnits::Fahrenheit),
+        _ => Err(anyhow::anyhow!(
+            "invalid temperature display units: {}",
+            value
+        )),
+    }
+}
+
+fn temperature_units_value(units: TemperatureUnits) -> u8 {
+    match units {
+        TemperatureUnits::Celsius => 0,
+        TemperatureUnits::Fahrenheit => 1,
+    }
+}
+
+/// HAP color temperature is expressed in reciprocal megakelvins
+fn mireds_to_kelvin(mireds: u32) -> u16 {
+    (1_000_000 / mireds.max(1)).min(u16::MAX as u32) as u16
+}
+
+fn kelvin_to_mireds(kelvin: u16) -> u32 {
+    1_000_000 / (kelvin.max(1) as u32)
+}
+
+fn lock_state_value(state: LockState) -> u8 {
+    match state {
+        LockState::Unsecured => 0,
+        LockState::Secured => 1,
+        LockState::Jammed => 2,
+        LockState::Unknown => 3,
+    }
+}
+
+/// Maps the value of HAP lock target state characteristic
+fn lock_target(value: u8) -> Result<LockTarget, anyhow::Error> {
+    match value {
+        0 => Ok(LockTarget::Unsecured),
+        1 => Ok(LockTarget::Secured),
+        _ => Err(anyhow::anyhow!("invalid lock target state: {}", value)),
+    }
+}
+
+fn lock_target_value(target: LockTarget) -> u8 {
+    match target {
+        LockTarget::Unsecured => 0,
+        LockTarget::Secured => 1,
+    }
+}
+
+/// Maps the value of HAP rotation direction characteristic
+fn direction(value: i32) -> Result<Direction, anyhow::Error> {
+    match value {
+        0 => Ok(Direction::Clockwise),
+        1 => Ok(Direction::CounterClockwise),
+        _ => Err(anyhow::anyhow!("invalid rotation direction: {}", value)),
+    }
+}
+
+fn direction_value(direction: Direction) -> i32 {
+    match direction {
+        Direction::Clockwise => 0,
+        Direction::CounterClockwise => 1,
+    }
 }
 
 #[derive(Debug, Default)]
@@ -456,3 +1274,63 @@ impl Serialize for HygroThermometerAccessory {
         state.end()
     }
 }
+
+/// Instance ID of the service of a `SingleServiceAccessory`, the accessory information service ends
+/// at IID 6
+const FIRST_SERVICE_IID: u64 = 7;
+
+/// Accessory exposing a single service, e.g. motion sensor or fan
+struct SingleServiceAccessory<S> {
+    id: u64,
+
+    pub accessory_information: AccessoryInformationService,
+    pub service: S,
+}
+
+impl<S: HapService> hap::accessory::HapAccessory for SingleServiceAccessory<S> {
+    fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id
+    }
+
+    fn get_service(&self, hap_type: HapType) -> Option<&dyn hap::service::HapService> {
+        for service in self.get_services() {
+            if service.get_type() == hap_type {
+                return Some(service);
+            }
+        }
+        None
+    }
+
+    fn get_mut_service(&mut self, hap_type: HapType) -> Option<&mut dyn hap::service::HapService> {
+        for service in self.get_mut_services() {
+            if service.get_type() == hap_type {
+                return Some(service);
+            }
+        }
+        None
+    }
+
+    fn get_services(&self) -> Vec<&dyn hap::service::HapService> {
+        vec![&self.accessory_information, &self.service]
+    }
+
+    fn get_mut_services(&mut self) -> Vec<&mut dyn hap::service::HapService> {
+        vec![&mut self.accessory_information, &mut self.service]
+    }
+}
+
+impl<S: HapService> Serialize for SingleServiceAccessory<S> {
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("HapAccessory", 2)?;
+        state.serialize_field("aid", &self.get_id())?;
+        state.serialize_field("services", &self.get_services())?;
+        state.end()
+    }
+}
@@ -1,27 +1,49 @@
 pub use super::Handle;
 use super::Name;
 
+use super::audit;
 use super::Message;
 use crate::providers;
 use crate::providers::ProviderExt;
+use crate::ConfiguredAccessories;
 use async_trait::async_trait;
 use houseflow_config::hub::controllers::Lighthouse as Config;
+use houseflow_config::hub::Accessory;
+use houseflow_config::hub::Audit as AuditConfig;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::accessory::characteristics::CharacteristicName;
+use houseflow_types::accessory::services::ServiceName;
 use houseflow_types::hub;
 use houseflow_types::lighthouse;
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::mpsc;
 
 pub struct LighthouseController {
     provider: providers::MasterHandle,
+    configured_accessories: ConfiguredAccessories,
+    audit_config: AuditConfig,
     client: ezsockets::Client<Message>,
 }
 
 impl LighthouseController {
     async fn send(&mut self, frame: lighthouse::HubFrame) -> anyhow::Result<()> {
-        let json = serde_json::to_string(&frame)?;
-        self.client.text(json).await;
-        Ok(())
+        send(&self.client, frame).await
     }
 }
 
+async fn send(
+    client: &ezsockets::Client<Message>,
+    frame: lighthouse::HubFrame,
+) -> anyhow::Result<()> {
+    let json = serde_json::to_string(&frame)?;
+    client.text(json).await;
+    Ok(())
+}
+
 #[async_trait]
 impl ezsockets::ClientExt for LighthouseController {
     type Params = Message;
@@ -51,15 +73,30 @@ impl ezsockets::ClientExt for LighthouseController {
                 service_name,
                 characteristic,
             }) => {
-                let result = self
-                    .provider
-                    .write_characteristic(accessory_id, service_name, characteristic)
+                // Confirmation of a lock write takes a while, other frames are handled meanwhile
+                let provider = self.provider.clone();
+                let configured_accessories = self.configured_accessories.clone();
+                let audit_config = self.audit_config.clone();
+                let client = self.client.clone();
+                tokio::spawn(async move {
+                    let result = audit::write_characteristic(
+                        &provider,
+                        &configured_accessories,
+                        &audit_config,
+                        audit::Source::Lighthouse,
+                        accessory_id,
+                        service_name,
+                        characteristic,
+                    )
                     .await
                     .into();
-                self.send(lighthouse::HubFrame::WriteCharacteristicResult(
-                    lighthouse::WriteCharacteristicResult { id, result },
-                ))
-                .await?;
+                    let frame = lighthouse::HubFrame::WriteCharacteristicResult(
+                        lighthouse::WriteCharacteristicResult { id, result },
+                    );
+                    if let Err(err) = send(&client, frame).await {
+                        tracing::error!("failed to send write characteristic result: {}", err);
+                    }
+                });
             }
             _ => unimplemented!(),
         }
@@ -80,11 +117,8 @@ impl ezsockets::ClientExt for LighthouseController {
                 self.send(lighthouse::HubFrame::AccessoryDisconnected(accessory_id))
                     .await?;
             }
-            Message::Updated {
-                accessory_id: _,
-                service_name: _,
-                characteristic: _,
-            } => {}
+            // Updates are batched by the supervisor and sent in `Supervisor::flush` instead
+            Message::Updated { .. } => {}
         };
         Ok(())
     }
@@ -94,14 +128,187 @@ pub async fn new(
     config: Config,
     hub_id: hub::ID,
     provider: providers::MasterHandle,
+    configured_accessories: ConfiguredAccessories,
+    audit_config: AuditConfig,
 ) -> Result<Handle, anyhow::Error> {
-    let (client, _) = ezsockets::connect(
-        |client| LighthouseController { provider, client },
-        ezsockets::ClientConfig::new(config.url).basic(&hub_id.to_string(), &config.password),
-    )
-    .await;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let supervisor = Supervisor {
+        config,
+        hub_id,
+        provider,
+        configured_accessories,
+        audit_config,
+        receiver,
+        connected_accessories: Default::default(),
+        pending_updates: Default::default(),
+        flush_at: None,
+    };
+    tokio::spawn(supervisor.run());
 
-    let sender: tokio::sync::mpsc::UnboundedSender<Message> = client.into();
     let sender = acu::Sender::new_from_mpsc(sender, Name::Lighthouse);
     Ok(Handle { sender })
 }
+
+/// Keeps the connection to the server alive, reconnecting with exponential backoff and jitter.
+struct Supervisor {
+    config: Config,
+    hub_id: hub::ID,
+    provider: providers::MasterHandle,
+    configured_accessories: ConfiguredAccessories,
+    audit_config: AuditConfig,
+    receiver: mpsc::UnboundedReceiver<Message>,
+    connected_accessories: HashMap<accessory::ID, Accessory>,
+    /// Latest values of the characteristics updated since the last batch was sent
+    pending_updates: HashMap<(accessory::ID, ServiceName, CharacteristicName), Characteristic>,
+    flush_at: Option<tokio::time::Instant>,
+}
+
+impl Supervisor {
+    async fn run(mut self) {
+        let mut attempt = 0;
+        loop {
+            let provider = self.provider.clone();
+            let configured_accessories = self.configured_accessories.clone();
+            let audit_config = self.audit_config.clone();
+            let (client, future) = ezsockets::connect(
+                |client| LighthouseController {
+                    provider,
+                    configured_accessories,
+                    audit_config,
+                    client,
+                },
+                ezsockets::ClientConfig::new(self.config.url.clone())
+                    .basic(&self.hub_id.to_string(), &self.config.password),
+            )
+            .await;
+            let text_client = client.clone();
+            let client: mpsc::UnboundedSender<Message> = client.into();
+            // Server forgets about the accessories of the hub on disconnect
+            if let Err(err) = self.replay(&text_client).await {
+                tracing::error!("failed to replay connected accessories: {}", err);
+            }
+
+            let connected_at = Instant::now();
+            tokio::pin!(future);
+            loop {
+                let flush_at = self.flush_at.unwrap_or_else(tokio::time::Instant::now);
+                tokio::select! {
+                    _ = tokio::time::sleep_until(flush_at), if self.flush_at.is_some() => {
+                        if let Err(err) = self.flush(&text_client).await {
+                            tracing::error!("failed to send characteristic updates: {}", err);
+                        }
+                    }
+                    result = &mut future => {
+                        match result {
+                            Ok(()) => tracing::warn!("connection with the server has been closed"),
+                            Err(err) => {
+                                tracing::error!("connection with the server failed: {}", err)
+                            }
+                        }
+                        break;
+                    }
+                    message = self.receiver.recv() => match message {
+                        // Updates are coalesced and sent in batches
+                        Some(message @ Message::Updated { .. }) => self.track(&message),
+                        Some(message) => {
+                            self.track(&message);
+                            client.send(message).ok();
+                        }
+                        None => return,
+                    }
+                }
+            }
+
+            // Connection that was up for a while is not a failed attempt
+            if connected_at.elapsed() > self.config.reconnect_max_delay {
+                attempt = 0;
+            }
+            let delay = reconnect_delay(&self.config, attempt);
+            attempt = attempt.saturating_add(1);
+            tracing::info!("reconnecting to the server in {:?}", delay);
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    message = self.receiver.recv() => match message {
+                        Some(message) => self.track(&message),
+                        None => return,
+                    }
+                }
+            }
+        }
+    }
+
+    fn track(&mut self, message: &Message) {
+        match message {
+            Message::Connected { accessory } => {
+                self.connected_accessories
+                    .insert(accessory.id, accessory.clone());
+            }
+            Message::Disconnected { accessory_id } => {
+                self.connected_accessories.remove(accessory_id);
+                self.pending_updates
+                    .retain(|(id, _, _), _| id != accessory_id);
+            }
+            Message::Updated {
+                accessory_id,
+                service_name,
+                characteristic,
+            } => {
+                let characteristic_name = CharacteristicName::from(characteristic);
+                self.pending_updates.insert(
+                    (*accessory_id, *service_name, characteristic_name),
+                    characteristic.clone(),
+                );
+                let update_batch_interval = self.config.update_batch_interval;
+                self.flush_at
+                    .get_or_insert_with(|| tokio::time::Instant::now() + update_batch_interval);
+            }
+        }
+    }
+
+    /// Sends `AccessoryConnected` frame for each of the connected accessories
+    async fn replay(&self, client: &ezsockets::Client<Message>) -> anyhow::Result<()> {
+        for accessory in self.connected_accessories.values() {
+            let frame = lighthouse::HubFrame::AccessoryConnected(accessory.clone().into());
+            let json = serde_json::to_string(&frame)?;
+            client.text(json).await;
+        }
+        Ok(())
+    }
+
+    /// Sends pending characteristic updates to the server in a single frame
+    async fn flush(&mut self, client: &ezsockets::Client<Message>) -> anyhow::Result<()> {
+        self.flush_at = None;
+        let updates = self
+            .pending_updates
+            .drain()
+            .map(|((accessory_id, service_name, _), characteristic)| {
+                lighthouse::UpdateCharacteristic {
+                    accessory_id,
+                    service_name,
+                    characteristic,
+                }
+            })
+            .collect::<Vec<_>>();
+        if updates.is_empty() {
+            return Ok(());
+        }
+        let frame = lighthouse::HubFrame::UpdateCharacteristics(updates);
+        let json = serde_json::to_string(&frame)?;
+        client.text(json).await;
+        Ok(())
+    }
+}
+
+/// Returns delay before the reconnect attempt, which is doubled on each attempt up to the maximum,
+/// with random jitter of up to half of the delay
+fn reconnect_delay(config: &Config, attempt: u32) -> Duration {
+    let delay = config
+        .reconnect_initial_delay
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(config.reconnect_max_delay);
+    let jitter = rand::thread_rng().gen_range(0.0..=0.5);
+    delay.mul_f64(1.0 - jitter)
+}
@@ -1,4 +1,8 @@
-pub fn app(master_provider: providers::MasterHandle) -> axum::Router {
+pub fn app(
+    master_provider: providers::MasterHandle,
+    configured_accessories: ConfiguredAccessories,
+    audit_config: AuditConfig,
+) -> axum::Router {
     use axum::routing::get;
     use axum::routing::post;
 
@@ -12,13 +16,18 @@ pub fn app(master_provider: providers::MasterHandle) -> axum::Router {
             post(write_characteristic),
         )
         .layer(Extension(master_provider))
+        .layer(Extension(configured_accessories))
+        .layer(Extension(audit_config))
 }
 
+use super::audit;
 use crate::providers;
 use crate::providers::ProviderExt;
+use crate::ConfiguredAccessories;
 use axum::extract::Extension;
 use axum::extract::Json;
 use axum::extract::Path;
+use houseflow_config::hub::Audit as AuditConfig;
 use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics::Characteristic;
 use houseflow_types::accessory::characteristics::CharacteristicName;
@@ -41,11 +50,20 @@ async fn read_characteristic(
 
 async fn write_characteristic(
     Extension(master_provider): Extension<providers::MasterHandle>,
+    Extension(configured_accessories): Extension<ConfiguredAccessories>,
+    Extension(audit_config): Extension<AuditConfig>,
     Path((accessory_id, service_name)): Path<(accessory::ID, ServiceName)>,
     Json(characteristic): Json<Characteristic>,
 ) -> Result<(), hub::Error> {
-    master_provider
-        .write_characteristic(accessory_id, service_name, characteristic)
-        .await?;
+    audit::write_characteristic(
+        &master_provider,
+        &configured_accessories,
+        &audit_config,
+        audit::Source::Meta,
+        accessory_id,
+        service_name,
+        characteristic,
+    )
+    .await?;
     Ok(())
 }
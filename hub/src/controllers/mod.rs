@@ -1,3 +1,13 @@
+cfg_if::cfg_if! {
+    if #[cfg(any(
+        feature = "controllers-hap",
+        feature = "controllers-meta",
+        feature = "controllers-lighthouse",
+    ))] {
+        pub mod audit;
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "controllers-meta")] {
         pub mod meta;
@@ -71,20 +71,35 @@ pub async fn run(config: Config) -> Result<(), anyhow::Error> {
         let mut router = Router::new();
 
         optional_controller!(hap, {
-            let handle = controllers::hap::new(hap, master_provider.clone()).await?;
+            let handle = controllers::hap::new(
+                hap,
+                master_provider.clone(),
+                configured_accessories.clone(),
+                config.audit.clone(),
+            )
+            .await?;
             master_controller.push(handle).await;
         });
 
         optional_controller!(lighthouse, {
-            let handle =
-                controllers::lighthouse::new(lighthouse, config.hub.id, master_provider.clone())
-                    .await?;
+            let handle = controllers::lighthouse::new(
+                lighthouse,
+                config.hub.id,
+                master_provider.clone(),
+                configured_accessories.clone(),
+                config.audit.clone(),
+            )
+            .await?;
             master_controller.push(handle).await;
         });
 
         optional_controller!(meta, {
             let _meta = meta;
-            let app = controllers::meta::app(master_provider.clone());
+            let app = controllers::meta::app(
+                master_provider.clone(),
+                configured_accessories.clone(),
+                config.audit.clone(),
+            );
             router = router.nest("/meta", app);
         });
 
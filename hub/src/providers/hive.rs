@@ -19,10 +19,12 @@ use houseflow_config::hub::Accessory;
 use houseflow_config::hub::HiveProvider as Config;
 use houseflow_types::accessory;
 use houseflow_types::hive;
+use houseflow_types::password;
+use houseflow_types::requests::wait_for_result;
+use houseflow_types::requests::PendingRequests;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
-use tokio::sync::oneshot;
 
 type Server = ezsockets::Server<HiveProvider>;
 type Session = ezsockets::Session<accessory::ID, SessionMessage>;
@@ -37,10 +39,11 @@ pub struct HiveProvider {
     controller: controllers::MasterHandle,
     sessions: HashMap<accessory::ID, Session>,
     configured_accessories: ConfiguredAccessories,
+    config: Config,
 }
 
 pub fn new(
-    _config: Config,
+    config: Config,
     controller: controllers::MasterHandle,
     configured_accessories: ConfiguredAccessories,
 ) -> Server {
@@ -48,6 +51,7 @@ pub fn new(
         controller,
         configured_accessories,
         sessions: Default::default(),
+        config,
     });
     server
 }
@@ -69,8 +73,7 @@ impl ezsockets::ServerExt for HiveProvider {
                 session,
                 accessory_id: accessory.id,
                 controller: self.controller.clone(),
-                characteristic_write_results: Default::default(),
-                characteristic_read_results: Default::default(),
+                requests: Default::default(),
             },
             accessory.id,
             socket,
@@ -96,17 +99,25 @@ impl ezsockets::ServerExt for HiveProvider {
                 characteristic_name,
                 respond_to,
             } => {
-                let session = self.sessions.get(&accessory_id).unwrap();
+                let session = match self.sessions.get(&accessory_id) {
+                    Some(session) => session,
+                    None => {
+                        respond_to.send(Err(accessory::Error::NotConnected)).ok();
+                        return Ok(());
+                    }
+                };
                 let result = session
                     .call_with(|respond_to| SessionMessage::ReadCharacteristic {
                         service_name,
                         characteristic_name,
                         respond_to,
                     })
-                    .await
-                    .await
-                    .unwrap();
-                respond_to.send(result).unwrap();
+                    .await;
+                let request_timeout = self.config.request_timeout;
+                tokio::spawn(async move {
+                    let result = wait_for_result(result, request_timeout).await;
+                    respond_to.send(result).ok();
+                });
             }
             Message::WriteCharacteristic {
                 accessory_id,
@@ -114,17 +125,25 @@ impl ezsockets::ServerExt for HiveProvider {
                 characteristic,
                 respond_to,
             } => {
-                let session = self.sessions.get(&accessory_id).unwrap();
+                let session = match self.sessions.get(&accessory_id) {
+                    Some(session) => session,
+                    None => {
+                        respond_to.send(Err(accessory::Error::NotConnected)).ok();
+                        return Ok(());
+                    }
+                };
                 let result = session
                     .call_with(|respond_to| SessionMessage::WriteCharacteristic {
                         service_name,
                         characteristic,
                         respond_to,
                     })
-                    .await
-                    .await
-                    .unwrap();
-                respond_to.send(result).unwrap();
+                    .await;
+                let request_timeout = self.config.request_timeout;
+                tokio::spawn(async move {
+                    let result = wait_for_result(result, request_timeout).await;
+                    respond_to.send(result).ok();
+                });
             }
             Message::GetAccessoryConfiguration {
                 accessory_id,
@@ -172,6 +191,7 @@ pub enum ConnectError {
     InvalidAuthorizationHeader(String),
     AccessoryNotFound,
     AccessoryAlreadyConnected,
+    InvalidCredentials,
 }
 
 impl axum::response::IntoResponse for ConnectError {
@@ -180,6 +200,7 @@ impl axum::response::IntoResponse for ConnectError {
             Self::InvalidAuthorizationHeader(_) => StatusCode::BAD_REQUEST,
             Self::AccessoryNotFound => StatusCode::UNAUTHORIZED,
             Self::AccessoryAlreadyConnected => StatusCode::NOT_ACCEPTABLE,
+            Self::InvalidCredentials => StatusCode::UNAUTHORIZED,
         };
         let mut response = axum::Json(self).into_response();
         *response.status_mut() = status;
@@ -213,7 +234,7 @@ pub async fn websocket_handler(
     Extension(provider): Extension<providers::MasterHandle>,
     Extension(configured_accessories): Extension<ConfiguredAccessories>,
     Extension(server): Extension<Server>,
-    DeviceCredentials(accessory_id, _password): DeviceCredentials,
+    DeviceCredentials(accessory_id, password): DeviceCredentials,
 ) -> Result<impl axum::response::IntoResponse, ConnectError> {
     let accessory = configured_accessories
         .load()
@@ -221,14 +242,25 @@ pub async fn websocket_handler(
         .find(|accessory| accessory.id == accessory_id)
         .ok_or(ConnectError::AccessoryNotFound)?
         .clone();
+    let password_hash = accessory.password_hash.clone().ok_or_else(|| {
+        tracing::warn!(%accessory_id, "accessory has no password hash configured");
+        ConnectError::InvalidCredentials
+    })?;
+    let is_valid = tokio::task::spawn_blocking(move || password::verify(&password, &password_hash))
+        .await
+        .unwrap()
+        .map_err(|err| {
+            tracing::error!(%accessory_id, "malformed password hash: {}", err);
+            ConnectError::InvalidCredentials
+        })?;
+    if !is_valid {
+        return Err(ConnectError::InvalidCredentials);
+    }
     let is_connected = provider.is_connected(accessory_id).await;
     if is_connected {
         return Err(ConnectError::AccessoryAlreadyConnected);
     }
 
-    // TODO: Verify password and remove following line
-    tracing::warn!("{} connected without password verification", accessory_id);
-
     Ok(websocket.on_upgrade(server, Args { accessory }))
 }
 
@@ -236,12 +268,7 @@ pub struct HiveSession {
     session: Session,
     accessory_id: accessory::ID,
     controller: controllers::MasterHandle,
-    characteristic_write_results:
-        HashMap<hive::FrameID, oneshot::Sender<Result<(), accessory::Error>>>,
-    characteristic_read_results: HashMap<
-        hive::FrameID,
-        oneshot::Sender<Result<accessory::characteristics::Characteristic, accessory::Error>>,
-    >,
+    requests: PendingRequests,
 }
 
 #[async_trait]
@@ -262,18 +289,24 @@ impl ezsockets::SessionExt for HiveSession {
                     .updated(self.accessory_id, frame.service_name, frame.characteristic)
                     .await;
             }
-            hive::AccessoryFrame::CharacteristicReadResult(frame) => self
-                .characteristic_read_results
-                .remove(&frame.id)
-                .unwrap()
-                .send(frame.result.into())
-                .unwrap(),
-            hive::AccessoryFrame::CharacteristicWriteResult(frame) => self
-                .characteristic_write_results
-                .remove(&frame.id)
-                .unwrap()
-                .send(frame.result.into())
-                .unwrap(),
+            hive::AccessoryFrame::CharacteristicReadResult(frame) => {
+                if !self.requests.resolve_read(frame.id, frame.result.into()) {
+                    tracing::warn!(
+                        accessory_id = %self.accessory_id,
+                        frame_id = frame.id,
+                        "unexpected read characteristic result"
+                    )
+                }
+            }
+            hive::AccessoryFrame::CharacteristicWriteResult(frame) => {
+                if !self.requests.resolve_write(frame.id, frame.result.into()) {
+                    tracing::warn!(
+                        accessory_id = %self.accessory_id,
+                        frame_id = frame.id,
+                        "unexpected write characteristic result"
+                    )
+                }
+            }
         }
         Ok(())
     }
@@ -289,16 +322,13 @@ impl ezsockets::SessionExt for HiveSession {
                 characteristic_name,
                 respond_to,
             } => {
-                let frame_id = rand::random();
+                let (frame_id, response_rx) = self.requests.read();
                 let frame = hive::HubFrame::ReadCharacteristic(hive::ReadCharacteristic {
                     id: frame_id,
                     service_name,
                     characteristic_name,
                 });
                 let text = serde_json::to_string(&frame)?;
-                let (response_tx, response_rx) = oneshot::channel();
-                self.characteristic_read_results
-                    .insert(frame_id, response_tx);
                 self.session.text(text).await;
                 respond_to.send(response_rx).unwrap();
             }
@@ -307,16 +337,13 @@ impl ezsockets::SessionExt for HiveSession {
                 characteristic,
                 respond_to,
             } => {
-                let frame_id = rand::random();
+                let (frame_id, response_rx) = self.requests.write();
                 let frame = hive::HubFrame::WriteCharacteristic(hive::WriteCharacteristic {
                     id: frame_id,
                     service_name,
                     characteristic,
                 });
                 let text = serde_json::to_string(&frame)?;
-                let (response_tx, response_rx) = oneshot::channel();
-                self.characteristic_write_results
-                    .insert(frame_id, response_tx);
                 self.session.text(text).await;
                 respond_to.send(response_rx).unwrap();
             }
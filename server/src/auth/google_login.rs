@@ -0,0 +1,372 @@
+use super::login::logged_in;
+use crate::extensions;
+use axum::extract::Form;
+use axum::extract::TypedHeader;
+use axum::headers::Cookie;
+use axum::http::header;
+use axum::Json;
+use houseflow_config::server::JwksSource;
+use houseflow_types::auth::login::GoogleRequest;
+use houseflow_types::auth::login::Response;
+use houseflow_types::errors::AuthError;
+use houseflow_types::errors::InternalError;
+use houseflow_types::errors::ServerError;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::Algorithm;
+use jsonwebtoken::DecodingKey;
+use jsonwebtoken::Validation;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tracing::Level;
+
+const CSRF_TOKEN_COOKIE: &str = "g_csrf_token";
+const GOOGLE_ISSUERS: &[&str] = &["accounts.google.com", "https://accounts.google.com"];
+
+#[derive(Debug, Clone, Deserialize)]
+struct GoogleClaims {
+    email: String,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+#[tracing::instrument(name = "Google login", skip(config, store, jwks, cookie, request), err)]
+pub async fn handle(
+    config: extensions::Config,
+    store: extensions::Store,
+    jwks: extensions::GoogleJwks,
+    cookie: Option<TypedHeader<Cookie>>,
+    Form(request): Form<GoogleRequest>,
+) -> Result<Json<Response>, ServerError> {
+    let csrf_token = cookie
+        .as_ref()
+        .and_then(|TypedHeader(cookie)| cookie.get(CSRF_TOKEN_COOKIE));
+    if csrf_token != Some(request.g_csrf_token.as_str()) {
+        return Err(AuthError::InvalidCsrfToken.into());
+    }
+
+    let google_login =
+        config.get().logins.google.clone().ok_or_else(|| {
+            ServerError::ValidationError(String::from("google login is disabled"))
+        })?;
+    let kid = key_id(&request.credential)?;
+    let jwks = jwks.get(&google_login.jwks, &kid).await?;
+    let claims = verify_id_token(&jwks, &kid, &google_login.client_id, &request.credential)?;
+    if !claims.email_verified {
+        return Err(AuthError::InvalidGoogleJwt(String::from("email is not verified")).into());
+    }
+    let email: lettre::Address = claims
+        .email
+        .parse()
+        .map_err(|err| AuthError::InvalidGoogleJwt(format!("invalid email: {}", err)))?;
+    let user = store
+        .get_user_by_email(&email)
+        .await?
+        .ok_or(AuthError::UserNotFound)?;
+
+    tracing::event!(Level::INFO, user_id = %user.id, "Logged in with Google");
+    Ok(Json(logged_in(&config.get(), user.id)?))
+}
+
+/// Key set loaded from the source, along with the time after which it has to be loaded again
+struct CachedJwks {
+    source: JwksSource,
+    jwks: Arc<JwkSet>,
+    expires_at: Option<Instant>,
+}
+
+/// Cache of the JSON Web Key Set used to verify ID tokens issued by Google.
+///
+/// The key set is loaded again once the `max-age` from the `Cache-Control` header of the response
+/// elapses, or when a token is signed with a key that is not in the cached set.
+#[derive(Default)]
+pub struct JwksCache {
+    cached: tokio::sync::Mutex<Option<CachedJwks>>,
+}
+
+impl JwksCache {
+    /// Returns the key set from the source, that is expected to contain the key with `kid`
+    async fn get(&self, source: &JwksSource, kid: &str) -> Result<Arc<JwkSet>, ServerError> {
+        let mut cached = self.cached.lock().await;
+        if let Some(cached) = cached.as_ref() {
+            let is_fresh = cached
+                .expires_at
+                .map_or(true, |expires_at| Instant::now() < expires_at);
+            if cached.source == *source && is_fresh && cached.jwks.find(kid).is_some() {
+                return Ok(cached.jwks.clone());
+            }
+        }
+        let (jwks, max_age) = load_jwks(source).await?;
+        let jwks = Arc::new(jwks);
+        *cached = Some(CachedJwks {
+            source: source.clone(),
+            jwks: jwks.clone(),
+            expires_at: max_age.map(|max_age| Instant::now() + max_age),
+        });
+        Ok(jwks)
+    }
+}
+
+/// Loads the key set, along with the time for which it can be cached, if the source specifies it
+async fn load_jwks(source: &JwksSource) -> Result<(JwkSet, Option<Duration>), ServerError> {
+    let error = |err: String| InternalError::Other(format!("loading google jwks: {}", err));
+    let loaded = match source {
+        JwksSource::Url(url) => {
+            let response = reqwest::get(url.clone())
+                .await
+                .map_err(|err| error(err.to_string()))?;
+            let max_age = response
+                .headers()
+                .get(header::CACHE_CONTROL)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_max_age);
+            let jwks = response
+                .json()
+                .await
+                .map_err(|err| error(err.to_string()))?;
+            // Without max-age the key set must not be reused
+            (jwks, Some(max_age.unwrap_or_default()))
+        }
+        JwksSource::Path(path) => {
+            let contents = tokio::fs::read(path)
+                .await
+                .map_err(|err| error(err.to_string()))?;
+            let jwks = serde_json::from_slice(&contents).map_err(|err| error(err.to_string()))?;
+            (jwks, None)
+        }
+    };
+    Ok(loaded)
+}
+
+/// Parses the `max-age` directive of the `Cache-Control` header
+fn parse_max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.parse().ok())
+        .map(Duration::from_secs)
+}
+
+fn key_id(token: &str) -> Result<String, AuthError> {
+    let header = jsonwebtoken::decode_header(token)
+        .map_err(|err| AuthError::InvalidGoogleJwt(err.to_string()))?;
+    header
+        .kid
+        .ok_or_else(|| AuthError::InvalidGoogleJwt(String::from("missing key id")))
+}
+
+fn verify_id_token(
+    jwks: &JwkSet,
+    kid: &str,
+    client_id: &str,
+    token: &str,
+) -> Result<GoogleClaims, AuthError> {
+    let jwk = jwks
+        .find(kid)
+        .ok_or_else(|| AuthError::InvalidGoogleJwt(format!("unknown key id: {}", kid)))?;
+    let key =
+        DecodingKey::from_jwk(jwk).map_err(|err| AuthError::InvalidGoogleJwt(err.to_string()))?;
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(GOOGLE_ISSUERS);
+    let data = jsonwebtoken::decode::<GoogleClaims>(token, &key, &validation)
+        .map_err(|err| AuthError::InvalidGoogleJwt(err.to_string()))?;
+    Ok(data.claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use axum::headers::Header;
+    use axum::http::HeaderValue;
+    use chrono::Utc;
+    use houseflow_types::token::AccessToken;
+    use houseflow_types::token::RefreshToken;
+    use jsonwebtoken::EncodingKey;
+    use serde::Serialize;
+
+    const PRIVATE_KEY: &[u8] = include_bytes!("../../fixtures/google-login/private-key.pem");
+    const KEY_ID: &str = "houseflow-test-key";
+
+    #[derive(Serialize)]
+    struct Claims<'a> {
+        iss: &'a str,
+        aud: &'a str,
+        sub: &'a str,
+        email: &'a str,
+        email_verified: bool,
+        exp: i64,
+    }
+
+    fn get_id_token(audience: &str, email: &str) -> String {
+        let mut header = jsonwebtoken::Header::new(Algorithm::RS256);
+        header.kid = Some(String::from(KEY_ID));
+        let claims = Claims {
+            iss: "https://accounts.google.com",
+            aud: audience,
+            sub: "1234567890",
+            email,
+            email_verified: true,
+            exp: (Utc::now() + chrono::Duration::hours(1)).timestamp(),
+        };
+        let key = EncodingKey::from_rsa_pem(PRIVATE_KEY).unwrap();
+        jsonwebtoken::encode(&header, &claims, &key).unwrap()
+    }
+
+    fn get_cookie(csrf_token: &'static str) -> TypedHeader<Cookie> {
+        let value = HeaderValue::from_static(csrf_token);
+        TypedHeader(Cookie::decode(&mut std::iter::once(&value)).unwrap())
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let Json(response) = super::handle(
+            config.clone(),
+            store.clone(),
+            get_google_jwks(),
+            Some(get_cookie("g_csrf_token=csrf-token")),
+            Form(GoogleRequest {
+                credential: get_id_token("google-login-client-id", &user.email.to_string()),
+                g_csrf_token: String::from("csrf-token"),
+            }),
+        )
+        .await
+        .unwrap();
+        let (at, rt) = match response {
+            Response::LoggedIn {
+                access_token,
+                refresh_token,
+            } => (access_token, refresh_token),
+            _ => panic!("expected Response::LoggedIn"),
+        };
+        let (at, rt) = (
+            AccessToken::decode(config.get().secrets.access_key.as_bytes(), &at).unwrap(),
+            RefreshToken::decode(config.get().secrets.refresh_key.as_bytes(), &rt).unwrap(),
+        );
+        assert_eq!(at.sub, user.id);
+        assert_eq!(rt.sub, user.id);
+    }
+
+    #[tokio::test]
+    async fn invalid_csrf_token() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let err = super::handle(
+            config,
+            store,
+            get_google_jwks(),
+            Some(get_cookie("g_csrf_token=other-csrf-token")),
+            Form(GoogleRequest {
+                credential: get_id_token("google-login-client-id", &user.email.to_string()),
+                g_csrf_token: String::from("csrf-token"),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err, ServerError::AuthError(AuthError::InvalidCsrfToken));
+    }
+
+    #[tokio::test]
+    async fn invalid_audience() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let err = super::handle(
+            config,
+            store,
+            get_google_jwks(),
+            Some(get_cookie("g_csrf_token=csrf-token")),
+            Form(GoogleRequest {
+                credential: get_id_token("other-client-id", &user.email.to_string()),
+                g_csrf_token: String::from("csrf-token"),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ServerError::AuthError(AuthError::InvalidGoogleJwt(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn not_existing_user() {
+        let user = get_user();
+        let config = get_config(GetConfig::default()).await;
+        let store = get_store(&config).await;
+        let err = super::handle(
+            config,
+            store,
+            get_google_jwks(),
+            Some(get_cookie("g_csrf_token=csrf-token")),
+            Form(GoogleRequest {
+                credential: get_id_token("google-login-client-id", &user.email.to_string()),
+                g_csrf_token: String::from("csrf-token"),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err, ServerError::AuthError(AuthError::UserNotFound));
+    }
+
+    #[test]
+    fn parse_max_age() {
+        assert_eq!(
+            super::parse_max_age("public, max-age=19800, must-revalidate, no-transform"),
+            Some(Duration::from_secs(19800))
+        );
+        assert_eq!(super::parse_max_age("no-cache"), None);
+        assert_eq!(super::parse_max_age("max-age=invalid"), None);
+    }
+
+    #[tokio::test]
+    async fn jwks_cache() {
+        let path = std::env::temp_dir().join(format!("houseflow-jwks-{}", rand::random::<u32>()));
+        let jwks = include_bytes!("../../fixtures/google-login/jwks.json");
+        tokio::fs::write(&path, jwks).await.unwrap();
+        let source = JwksSource::Path(path.clone());
+        let cache = JwksCache::default();
+        assert!(cache
+            .get(&source, KEY_ID)
+            .await
+            .unwrap()
+            .find(KEY_ID)
+            .is_some());
+
+        // Key set is not loaded again as long as it contains the key
+        tokio::fs::write(&path, r#"{"keys": []}"#).await.unwrap();
+        assert!(cache
+            .get(&source, KEY_ID)
+            .await
+            .unwrap()
+            .find(KEY_ID)
+            .is_some());
+
+        // Key set is loaded again when the key is unknown
+        assert!(cache
+            .get(&source, "other-key")
+            .await
+            .unwrap()
+            .keys
+            .is_empty());
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -1,10 +1,15 @@
 use crate::extensions;
+use crate::extractors::AcceptLanguage;
+use crate::mailer::Locale;
 use crate::mailer::MailerExt;
+use crate::mailer::Purpose;
 use axum::Json;
-use chrono::Duration;
 use chrono::Utc;
+use houseflow_config::server::Config;
+use houseflow_config::server::RateLimits;
 use houseflow_types::auth::login::Request;
 use houseflow_types::auth::login::Response;
+use houseflow_types::client::Client;
 use houseflow_types::code::VerificationCode;
 use houseflow_types::errors::AuthError;
 use houseflow_types::errors::ServerError;
@@ -12,14 +17,17 @@ use houseflow_types::token::AccessToken;
 use houseflow_types::token::AccessTokenClaims;
 use houseflow_types::token::RefreshToken;
 use houseflow_types::token::RefreshTokenClaims;
+use houseflow_types::user;
+use houseflow_types::user::User;
 use tracing::Level;
 
-const VERIFICATION_CODE_DURATION: std::time::Duration = std::time::Duration::from_secs(60 * 30);
+pub(crate) const VERIFICATION_CODE_DURATION: std::time::Duration =
+    std::time::Duration::from_secs(60 * 30);
 const VERIFICATION_CODE_LIMIT: usize = 3;
 
 #[tracing::instrument(
     name = "Login",
-    skip(config, clerk, mailer, request),
+    skip(config, store, clerk, mailer, limiter, request),
     fields(
         email = %request.email,
     ),
@@ -27,67 +35,30 @@ const VERIFICATION_CODE_LIMIT: usize = 3;
 )]
 pub async fn handle(
     config: extensions::Config,
+    store: extensions::Store,
     clerk: extensions::Clerk,
     mailer: extensions::MasterMailer,
+    limiter: extensions::RateLimiter,
+    AcceptLanguage(locale): AcceptLanguage,
     Json(request): Json<Request>,
 ) -> Result<Json<Response>, ServerError> {
     validator::Validate::validate(&request)?;
-    let user = config
-        .get()
+    let rate_limits = config.get().rate_limits.clone();
+    limiter.check_account(&request.email, &rate_limits.account)?;
+    let user = store
         .get_user_by_email(&request.email)
-        .ok_or(AuthError::UserNotFound)?
-        .to_owned();
+        .await?
+        .ok_or(AuthError::UserNotFound)?;
 
     let response = match request.verification_code {
         Some(verification_code) => {
-            let user_id = clerk.get(&verification_code).await?.ok_or_else(|| {
-                AuthError::InvalidVerificationCode("code is not known by clerk".to_string())
-            })?;
-            if user_id != user.id {
-                return Err(AuthError::InvalidVerificationCode(
-                    "user-id doesn't match".to_string(),
-                )
-                .into());
-            }
-            let refresh_token = RefreshToken::new(
-                config.get().secrets.refresh_key.as_bytes(),
-                RefreshTokenClaims {
-                    sub: user.id,
-                    exp: Some(Utc::now() + Duration::days(7)),
-                },
-            )?;
-            let access_token = AccessToken::new(
-                config.get().secrets.access_key.as_bytes(),
-                AccessTokenClaims {
-                    sub: user.id,
-                    exp: Utc::now() + Duration::minutes(10),
-                },
-            )?;
+            verify_verification_code(&clerk, &limiter, &rate_limits, &user, &verification_code)
+                .await?;
             tracing::event!(Level::INFO, user_id = %user.id, "Logged in");
-            Response::LoggedIn {
-                access_token: access_token.encode(),
-                refresh_token: refresh_token.encode(),
-            }
+            logged_in(&config.get(), user.id)?
         }
         None => {
-            if clerk.count_verification_codes_for_user(&user.id)? > VERIFICATION_CODE_LIMIT {
-                return Err(ServerError::TooManyRequests);
-            }
-            let verification_code: VerificationCode = rand::random();
-            clerk
-                .add(
-                    verification_code.clone(),
-                    user.id,
-                    Utc::now() + chrono::Duration::from_std(VERIFICATION_CODE_DURATION).unwrap(),
-                )
-                .await?;
-            mailer
-                .send_verification_code(
-                    String::from("Your Houseflow account: Access from a new computer"),
-                    user.email.to_owned(),
-                    verification_code,
-                )
-                .await;
+            send_verification_code(&clerk, &mailer, &user, Purpose::Login, locale).await?;
             Response::VerificationCodeSent
         }
     };
@@ -95,12 +66,98 @@ pub async fn handle(
     Ok(Json(response))
 }
 
+/// Issues a new pair of access and refresh tokens for the user
+pub(crate) fn logged_in(config: &Config, user_id: user::ID) -> Result<Response, ServerError> {
+    let client = Client::Internal;
+    let refresh_token = RefreshToken::new(
+        config.secrets.refresh_key.as_bytes(),
+        RefreshTokenClaims::new(
+            user_id,
+            client,
+            client
+                .refresh_token_duration()
+                .map(|duration| Utc::now() + duration),
+        ),
+    )?;
+    let access_token = AccessToken::new(
+        config.secrets.access_key.as_bytes(),
+        AccessTokenClaims {
+            sub: user_id,
+            exp: Utc::now() + client.access_token_duration(),
+        },
+    )?;
+    Ok(Response::LoggedIn {
+        access_token: access_token.encode(),
+        refresh_token: refresh_token.encode(),
+    })
+}
+
+/// Generates a new verification code and sends it to the user's email
+pub(crate) async fn send_verification_code(
+    clerk: &extensions::Clerk,
+    mailer: &extensions::MasterMailer,
+    user: &User,
+    purpose: Purpose,
+    locale: Locale,
+) -> Result<(), ServerError> {
+    if clerk.count_verification_codes_for_user(&user.id)? >= VERIFICATION_CODE_LIMIT {
+        // The oldest code expires at the latest after that time
+        return Err(ServerError::TooManyRequests {
+            retry_after: VERIFICATION_CODE_DURATION.as_secs(),
+        });
+    }
+    let verification_code: VerificationCode = rand::random();
+    clerk
+        .add(
+            verification_code.clone(),
+            user.id,
+            Utc::now() + chrono::Duration::from_std(VERIFICATION_CODE_DURATION).unwrap(),
+        )
+        .await?;
+    mailer
+        .send_verification_code(purpose, locale, user.email.to_owned(), verification_code)
+        .await;
+    Ok(())
+}
+
+/// Checks if the verification code has been issued for the user, locks the account after too many
+/// invalid codes
+pub(crate) async fn verify_verification_code(
+    clerk: &extensions::Clerk,
+    limiter: &extensions::RateLimiter,
+    rate_limits: &RateLimits,
+    user: &User,
+    verification_code: &VerificationCode,
+) -> Result<(), ServerError> {
+    limiter.check_lockout(&user.id)?;
+    let user_id = clerk.get(verification_code).await?.ok_or_else(|| {
+        AuthError::InvalidVerificationCode("code is not known by clerk".to_string())
+    });
+    let result = match user_id {
+        Ok(user_id) if user_id != user.id => Err(AuthError::InvalidVerificationCode(
+            "user-id doesn't match".to_string(),
+        )),
+        result => result.map(drop),
+    };
+    match result {
+        Ok(()) => limiter.reset_failed_attempts(&user.id),
+        Err(_) => limiter.record_failed_attempt(&user.id, rate_limits),
+    }
+    result.map_err(Into::into)
+}
+
 #[cfg(test)]
 mod tests {
     use super::Request;
     use super::Response;
     use super::VERIFICATION_CODE_DURATION;
+    use super::VERIFICATION_CODE_LIMIT;
+    use crate::extractors::AcceptLanguage;
+    use crate::mailer::Locale;
     use crate::test_utils::*;
+    use axum::http::header;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
     use axum::Json;
     use chrono::Utc;
     use houseflow_types::code::VerificationCode;
@@ -120,15 +177,20 @@ mod tests {
             ..Default::default()
         })
         .await;
+        let store = get_store(&config).await;
         let clerk = get_clerk(GetClerk::default()).await;
         let mailer = get_master_mailer(GetMasterMailer {
             tx: Some(mailer_tx),
         })
         .await;
+        let limiter = get_rate_limiter();
         let Json(response) = super::handle(
             config.clone(),
+            store.clone(),
             clerk.clone(),
             mailer.clone(),
+            limiter.clone(),
+            AcceptLanguage(Locale::default()),
             Json(Request {
                 email: user.email.clone(),
                 verification_code: None,
@@ -141,8 +203,11 @@ mod tests {
         assert_eq!(address, user.email);
         let Json(response) = super::handle(
             config.clone(),
+            store.clone(),
             clerk,
             mailer,
+            limiter,
+            AcceptLanguage(Locale::default()),
             Json(Request {
                 email: user.email.clone(),
                 verification_code: Some(verification_code),
@@ -172,13 +237,17 @@ mod tests {
             ..Default::default()
         })
         .await;
+        let store = get_store(&config).await;
         let clerk = get_clerk(GetClerk::default()).await;
         let mailer = get_master_mailer(GetMasterMailer::default()).await;
         let verification_code: VerificationCode = rand::random();
         let response = super::handle(
             config,
+            store,
             clerk,
             mailer,
+            get_rate_limiter(),
+            AcceptLanguage(Locale::default()),
             Json(Request {
                 email: user.email,
                 verification_code: Some(verification_code),
@@ -198,6 +267,7 @@ mod tests {
             ..Default::default()
         })
         .await;
+        let store = get_store(&config).await;
         let clerk = get_clerk(GetClerk::default()).await;
         let mailer = get_master_mailer(GetMasterMailer::default()).await;
         let verification_code: VerificationCode = rand::random();
@@ -212,8 +282,11 @@ mod tests {
 
         let response = super::handle(
             config,
+            store,
             clerk,
             mailer,
+            get_rate_limiter(),
+            AcceptLanguage(Locale::default()),
             Json(Request {
                 email: user.email,
                 verification_code: Some(verification_code),
@@ -225,16 +298,124 @@ mod tests {
         assert!(matches!(response, ServerError::AuthError(_)))
     }
 
+    #[tokio::test]
+    async fn locked_after_invalid_codes() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        // Let the account rate limit pass all requests, so only the lockout rejects them
+        let mut server_config = (**config.get()).clone();
+        server_config.rate_limits.account.burst = server_config.rate_limits.lockout_attempts + 1;
+        config.update(server_config);
+        let rate_limits = config.get().rate_limits.clone();
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer::default()).await;
+        let limiter = get_rate_limiter();
+        let login = |verification_code: VerificationCode| {
+            super::handle(
+                config.clone(),
+                store.clone(),
+                clerk.clone(),
+                mailer.clone(),
+                limiter.clone(),
+                AcceptLanguage(Locale::default()),
+                Json(Request {
+                    email: user.email.clone(),
+                    verification_code: Some(verification_code),
+                }),
+            )
+        };
+        for _ in 0..rate_limits.lockout_attempts {
+            let err = login(rand::random()).await.unwrap_err();
+            assert!(matches!(
+                err,
+                ServerError::AuthError(AuthError::InvalidVerificationCode(_))
+            ));
+        }
+
+        // Even a valid code is rejected while the account is locked
+        let verification_code: VerificationCode = rand::random();
+        clerk
+            .add(
+                verification_code.clone(),
+                user.id,
+                Utc::now() + chrono::Duration::from_std(VERIFICATION_CODE_DURATION).unwrap(),
+            )
+            .await
+            .unwrap();
+        let response = login(verification_code).await.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response.headers()[header::RETRY_AFTER],
+            rate_limits.lockout_duration.as_secs().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn verification_code_limit() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer::default()).await;
+        let limiter = get_rate_limiter();
+        let send_code = || {
+            super::handle(
+                config.clone(),
+                store.clone(),
+                clerk.clone(),
+                mailer.clone(),
+                limiter.clone(),
+                AcceptLanguage(Locale::default()),
+                Json(Request {
+                    email: user.email.clone(),
+                    verification_code: None,
+                }),
+            )
+        };
+        for _ in 0..VERIFICATION_CODE_LIMIT {
+            let Json(response) = send_code().await.unwrap();
+            assert_eq!(response, Response::VerificationCodeSent);
+        }
+        assert_eq!(
+            clerk.count_verification_codes_for_user(&user.id).unwrap(),
+            VERIFICATION_CODE_LIMIT
+        );
+
+        assert_eq!(
+            send_code().await.unwrap_err(),
+            ServerError::TooManyRequests {
+                retry_after: VERIFICATION_CODE_DURATION.as_secs()
+            }
+        );
+        assert_eq!(
+            clerk.count_verification_codes_for_user(&user.id).unwrap(),
+            VERIFICATION_CODE_LIMIT
+        );
+    }
+
     #[tokio::test]
     async fn not_existing_user() {
         let user = get_user();
         let config = get_config(GetConfig::default()).await;
+        let store = get_store(&config).await;
         let clerk = get_clerk(GetClerk::default()).await;
         let mailer = get_master_mailer(GetMasterMailer::default()).await;
         let response = super::handle(
             config,
+            store,
             clerk,
             mailer,
+            get_rate_limiter(),
+            AcceptLanguage(Locale::default()),
             Json(Request {
                 email: user.email,
                 verification_code: None,
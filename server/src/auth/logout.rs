@@ -0,0 +1,161 @@
+use super::refresh::is_revoked;
+use crate::extensions;
+use crate::extractors::RefreshToken;
+use axum::Json;
+use chrono::Utc;
+use houseflow_types::auth::logout::Request;
+use houseflow_types::auth::logout::Response;
+use houseflow_types::errors::AuthError;
+use houseflow_types::errors::ServerError;
+use tracing::Level;
+
+#[tracing::instrument(name = "Logout", skip(store, request), err)]
+pub async fn handle(
+    store: extensions::Store,
+    RefreshToken(refresh_token): RefreshToken,
+    Json(request): Json<Request>,
+) -> Result<Json<Response>, ServerError> {
+    if is_revoked(&**store, &refresh_token.claims).await? {
+        return Err(AuthError::RefreshTokenBlacklisted.into());
+    }
+    let user_id = refresh_token.sub;
+    if request.all_sessions {
+        store
+            .revoke_user_refresh_tokens(&user_id, Utc::now())
+            .await?;
+        tracing::event!(Level::INFO, %user_id, "Logged out from all sessions");
+    } else {
+        if !store.revoke_refresh_token(&refresh_token.jti).await? {
+            return Err(AuthError::RefreshTokenBlacklisted.into());
+        }
+        tracing::event!(Level::INFO, %user_id, "Logged out");
+    }
+
+    Ok(Json(Response {}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Request;
+    use crate::extractors;
+    use crate::test_utils::*;
+    use axum::Json;
+    use houseflow_types::auth::login;
+    use houseflow_types::auth::token;
+    use houseflow_types::client::Client;
+    use houseflow_types::errors::AuthError;
+    use houseflow_types::errors::ServerError;
+    use houseflow_types::token::RefreshToken;
+    use houseflow_types::token::RefreshTokenClaims;
+    use houseflow_types::user;
+    use std::time::Duration;
+
+    fn get_refresh_token(config: &crate::extensions::Config, user_id: user::ID) -> RefreshToken {
+        RefreshToken::new(
+            config.get().secrets.refresh_key.as_bytes(),
+            RefreshTokenClaims::new(user_id, Client::Internal, None),
+        )
+        .unwrap()
+    }
+
+    async fn refresh(
+        config: &crate::extensions::Config,
+        store: &crate::extensions::Store,
+        refresh_token: &RefreshToken,
+    ) -> Result<(), ServerError> {
+        crate::auth::refresh::handle(
+            config.clone(),
+            store.clone(),
+            extractors::RefreshToken(refresh_token.clone()),
+            Json(token::Request {}),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn current_session() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = get_refresh_token(&config, user.id);
+        let other_refresh_token = get_refresh_token(&config, user.id);
+        super::handle(
+            store.clone(),
+            extractors::RefreshToken(refresh_token.clone()),
+            Json(Request::default()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            refresh(&config, &store, &refresh_token).await,
+            Err(ServerError::AuthError(AuthError::RefreshTokenBlacklisted))
+        );
+        refresh(&config, &store, &other_refresh_token)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn all_sessions() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = get_refresh_token(&config, user.id);
+        let other_refresh_token = get_refresh_token(&config, user.id);
+        // Tokens issued within the same millisecond as the logout remain valid
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        super::handle(
+            store.clone(),
+            extractors::RefreshToken(refresh_token.clone()),
+            Json(Request { all_sessions: true }),
+        )
+        .await
+        .unwrap();
+
+        for refresh_token in [refresh_token, other_refresh_token] {
+            assert_eq!(
+                refresh(&config, &store, &refresh_token).await,
+                Err(ServerError::AuthError(AuthError::RefreshTokenBlacklisted))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn login_after_all_sessions() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = get_refresh_token(&config, user.id);
+        super::handle(
+            store.clone(),
+            extractors::RefreshToken(refresh_token),
+            Json(Request { all_sessions: true }),
+        )
+        .await
+        .unwrap();
+
+        let response = crate::auth::login::logged_in(&config.get(), user.id).unwrap();
+        let refresh_token = match response {
+            login::Response::LoggedIn { refresh_token, .. } => {
+                RefreshToken::decode(config.get().secrets.refresh_key.as_bytes(), &refresh_token)
+                    .unwrap()
+            }
+            _ => panic!("expected Response::LoggedIn"),
+        };
+        refresh(&config, &store, &refresh_token).await.unwrap();
+    }
+}
@@ -1,5 +1,9 @@
+pub mod google_login;
 pub mod login;
+pub mod logout;
+pub mod oauth;
 pub mod refresh;
+pub mod register;
 pub mod whoami;
 
 pub fn app() -> axum::Router {
@@ -8,6 +12,10 @@ pub fn app() -> axum::Router {
 
     axum::Router::new()
         .route("/login", post(login::handle))
+        .route("/login/google", post(google_login::handle))
+        .route("/logout", post(logout::handle))
         .route("/refresh", post(refresh::handle))
+        .route("/register", post(register::handle))
         .route("/whoami", get(whoami::handle))
+        .layer(axum::middleware::from_fn(crate::rate_limiter::limit_ip))
 }
@@ -0,0 +1,242 @@
+use super::verify_client;
+use super::verify_redirect_uri;
+use crate::auth::login::send_verification_code;
+use crate::auth::login::verify_verification_code;
+use crate::extensions;
+use crate::mailer::Locale;
+use crate::mailer::Purpose;
+use askama::Template;
+use axum::extract::Form;
+use axum::extract::Query;
+use axum::response::Html;
+use axum::response::IntoResponse;
+use axum::response::Redirect;
+use axum::response::Response;
+use chrono::Duration;
+use chrono::Utc;
+use houseflow_config::server::Config;
+use houseflow_types::client::Client;
+use houseflow_types::errors::AuthError;
+use houseflow_types::errors::ServerError;
+use houseflow_types::oauth::AuthorizationRequestForm;
+use houseflow_types::oauth::AuthorizationRequestQuery;
+use houseflow_types::token;
+use houseflow_types::token::AuthorizationCode;
+use houseflow_types::token::AuthorizationCodeClaims;
+use tracing::Level;
+
+/// Time after which the issued authorization code can no longer be exchanged for tokens
+const AUTHORIZATION_CODE_DURATION: std::time::Duration = std::time::Duration::from_secs(60 * 10);
+
+#[derive(Template)]
+#[template(path = "authorize.html")]
+struct AuthorizeTemplate {
+    client_name: &'static str,
+    /// Email of the user, present if the verification code has been already sent
+    email: Option<String>,
+}
+
+fn client_name(client: Client) -> &'static str {
+    match client {
+        Client::Internal => "Houseflow",
+        Client::GoogleHome => "Google Home",
+    }
+}
+
+fn verify_request(
+    config: &Config,
+    query: &AuthorizationRequestQuery,
+) -> Result<Client, ServerError> {
+    let client = verify_client(config, &query.client_id, None)?;
+    verify_redirect_uri(config, client, &query.redirect_uri)?;
+    Ok(client)
+}
+
+#[tracing::instrument(name = "Authorize", skip(config, query), err)]
+pub async fn handle_get(
+    config: extensions::Config,
+    Query(query): Query<AuthorizationRequestQuery>,
+) -> Result<Html<String>, ServerError> {
+    let client = verify_request(&config.get(), &query)?;
+    let template = AuthorizeTemplate {
+        client_name: client_name(client),
+        email: None,
+    };
+    Ok(Html(template.render()?))
+}
+
+#[tracing::instrument(
+    name = "Authorize",
+    skip(config, store, clerk, mailer, limiter, query, form),
+    fields(
+        email = %form.email,
+    ),
+    err,
+)]
+pub async fn handle_post(
+    config: extensions::Config,
+    store: extensions::Store,
+    clerk: extensions::Clerk,
+    mailer: extensions::MasterMailer,
+    limiter: extensions::RateLimiter,
+    Query(query): Query<AuthorizationRequestQuery>,
+    Form(form): Form<AuthorizationRequestForm>,
+) -> Result<Response, ServerError> {
+    let client = verify_request(&config.get(), &query)?;
+    let rate_limits = config.get().rate_limits.clone();
+    limiter.check_account(&form.email, &rate_limits.account)?;
+    let user = store
+        .get_user_by_email(&form.email)
+        .await?
+        .ok_or(AuthError::UserNotFound)?;
+
+    let response = match form.verification_code {
+        Some(verification_code) => {
+            verify_verification_code(&clerk, &limiter, &rate_limits, &user, &verification_code)
+                .await?;
+            let authorization_code = AuthorizationCode::new(
+                config.get().secrets.authorization_code_key.as_bytes(),
+                AuthorizationCodeClaims {
+                    jti: token::ID::new_v4(),
+                    sub: user.id,
+                    client_id: query.client_id.clone(),
+                    redirect_uri: query.redirect_uri.clone(),
+                    exp: Utc::now() + Duration::from_std(AUTHORIZATION_CODE_DURATION).unwrap(),
+                },
+            )?;
+            let mut redirect_uri = query.redirect_uri;
+            redirect_uri
+                .query_pairs_mut()
+                .append_pair("code", &authorization_code.encode())
+                .append_pair("state", &query.state);
+            tracing::event!(Level::INFO, user_id = %user.id, %client, "Authorized client");
+            Redirect::to(redirect_uri.as_str()).into_response()
+        }
+        None => {
+            let locale = query
+                .user_locale
+                .as_deref()
+                .map(Locale::from_language_tag)
+                .unwrap_or_default();
+            send_verification_code(&clerk, &mailer, &user, Purpose::Login, locale).await?;
+            let template = AuthorizeTemplate {
+                client_name: client_name(client),
+                email: Some(user.email.to_string()),
+            };
+            Html(template.render()?).into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+    use axum::extract::Form;
+    use axum::extract::Query;
+    use axum::http::header;
+    use axum::http::StatusCode;
+    use houseflow_config::server::controllers;
+    use houseflow_types::oauth::AuthorizationRequestForm;
+    use houseflow_types::oauth::AuthorizationRequestQuery;
+    use houseflow_types::oauth::ResponseType;
+    use houseflow_types::token::AuthorizationCode;
+    use tokio::sync::mpsc;
+    use url::Url;
+
+    fn get_query() -> AuthorizationRequestQuery {
+        AuthorizationRequestQuery {
+            client_id: String::from("client-id"),
+            redirect_uri: Url::parse("https://oauth-redirect.googleusercontent.com/r/project-id")
+                .unwrap(),
+            state: String::from("some-state"),
+            response_type: ResponseType::Code,
+            scope: None,
+            user_locale: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        let user = get_user();
+        let (mailer_tx, mut mailer_rx) = mpsc::unbounded_channel();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(controllers::GoogleHome {
+                client_id: String::from("client-id"),
+                client_secret: String::from("client-secret"),
+                project_id: String::from("project-id"),
+            }),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer {
+            tx: Some(mailer_tx),
+        })
+        .await;
+        let limiter = get_rate_limiter();
+        let response = super::handle_post(
+            config.clone(),
+            store.clone(),
+            clerk.clone(),
+            mailer.clone(),
+            limiter.clone(),
+            Query(get_query()),
+            Form(AuthorizationRequestForm {
+                email: user.email.clone(),
+                verification_code: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let (address, verification_code) = mailer_rx.recv().await.unwrap();
+        assert_eq!(address, user.email);
+
+        let response = super::handle_post(
+            config.clone(),
+            store.clone(),
+            clerk,
+            mailer,
+            limiter,
+            Query(get_query()),
+            Form(AuthorizationRequestForm {
+                email: user.email.clone(),
+                verification_code: Some(verification_code),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        let location = Url::parse(location).unwrap();
+        let pairs = location
+            .query_pairs()
+            .into_owned()
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(pairs["state"], "some-state");
+        let authorization_code = AuthorizationCode::decode(
+            config.get().secrets.authorization_code_key.as_bytes(),
+            &pairs["code"],
+        )
+        .unwrap();
+        assert_eq!(authorization_code.sub, user.id);
+        assert_eq!(authorization_code.client_id, "client-id");
+        assert_eq!(authorization_code.redirect_uri, get_query().redirect_uri);
+    }
+
+    #[tokio::test]
+    async fn invalid_client() {
+        let config = get_config(GetConfig::default()).await;
+        let response = super::handle_get(config, Query(get_query()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            response,
+            houseflow_types::errors::ServerError::OAuthError(_)
+        ));
+    }
+}
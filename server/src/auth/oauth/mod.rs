@@ -0,0 +1,146 @@
+pub mod authorize;
+pub mod token;
+
+use houseflow_config::server::Config;
+use houseflow_types::client::Client;
+use houseflow_types::errors::OAuthError;
+use url::Url;
+
+/// Hosts that Google uses as redirect URIs during account linking
+const GOOGLE_REDIRECT_HOSTS: &[&str] = &[
+    "oauth-redirect.googleusercontent.com",
+    "oauth-redirect-sandbox.googleusercontent.com",
+];
+
+pub fn app() -> axum::Router {
+    use axum::routing::get;
+    use axum::routing::post;
+
+    axum::Router::new()
+        .route(
+            "/authorize",
+            get(authorize::handle_get).post(authorize::handle_post),
+        )
+        .route("/token", post(token::handle))
+        .layer(axum::middleware::from_fn(crate::rate_limiter::limit_ip))
+}
+
+/// Finds the client with the given ID, and verifies its secret if there is one.
+fn verify_client(
+    config: &Config,
+    client_id: &str,
+    client_secret: Option<&str>,
+) -> Result<Client, OAuthError> {
+    let google_home = config
+        .controllers
+        .google_home
+        .as_ref()
+        .filter(|google_home| google_home.client_id == client_id)
+        .ok_or_else(|| OAuthError::InvalidClient(Some(String::from("unknown client id"))))?;
+    if let Some(client_secret) = client_secret {
+        if google_home.client_secret != client_secret {
+            return Err(OAuthError::InvalidClient(Some(String::from(
+                "invalid client secret",
+            ))));
+        }
+    }
+    Ok(Client::GoogleHome)
+}
+
+fn verify_redirect_uri(
+    config: &Config,
+    client: Client,
+    redirect_uri: &Url,
+) -> Result<(), OAuthError> {
+    let valid = match client {
+        Client::GoogleHome => {
+            let project_id = config
+                .controllers
+                .google_home
+                .as_ref()
+                .map(|google_home| google_home.project_id.as_str())
+                .ok_or_else(|| {
+                    OAuthError::UnauthorizedClient(Some(String::from("google home is disabled")))
+                })?;
+            redirect_uri.scheme() == "https"
+                && redirect_uri
+                    .host_str()
+                    .map(|host| GOOGLE_REDIRECT_HOSTS.contains(&host))
+                    .unwrap_or(false)
+                && redirect_uri.path() == format!("/r/{}", project_id)
+        }
+        Client::Internal => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(OAuthError::InvalidRequest(Some(String::from(
+            "invalid redirect uri",
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use houseflow_config::server::controllers;
+
+    fn google_home() -> controllers::GoogleHome {
+        controllers::GoogleHome {
+            client_id: String::from("client-id"),
+            client_secret: String::from("client-secret"),
+            project_id: String::from("project-id"),
+        }
+    }
+
+    #[tokio::test]
+    async fn client() {
+        let config = get_config(GetConfig {
+            google_home: Some(google_home()),
+            ..Default::default()
+        })
+        .await;
+        let config = config.get();
+        assert_eq!(
+            verify_client(&config, "client-id", Some("client-secret")),
+            Ok(Client::GoogleHome)
+        );
+        assert_eq!(
+            verify_client(&config, "client-id", None),
+            Ok(Client::GoogleHome)
+        );
+        assert!(verify_client(&config, "client-id", Some("other-secret")).is_err());
+        assert!(verify_client(&config, "other-client-id", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn redirect_uri() {
+        let config = get_config(GetConfig {
+            google_home: Some(google_home()),
+            ..Default::default()
+        })
+        .await;
+        let config = config.get();
+        let valid = [
+            "https://oauth-redirect.googleusercontent.com/r/project-id",
+            "https://oauth-redirect-sandbox.googleusercontent.com/r/project-id",
+        ];
+        for redirect_uri in valid {
+            let redirect_uri = Url::parse(redirect_uri).unwrap();
+            assert_eq!(
+                verify_redirect_uri(&config, Client::GoogleHome, &redirect_uri),
+                Ok(())
+            );
+        }
+        let invalid = [
+            "http://oauth-redirect.googleusercontent.com/r/project-id",
+            "https://oauth-redirect.googleusercontent.com/r/other-project-id",
+            "https://example.com/r/project-id",
+        ];
+        for redirect_uri in invalid {
+            let redirect_uri = Url::parse(redirect_uri).unwrap();
+            assert!(verify_redirect_uri(&config, Client::GoogleHome, &redirect_uri).is_err());
+        }
+    }
+}
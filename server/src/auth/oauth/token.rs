@@ -0,0 +1,419 @@
+use super::verify_client;
+use crate::auth::refresh::rotate;
+use crate::extensions;
+use axum::extract::Form;
+use axum::Json;
+use chrono::Utc;
+use houseflow_types::errors::OAuthError;
+use houseflow_types::errors::ServerError;
+use houseflow_types::oauth::TokenRequest;
+use houseflow_types::oauth::TokenRequestGrant;
+use houseflow_types::oauth::TokenResponse;
+use houseflow_types::oauth::TokenType;
+use houseflow_types::token::AccessToken;
+use houseflow_types::token::AccessTokenClaims;
+use houseflow_types::token::AuthorizationCode;
+use houseflow_types::token::RefreshToken;
+use houseflow_types::token::RefreshTokenClaims;
+use std::sync::Arc;
+use tracing::Level;
+
+#[tracing::instrument(
+    name = "OAuth token",
+    skip(config, store, request),
+    fields(
+        client_id = %request.client_id,
+    ),
+    err,
+)]
+pub async fn handle(
+    config: extensions::Config,
+    store: extensions::Store,
+    Form(request): Form<TokenRequest>,
+) -> Result<Json<TokenResponse>, ServerError> {
+    let config = Arc::clone(&config.get());
+    let client = verify_client(&config, &request.client_id, Some(&request.client_secret))?;
+    let access_token_duration = client.access_token_duration();
+
+    let response = match request.grant {
+        TokenRequestGrant::AuthorizationCode { code, redirect_uri } => {
+            let authorization_code =
+                AuthorizationCode::decode(config.secrets.authorization_code_key.as_bytes(), &code)
+                    .map_err(|err| OAuthError::InvalidGrant(Some(err.to_string())))?;
+            if authorization_code.client_id != request.client_id {
+                return Err(OAuthError::InvalidGrant(Some(String::from(
+                    "authorization code was issued to another client",
+                )))
+                .into());
+            }
+            if redirect_uri.as_ref() != Some(&authorization_code.redirect_uri) {
+                return Err(OAuthError::InvalidGrant(Some(String::from(
+                    "redirect_uri does not match the one from the authorization request",
+                )))
+                .into());
+            }
+            if !store
+                .use_authorization_code(&authorization_code.jti)
+                .await?
+            {
+                return Err(OAuthError::InvalidGrant(Some(String::from(
+                    "authorization code has been already used",
+                )))
+                .into());
+            }
+            let user_id = authorization_code.sub;
+            let refresh_token = RefreshToken::new(
+                config.secrets.refresh_key.as_bytes(),
+                RefreshTokenClaims::new(
+                    user_id,
+                    client,
+                    client
+                        .refresh_token_duration()
+                        .map(|duration| Utc::now() + duration),
+                ),
+            )?;
+            let access_token = AccessToken::new(
+                config.secrets.access_key.as_bytes(),
+                AccessTokenClaims {
+                    sub: user_id,
+                    exp: Utc::now() + access_token_duration,
+                },
+            )?;
+            tracing::event!(Level::INFO, %user_id, %client, "Exchanged authorization code");
+            TokenResponse {
+                token_type: TokenType::Bearer,
+                access_token: access_token.encode(),
+                refresh_token: Some(refresh_token.encode()),
+                expires_in: Some(access_token_duration),
+            }
+        }
+        TokenRequestGrant::RefreshToken { refresh_token } => {
+            let refresh_token =
+                RefreshToken::decode(config.secrets.refresh_key.as_bytes(), &refresh_token)
+                    .map_err(|err| OAuthError::InvalidGrant(Some(err.to_string())))?;
+            if refresh_token.client != client {
+                return Err(OAuthError::InvalidGrant(Some(String::from(
+                    "refresh token was issued to another client",
+                )))
+                .into());
+            }
+            let rotated_claims =
+                rotate(&**store, &refresh_token.claims)
+                    .await?
+                    .ok_or_else(|| {
+                        OAuthError::InvalidGrant(Some(String::from("refresh token is revoked")))
+                    })?;
+            let rotated_refresh_token =
+                RefreshToken::new(config.secrets.refresh_key.as_bytes(), rotated_claims)?;
+            let user_id = refresh_token.sub;
+            let access_token = AccessToken::new(
+                config.secrets.access_key.as_bytes(),
+                AccessTokenClaims {
+                    sub: user_id,
+                    exp: Utc::now() + access_token_duration,
+                },
+            )?;
+            tracing::event!(Level::INFO, %user_id, %client, "Refreshed token");
+            TokenResponse {
+                token_type: TokenType::Bearer,
+                access_token: access_token.encode(),
+                refresh_token: Some(rotated_refresh_token.encode()),
+                expires_in: Some(access_token_duration),
+            }
+        }
+    };
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use houseflow_config::server::controllers;
+    use houseflow_types::client::Client;
+    use houseflow_types::token;
+    use houseflow_types::token::AuthorizationCodeClaims;
+    use houseflow_types::user;
+    use url::Url;
+
+    fn get_google_home() -> controllers::GoogleHome {
+        controllers::GoogleHome {
+            client_id: String::from("client-id"),
+            client_secret: String::from("client-secret"),
+            project_id: String::from("project-id"),
+        }
+    }
+
+    fn get_redirect_uri() -> Url {
+        Url::parse("https://oauth-redirect.googleusercontent.com/r/project-id").unwrap()
+    }
+
+    fn get_authorization_code(
+        config: &extensions::Config,
+        user_id: user::ID,
+        client_id: &str,
+    ) -> AuthorizationCode {
+        AuthorizationCode::new(
+            config.get().secrets.authorization_code_key.as_bytes(),
+            AuthorizationCodeClaims {
+                jti: token::ID::new_v4(),
+                sub: user_id,
+                client_id: String::from(client_id),
+                redirect_uri: get_redirect_uri(),
+                exp: Utc::now() + chrono::Duration::minutes(10),
+            },
+        )
+        .unwrap()
+    }
+
+    fn get_authorization_code_request(
+        authorization_code: &AuthorizationCode,
+        redirect_uri: Option<Url>,
+    ) -> Form<TokenRequest> {
+        Form(TokenRequest {
+            client_id: String::from("client-id"),
+            client_secret: String::from("client-secret"),
+            grant: TokenRequestGrant::AuthorizationCode {
+                code: authorization_code.encode(),
+                redirect_uri,
+            },
+        })
+    }
+
+    fn get_refresh_token_request(refresh_token: &RefreshToken) -> Form<TokenRequest> {
+        Form(TokenRequest {
+            client_id: String::from("client-id"),
+            client_secret: String::from("client-secret"),
+            grant: TokenRequestGrant::RefreshToken {
+                refresh_token: refresh_token.encode(),
+            },
+        })
+    }
+
+    fn is_invalid_grant(err: &ServerError) -> bool {
+        matches!(err, ServerError::OAuthError(OAuthError::InvalidGrant(_)))
+    }
+
+    #[tokio::test]
+    async fn authorization_code() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let authorization_code = get_authorization_code(&config, user.id, "client-id");
+        let Json(response) = super::handle(
+            config.clone(),
+            store.clone(),
+            get_authorization_code_request(&authorization_code, Some(get_redirect_uri())),
+        )
+        .await
+        .unwrap();
+        let access_token = AccessToken::decode(
+            config.get().secrets.access_key.as_bytes(),
+            &response.access_token,
+        )
+        .unwrap();
+        let refresh_token = RefreshToken::decode(
+            config.get().secrets.refresh_key.as_bytes(),
+            &response.refresh_token.unwrap(),
+        )
+        .unwrap();
+        assert_eq!(access_token.sub, user.id);
+        assert_eq!(refresh_token.sub, user.id);
+        assert_eq!(refresh_token.exp, None);
+    }
+
+    #[tokio::test]
+    async fn authorization_code_replay() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let authorization_code = get_authorization_code(&config, user.id, "client-id");
+        super::handle(
+            config.clone(),
+            store.clone(),
+            get_authorization_code_request(&authorization_code, Some(get_redirect_uri())),
+        )
+        .await
+        .unwrap();
+        let err = super::handle(
+            config,
+            store,
+            get_authorization_code_request(&authorization_code, Some(get_redirect_uri())),
+        )
+        .await
+        .unwrap_err();
+        assert!(is_invalid_grant(&err));
+    }
+
+    #[tokio::test]
+    async fn authorization_code_other_client() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let authorization_code = get_authorization_code(&config, user.id, "other-client-id");
+        let err = super::handle(
+            config,
+            store,
+            get_authorization_code_request(&authorization_code, Some(get_redirect_uri())),
+        )
+        .await
+        .unwrap_err();
+        assert!(is_invalid_grant(&err));
+    }
+
+    #[tokio::test]
+    async fn authorization_code_other_redirect_uri() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let authorization_code = get_authorization_code(&config, user.id, "client-id");
+        let other_redirect_uri =
+            Url::parse("https://oauth-redirect.googleusercontent.com/r/other-project-id").unwrap();
+        for redirect_uri in [Some(other_redirect_uri), None] {
+            let err = super::handle(
+                config.clone(),
+                store.clone(),
+                get_authorization_code_request(&authorization_code, redirect_uri),
+            )
+            .await
+            .unwrap_err();
+            assert!(is_invalid_grant(&err));
+        }
+
+        // Rejected requests must not use up the code
+        super::handle(
+            config,
+            store,
+            get_authorization_code_request(&authorization_code, Some(get_redirect_uri())),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn refresh_token() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = RefreshToken::new(
+            config.get().secrets.refresh_key.as_bytes(),
+            RefreshTokenClaims::new(user.id, Client::GoogleHome, None),
+        )
+        .unwrap();
+        let Json(response) = super::handle(
+            config.clone(),
+            store.clone(),
+            get_refresh_token_request(&refresh_token),
+        )
+        .await
+        .unwrap();
+        let access_token = AccessToken::decode(
+            config.get().secrets.access_key.as_bytes(),
+            &response.access_token,
+        )
+        .unwrap();
+        assert_eq!(access_token.sub, user.id);
+        let rotated_refresh_token = RefreshToken::decode(
+            config.get().secrets.refresh_key.as_bytes(),
+            &response.refresh_token.unwrap(),
+        )
+        .unwrap();
+        assert_eq!(rotated_refresh_token.client, Client::GoogleHome);
+        assert_eq!(rotated_refresh_token.family, refresh_token.family);
+        assert_eq!(rotated_refresh_token.exp, None);
+
+        // Reuse of the old token revokes the token it was rotated into
+        for refresh_token in [refresh_token, rotated_refresh_token] {
+            let err = super::handle(
+                config.clone(),
+                store.clone(),
+                get_refresh_token_request(&refresh_token),
+            )
+            .await
+            .unwrap_err();
+            assert!(is_invalid_grant(&err));
+        }
+    }
+
+    #[tokio::test]
+    async fn refresh_token_other_client() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = RefreshToken::new(
+            config.get().secrets.refresh_key.as_bytes(),
+            RefreshTokenClaims::new(user.id, Client::Internal, None),
+        )
+        .unwrap();
+        let err = super::handle(
+            config.clone(),
+            store.clone(),
+            get_refresh_token_request(&refresh_token),
+        )
+        .await
+        .unwrap_err();
+        assert!(is_invalid_grant(&err));
+        assert!(!store
+            .is_refresh_token_revoked(&refresh_token.jti)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn invalid_client_secret() {
+        let config = get_config(GetConfig {
+            google_home: Some(get_google_home()),
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let err = super::handle(
+            config,
+            store,
+            Form(TokenRequest {
+                client_id: String::from("client-id"),
+                client_secret: String::from("invalid-client-secret"),
+                grant: TokenRequestGrant::RefreshToken {
+                    refresh_token: String::from("refresh-token"),
+                },
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ServerError::OAuthError(OAuthError::InvalidClient(_))
+        ));
+    }
+}
@@ -1,35 +1,100 @@
 use crate::extensions;
 use crate::extractors::RefreshToken;
+use crate::store;
 use axum::Json;
-use chrono::Duration;
 use chrono::Utc;
 use houseflow_types::auth::token::Request;
 use houseflow_types::auth::token::Response;
+use houseflow_types::client::Client;
+use houseflow_types::errors::AuthError;
 use houseflow_types::errors::ServerError;
+use houseflow_types::errors::TokenError;
+use houseflow_types::token;
 use houseflow_types::token::AccessToken;
 use houseflow_types::token::AccessTokenClaims;
+use houseflow_types::token::RefreshTokenClaims;
 use tracing::Level;
 
-#[tracing::instrument(name = "Refresh token", skip(config, _request), err)]
+/// Checks if the refresh token has been revoked, either on its own, with its family,
+/// or with all sessions of the user
+pub(crate) async fn is_revoked(
+    store: &dyn store::Store,
+    claims: &RefreshTokenClaims,
+) -> Result<bool, store::Error> {
+    Ok(store.is_refresh_token_revoked(&claims.jti).await?
+        || is_session_revoked(store, claims).await?)
+}
+
+/// Revokes the refresh token and returns claims of the token that replaces it, or None if the token
+/// can no longer be used.
+///
+/// Reuse of a token that has been already rotated or revoked revokes its whole family, as the token
+/// might have been stolen.
+pub(crate) async fn rotate(
+    store: &dyn store::Store,
+    claims: &RefreshTokenClaims,
+) -> Result<Option<RefreshTokenClaims>, store::Error> {
+    if is_session_revoked(store, claims).await? {
+        return Ok(None);
+    }
+    if !store.revoke_refresh_token(&claims.jti).await? {
+        store.revoke_refresh_token_family(&claims.family).await?;
+        tracing::warn!(user_id = %claims.sub, family = %claims.family, "Refresh token reused");
+        return Ok(None);
+    }
+    Ok(Some(claims.rotate()))
+}
+
+/// Checks if the refresh token has been revoked with its family, or with all sessions of the user
+async fn is_session_revoked(
+    store: &dyn store::Store,
+    claims: &RefreshTokenClaims,
+) -> Result<bool, store::Error> {
+    if store
+        .is_refresh_token_family_revoked(&claims.family)
+        .await?
+    {
+        return Ok(true);
+    }
+    let revoked_at = store
+        .get_user_refresh_tokens_revoked_at(&claims.sub)
+        .await?;
+    Ok(revoked_at.map_or(false, |revoked_at| claims.iat < revoked_at))
+}
+
+#[tracing::instrument(name = "Refresh token", skip(config, store, _request), err)]
 pub async fn handle(
     config: extensions::Config,
+    store: extensions::Store,
     RefreshToken(refresh_token): RefreshToken,
     Json(_request): Json<Request>,
 ) -> Result<Json<Response>, ServerError> {
-    let access_token_payload = AccessTokenClaims {
-        sub: refresh_token.claims.sub,
-        exp: Utc::now() + Duration::minutes(10),
-    };
+    let user_id = refresh_token.sub;
+    let client = Client::Internal;
+    if refresh_token.client != client {
+        return Err(AuthError::InvalidToken(TokenError {
+            description: String::from("refresh token was issued to another client"),
+        })
+        .into());
+    }
+    let rotated_claims = rotate(&**store, &refresh_token.claims)
+        .await?
+        .ok_or(AuthError::RefreshTokenBlacklisted)?;
+    let rotated_refresh_token =
+        token::RefreshToken::new(config.get().secrets.refresh_key.as_bytes(), rotated_claims)?;
     let access_token = AccessToken::new(
         config.get().secrets.access_key.as_bytes(),
-        access_token_payload,
+        AccessTokenClaims {
+            sub: user_id,
+            exp: Utc::now() + client.access_token_duration(),
+        },
     )?;
 
-    tracing::event!(Level::INFO, user_id = %refresh_token.claims.sub, "Refreshed token");
+    tracing::event!(Level::INFO, %user_id, "Refreshed token");
 
     Ok(Json(Response {
-        refresh_token: None,
-        access_token: access_token.to_string(),
+        refresh_token: Some(rotated_refresh_token.encode()),
+        access_token: access_token.encode(),
     }))
 }
 
@@ -37,6 +102,9 @@ pub async fn handle(
 mod tests {
     use crate::test_utils::*;
     use axum::Json;
+    use houseflow_types::client::Client;
+    use houseflow_types::errors::AuthError;
+    use houseflow_types::errors::ServerError;
     use houseflow_types::token::RefreshToken;
     use houseflow_types::token::RefreshTokenClaims;
 
@@ -48,16 +116,15 @@ mod tests {
             ..Default::default()
         })
         .await;
+        let store = get_store(&config).await;
         let refresh_token = RefreshToken::new(
             config.get().secrets.refresh_key.as_bytes(),
-            RefreshTokenClaims {
-                sub: user.id,
-                exp: None,
-            },
+            RefreshTokenClaims::new(user.id, Client::Internal, None),
         )
         .unwrap();
         let Json(response) = super::handle(
             config.clone(),
+            store,
             crate::extractors::RefreshToken(refresh_token.clone()),
             Json(super::Request {}),
         )
@@ -69,5 +136,127 @@ mod tests {
         )
         .unwrap();
         assert_eq!(access_token.claims.sub, refresh_token.sub);
+        let rotated_refresh_token = RefreshToken::decode(
+            config.get().secrets.refresh_key.as_bytes(),
+            &response.refresh_token.unwrap(),
+        )
+        .unwrap();
+        assert_eq!(rotated_refresh_token.sub, refresh_token.sub);
+        assert_eq!(rotated_refresh_token.family, refresh_token.family);
+        assert_ne!(rotated_refresh_token.jti, refresh_token.jti);
+        assert!(rotated_refresh_token.exp.is_some());
+    }
+
+    #[tokio::test]
+    async fn reused() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = RefreshToken::new(
+            config.get().secrets.refresh_key.as_bytes(),
+            RefreshTokenClaims::new(user.id, Client::Internal, None),
+        )
+        .unwrap();
+        let refresh = |refresh_token: RefreshToken| {
+            super::handle(
+                config.clone(),
+                store.clone(),
+                crate::extractors::RefreshToken(refresh_token),
+                Json(super::Request {}),
+            )
+        };
+        let Json(response) = refresh(refresh_token.clone()).await.unwrap();
+        let rotated_refresh_token = RefreshToken::decode(
+            config.get().secrets.refresh_key.as_bytes(),
+            &response.refresh_token.unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            refresh(refresh_token).await.unwrap_err(),
+            ServerError::AuthError(AuthError::RefreshTokenBlacklisted)
+        );
+        // Reuse of the old token revokes the token it was rotated into
+        assert_eq!(
+            refresh(rotated_refresh_token).await.unwrap_err(),
+            ServerError::AuthError(AuthError::RefreshTokenBlacklisted)
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = RefreshToken::new(
+            config.get().secrets.refresh_key.as_bytes(),
+            RefreshTokenClaims::new(user.id, Client::Internal, None),
+        )
+        .unwrap();
+        let refresh = || {
+            super::handle(
+                config.clone(),
+                store.clone(),
+                crate::extractors::RefreshToken(refresh_token.clone()),
+                Json(super::Request {}),
+            )
+        };
+        let (first, second) = tokio::join!(refresh(), refresh());
+        assert_eq!(
+            [&first, &second]
+                .iter()
+                .filter(|result| result.is_ok())
+                .count(),
+            1
+        );
+        for result in [first, second] {
+            if let Err(err) = result {
+                assert_eq!(
+                    err,
+                    ServerError::AuthError(AuthError::RefreshTokenBlacklisted)
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn other_client() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let refresh_token = RefreshToken::new(
+            config.get().secrets.refresh_key.as_bytes(),
+            RefreshTokenClaims::new(user.id, Client::GoogleHome, None),
+        )
+        .unwrap();
+        let err = super::handle(
+            config.clone(),
+            store.clone(),
+            crate::extractors::RefreshToken(refresh_token.clone()),
+            Json(super::Request {}),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ServerError::AuthError(AuthError::InvalidToken(_))
+        ));
+        // Token of the other client remains usable
+        assert!(!store
+            .is_refresh_token_revoked(&refresh_token.jti)
+            .await
+            .unwrap());
     }
 }
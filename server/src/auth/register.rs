@@ -0,0 +1,315 @@
+use super::login::send_verification_code;
+use super::login::verify_verification_code;
+use super::login::VERIFICATION_CODE_DURATION;
+use crate::extensions;
+use crate::extractors::AcceptLanguage;
+use crate::mailer::Purpose;
+use axum::Json;
+use chrono::Utc;
+use houseflow_types::auth::register::Request;
+use houseflow_types::auth::register::Response;
+use houseflow_types::errors::AuthError;
+use houseflow_types::errors::ServerError;
+use houseflow_types::user;
+use houseflow_types::user::User;
+use tracing::Level;
+
+#[tracing::instrument(
+    name = "Register",
+    skip(config, store, clerk, mailer, limiter, request),
+    fields(
+        email = %request.email,
+    ),
+    err,
+)]
+pub async fn handle(
+    config: extensions::Config,
+    store: extensions::Store,
+    clerk: extensions::Clerk,
+    mailer: extensions::MasterMailer,
+    limiter: extensions::RateLimiter,
+    AcceptLanguage(locale): AcceptLanguage,
+    Json(request): Json<Request>,
+) -> Result<Json<Response>, ServerError> {
+    validator::Validate::validate(&request)?;
+    let rate_limits = config.get().rate_limits.clone();
+    limiter.check_account(&request.email, &rate_limits.account)?;
+    if store.get_user_by_email(&request.email).await?.is_some() {
+        return Err(AuthError::UserAlreadyExists.into());
+    }
+
+    let response = match request.verification_code {
+        Some(verification_code) => {
+            let user = store
+                .get_pending_user_by_email(&request.email)
+                .await?
+                .ok_or_else(|| {
+                    AuthError::InvalidVerificationCode("no pending registration".to_string())
+                })?;
+            verify_verification_code(&clerk, &limiter, &rate_limits, &user, &verification_code)
+                .await?;
+            store.add_user(&user).await?;
+            store.remove_pending_user(&user.id).await?;
+            clerk.remove(&verification_code).await?;
+            tracing::event!(Level::INFO, user_id = %user.id, "Registered");
+            Response::Registered { user_id: user.id }
+        }
+        None => {
+            // The user is created once the registration is confirmed with the verification code.
+            // Registering again keeps the ID, so that the codes sent before remain valid
+            let user = match store.get_pending_user_by_email(&request.email).await? {
+                Some(pending_user) => User {
+                    username: request.username,
+                    ..pending_user
+                },
+                None => User {
+                    id: user::ID::new_v4(),
+                    username: request.username,
+                    email: request.email,
+                    admin: false,
+                },
+            };
+            // Pending registration can't be confirmed after the verification code expires
+            let expires_at =
+                Utc::now() + chrono::Duration::from_std(VERIFICATION_CODE_DURATION).unwrap();
+            store.add_pending_user(&user, expires_at).await?;
+            send_verification_code(&clerk, &mailer, &user, Purpose::Registration, locale).await?;
+            Response::VerificationCodeSent
+        }
+    };
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Request;
+    use super::Response;
+    use crate::extractors::AcceptLanguage;
+    use crate::mailer::Locale;
+    use crate::test_utils::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use axum::Json;
+    use houseflow_types::code::VerificationCode;
+    use houseflow_types::errors::AuthError;
+    use houseflow_types::errors::ServerError;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn valid() {
+        let user = get_user();
+        let (mailer_tx, mut mailer_rx) = mpsc::unbounded_channel();
+        let config = get_config(GetConfig::default()).await;
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer {
+            tx: Some(mailer_tx),
+        })
+        .await;
+        let limiter = get_rate_limiter();
+        let Json(response) = super::handle(
+            config.clone(),
+            store.clone(),
+            clerk.clone(),
+            mailer.clone(),
+            limiter.clone(),
+            AcceptLanguage(Locale::default()),
+            Json(Request {
+                email: user.email.clone(),
+                username: user.username.clone(),
+                verification_code: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response, Response::VerificationCodeSent);
+        assert_eq!(store.get_user_by_email(&user.email).await.unwrap(), None);
+        let (address, verification_code) = mailer_rx.recv().await.unwrap();
+        assert_eq!(address, user.email);
+
+        let Json(response) = super::handle(
+            config,
+            store.clone(),
+            clerk,
+            mailer,
+            limiter,
+            AcceptLanguage(Locale::default()),
+            Json(Request {
+                email: user.email.clone(),
+                username: user.username.clone(),
+                verification_code: Some(verification_code),
+            }),
+        )
+        .await
+        .unwrap();
+        let user_id = match response {
+            Response::Registered { user_id } => user_id,
+            _ => panic!("expected Response::Registered"),
+        };
+        let registered = store.get_user(&user_id).await.unwrap().unwrap();
+        assert_eq!(registered.email, user.email);
+        assert_eq!(registered.username, user.username);
+        assert!(!registered.admin);
+    }
+
+    #[tokio::test]
+    async fn registered_again() {
+        let user = get_user();
+        let (mailer_tx, mut mailer_rx) = mpsc::unbounded_channel();
+        let config = get_config(GetConfig::default()).await;
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer {
+            tx: Some(mailer_tx),
+        })
+        .await;
+        let limiter = get_rate_limiter();
+        let register = |username: &str, verification_code: Option<VerificationCode>| {
+            super::handle(
+                config.clone(),
+                store.clone(),
+                clerk.clone(),
+                mailer.clone(),
+                limiter.clone(),
+                AcceptLanguage(Locale::default()),
+                Json(Request {
+                    email: user.email.clone(),
+                    username: String::from(username),
+                    verification_code,
+                }),
+            )
+        };
+        register("john", None).await.unwrap();
+        let (_, first_verification_code) = mailer_rx.recv().await.unwrap();
+        register("johnny", None).await.unwrap();
+        mailer_rx.recv().await.unwrap();
+
+        // Code sent for the first registration confirms the pending user with the latest details
+        let Json(response) = register("johnny", Some(first_verification_code))
+            .await
+            .unwrap();
+        let user_id = match response {
+            Response::Registered { user_id } => user_id,
+            _ => panic!("expected Response::Registered"),
+        };
+        let registered = store.get_user(&user_id).await.unwrap().unwrap();
+        assert_eq!(registered.username, "johnny");
+        assert_eq!(store.get_pending_user(&user_id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn already_exists() {
+        let user = get_user();
+        let config = get_config(GetConfig {
+            users: vec![user.clone()],
+            ..Default::default()
+        })
+        .await;
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer::default()).await;
+        let response = super::handle(
+            config,
+            store,
+            clerk,
+            mailer,
+            get_rate_limiter(),
+            AcceptLanguage(Locale::default()),
+            Json(Request {
+                email: user.email,
+                username: user.username,
+                verification_code: None,
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            response,
+            ServerError::AuthError(AuthError::UserAlreadyExists)
+        );
+    }
+
+    #[tokio::test]
+    async fn verification_code_unknown_by_clerk() {
+        let user = get_user();
+        let config = get_config(GetConfig::default()).await;
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer::default()).await;
+        let verification_code: VerificationCode = rand::random();
+        let response = super::handle(
+            config,
+            store.clone(),
+            clerk,
+            mailer,
+            get_rate_limiter(),
+            AcceptLanguage(Locale::default()),
+            Json(Request {
+                email: user.email.clone(),
+                username: user.username,
+                verification_code: Some(verification_code),
+            }),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(
+            response,
+            ServerError::AuthError(AuthError::InvalidVerificationCode(_))
+        ));
+        assert_eq!(store.get_user_by_email(&user.email).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn locked_after_invalid_codes() {
+        let user = get_user();
+        let (mailer_tx, mut mailer_rx) = mpsc::unbounded_channel();
+        let config = get_config(GetConfig::default()).await;
+        // Let the account rate limit pass all requests, so only the lockout rejects them
+        let mut server_config = (**config.get()).clone();
+        server_config.rate_limits.account.burst = server_config.rate_limits.lockout_attempts + 2;
+        config.update(server_config);
+        let rate_limits = config.get().rate_limits.clone();
+        let store = get_store(&config).await;
+        let clerk = get_clerk(GetClerk::default()).await;
+        let mailer = get_master_mailer(GetMasterMailer {
+            tx: Some(mailer_tx),
+        })
+        .await;
+        let limiter = get_rate_limiter();
+        let register = |verification_code: Option<VerificationCode>| {
+            super::handle(
+                config.clone(),
+                store.clone(),
+                clerk.clone(),
+                mailer.clone(),
+                limiter.clone(),
+                AcceptLanguage(Locale::default()),
+                Json(Request {
+                    email: user.email.clone(),
+                    username: user.username.clone(),
+                    verification_code,
+                }),
+            )
+        };
+        register(None).await.unwrap();
+        let (_, verification_code) = mailer_rx.recv().await.unwrap();
+        for _ in 0..rate_limits.lockout_attempts {
+            let err = register(Some(rand::random())).await.unwrap_err();
+            assert!(matches!(
+                err,
+                ServerError::AuthError(AuthError::InvalidVerificationCode(_))
+            ));
+        }
+
+        // Even the valid code is rejected while the account is locked
+        let response = register(Some(verification_code))
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(store.get_user_by_email(&user.email).await.unwrap(), None);
+    }
+}
@@ -6,17 +6,16 @@ use houseflow_types::auth::whoami::Response;
 use houseflow_types::errors::AuthError;
 use houseflow_types::errors::ServerError;
 
-#[tracing::instrument(name = "Whoami", skip(config, _request), err)]
+#[tracing::instrument(name = "Whoami", skip(store, _request), err)]
 pub async fn handle(
-    config: extensions::Config,
+    store: extensions::Store,
     UserID(user_id): UserID,
     Json(_request): Json<Request>,
 ) -> Result<Json<Response>, ServerError> {
-    let user = config
-        .get()
+    let user = store
         .get_user(&user_id)
-        .ok_or(AuthError::UserNotFound)?
-        .to_owned();
+        .await?
+        .ok_or(AuthError::UserNotFound)?;
 
     tracing::info!(username = %user.username, email = %user.email);
 
@@ -39,8 +38,9 @@ mod tests {
             ..Default::default()
         })
         .await;
+        let store = get_store(&config).await;
         let Json(response) = super::handle(
-            config.clone(),
+            store,
             crate::extractors::UserID(user.id),
             Json(super::Request {}),
         )
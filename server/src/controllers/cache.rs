@@ -0,0 +1,225 @@
+use super::Message;
+use super::Name;
+use anyhow::Error;
+use chrono::Utc;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::CharacteristicName;
+use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+use houseflow_types::meta::AccessoryState;
+use houseflow_types::meta::CharacteristicState;
+use std::collections::HashMap;
+use tokio::sync::oneshot;
+
+#[derive(Debug)]
+pub enum CacheMessage {
+    GetCharacteristic {
+        accessory_id: accessory::ID,
+        service_name: ServiceName,
+        characteristic_name: CharacteristicName,
+        respond_to: oneshot::Sender<Option<CharacteristicState>>,
+    },
+    GetAccessories {
+        respond_to: oneshot::Sender<Vec<AccessoryState>>,
+    },
+    Message(Message),
+}
+
+impl acu::Message for CacheMessage {}
+
+pub type Handle = acu::Handle<CacheMessage, Name>;
+
+pub fn new() -> Handle {
+    let (sender, receiver) = acu::channel(Name::Cache);
+    let mut actor = CacheController {
+        receiver,
+        accessories: Default::default(),
+    };
+    let handle = Handle { sender };
+    tokio::spawn(async move { actor.run().await });
+    handle
+}
+
+struct CachedAccessory {
+    accessory: Accessory,
+    characteristics: HashMap<(ServiceName, CharacteristicName), CharacteristicState>,
+}
+
+/// Keeps the last known values of characteristics of the connected accessories
+pub struct CacheController {
+    receiver: acu::Receiver<CacheMessage, Name>,
+    accessories: HashMap<accessory::ID, CachedAccessory>,
+}
+
+impl CacheController {
+    async fn run(&mut self) -> Result<(), Error> {
+        while let Some(message) = self.receiver.recv().await {
+            self.handle_message(message).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, message: CacheMessage) -> Result<(), Error> {
+        match message {
+            CacheMessage::GetCharacteristic {
+                accessory_id,
+                service_name,
+                characteristic_name,
+                respond_to,
+            } => {
+                let state = self.accessories.get(&accessory_id).and_then(|cached| {
+                    cached
+                        .characteristics
+                        .get(&(service_name, characteristic_name))
+                        .cloned()
+                });
+                respond_to.send(state).ok();
+            }
+            CacheMessage::GetAccessories { respond_to } => {
+                let accessories = self
+                    .accessories
+                    .values()
+                    .map(|cached| AccessoryState {
+                        accessory: cached.accessory.clone(),
+                        characteristics: cached.characteristics.values().cloned().collect(),
+                    })
+                    .collect();
+                respond_to.send(accessories).ok();
+            }
+            CacheMessage::Message(Message::Connected { accessory }) => {
+                self.accessories.insert(
+                    accessory.id,
+                    CachedAccessory {
+                        accessory,
+                        characteristics: Default::default(),
+                    },
+                );
+            }
+            CacheMessage::Message(Message::Disconnected { accessory_id }) => {
+                self.accessories.remove(&accessory_id);
+            }
+            CacheMessage::Message(Message::Updated {
+                accessory_id,
+                service_name,
+                characteristic,
+            }) => {
+                if let Some(cached) = self.accessories.get_mut(&accessory_id) {
+                    let characteristic_name = CharacteristicName::from(&characteristic);
+                    cached.characteristics.insert(
+                        (service_name, characteristic_name),
+                        CharacteristicState {
+                            service_name,
+                            characteristic,
+                            updated_at: Utc::now(),
+                        },
+                    );
+                }
+            }
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use houseflow_types::accessory::characteristics;
+    use houseflow_types::accessory::characteristics::Characteristic;
+    use houseflow_types::accessory::manufacturers::Houseflow;
+
+    async fn get_characteristic(
+        handle: &Handle,
+        accessory_id: accessory::ID,
+    ) -> Option<Characteristic> {
+        handle
+            .sender
+            .call_with(|respond_to| CacheMessage::GetCharacteristic {
+                accessory_id,
+                service_name: ServiceName::Light,
+                characteristic_name: CharacteristicName::On,
+                respond_to,
+            })
+            .await
+            .map(|state| state.characteristic)
+    }
+
+    #[tokio::test]
+    async fn updated() {
+        let handle = new();
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Ceiling light"),
+            room_name: String::from("Bedroom"),
+            r#type: accessory::Type::Houseflow(Houseflow::Lightbulb),
+        };
+        let characteristic = Characteristic::On(characteristics::On { on: true });
+        handle
+            .sender
+            .notify(CacheMessage::Message(Message::Connected {
+                accessory: accessory.clone(),
+            }))
+            .await;
+        assert_eq!(get_characteristic(&handle, accessory.id).await, None);
+
+        handle
+            .sender
+            .notify(CacheMessage::Message(Message::Updated {
+                accessory_id: accessory.id,
+                service_name: ServiceName::Light,
+                characteristic: characteristic.clone(),
+            }))
+            .await;
+        assert_eq!(
+            get_characteristic(&handle, accessory.id).await,
+            Some(characteristic)
+        );
+        let accessories = handle
+            .sender
+            .call_with(|respond_to| CacheMessage::GetAccessories { respond_to })
+            .await;
+        assert_eq!(accessories.len(), 1);
+        assert_eq!(accessories[0].accessory, accessory);
+        assert_eq!(accessories[0].characteristics.len(), 1);
+
+        handle
+            .sender
+            .notify(CacheMessage::Message(Message::Disconnected {
+                accessory_id: accessory.id,
+            }))
+            .await;
+        assert_eq!(get_characteristic(&handle, accessory.id).await, None);
+    }
+
+    #[tokio::test]
+    async fn written() {
+        let handle = new();
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Ceiling light"),
+            room_name: String::from("Bedroom"),
+            r#type: accessory::Type::Houseflow(Houseflow::Lightbulb),
+        };
+        handle
+            .sender
+            .notify(CacheMessage::Message(Message::Connected {
+                accessory: accessory.clone(),
+            }))
+            .await;
+        for on in [true, false] {
+            // Successful write is reported to the cache as an update by the meta controller
+            let characteristic = Characteristic::On(characteristics::On { on });
+            handle
+                .sender
+                .notify(CacheMessage::Message(Message::Updated {
+                    accessory_id: accessory.id,
+                    service_name: ServiceName::Light,
+                    characteristic: characteristic.clone(),
+                }))
+                .await;
+            assert_eq!(
+                get_characteristic(&handle, accessory.id).await,
+                Some(characteristic)
+            );
+        }
+    }
+}
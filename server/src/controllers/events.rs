@@ -0,0 +1,277 @@
+use super::Message;
+use super::Name;
+use crate::providers;
+use crate::providers::ProviderExt;
+use crate::store;
+use anyhow::Error;
+use chrono::DateTime;
+use chrono::Utc;
+use houseflow_types::accessory;
+use houseflow_types::meta;
+use houseflow_types::structure;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::oneshot;
+
+/// Number of the most recent events kept for clients resuming the stream
+const REPLAY_BUFFER_SIZE: usize = 1024;
+
+/// Number of the low bits of event IDs that hold the number of the event since the startup
+const EVENT_COUNTER_BITS: u32 = 20;
+
+pub type EventID = u64;
+
+/// Returns ID of the first event sent after starting at the specified time.
+///
+/// The startup time in milliseconds is kept in the high bits, so that IDs are not reused after a
+/// restart, and IDs sent before the restart are lower than all of the new ones.
+fn first_event_id(started_at: DateTime<Utc>) -> EventID {
+    (started_at.timestamp_millis().max(0) as EventID) << EVENT_COUNTER_BITS
+}
+
+/// Event together with the structure of the accessory it concerns
+#[derive(Debug, Clone)]
+pub struct StructureEvent {
+    pub id: EventID,
+    pub structure_id: structure::ID,
+    pub event: meta::Event,
+}
+
+#[derive(Debug)]
+pub struct Subscription {
+    /// Buffered events that were sent after the last event seen by the client
+    pub replay: Vec<StructureEvent>,
+    /// Whether the client has missed events that are no longer buffered, or were sent before a
+    /// restart, so it has to fetch the state again
+    pub resync: bool,
+    pub receiver: broadcast::Receiver<StructureEvent>,
+}
+
+#[derive(Debug)]
+pub enum EventsMessage {
+    Subscribe {
+        last_event_id: Option<EventID>,
+        respond_to: oneshot::Sender<Subscription>,
+    },
+    Message(Message),
+}
+
+impl acu::Message for EventsMessage {}
+
+pub type Handle = acu::Handle<EventsMessage, Name>;
+
+pub fn new(store: Arc<dyn store::Store>, master_provider: providers::MasterHandle) -> Handle {
+    let (sender, receiver) = acu::channel(Name::Events);
+    let first_event_id = first_event_id(Utc::now());
+    let mut actor = EventsController {
+        receiver,
+        store,
+        master_provider,
+        structures: Default::default(),
+        first_event_id,
+        next_event_id: first_event_id,
+        buffer: Default::default(),
+        sender: broadcast::channel(REPLAY_BUFFER_SIZE).0,
+    };
+    let handle = Handle { sender };
+    tokio::spawn(async move { actor.run().await });
+    handle
+}
+
+/// Assigns events to structures and broadcasts them to the subscribed clients
+pub struct EventsController {
+    receiver: acu::Receiver<EventsMessage, Name>,
+    store: Arc<dyn store::Store>,
+    master_provider: providers::MasterHandle,
+    /// Structures of the connected accessories
+    structures: HashMap<accessory::ID, structure::ID>,
+    first_event_id: EventID,
+    next_event_id: EventID,
+    buffer: VecDeque<StructureEvent>,
+    sender: broadcast::Sender<StructureEvent>,
+}
+
+impl EventsController {
+    async fn run(&mut self) -> Result<(), Error> {
+        while let Some(message) = self.receiver.recv().await {
+            self.handle_message(message).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, message: EventsMessage) -> Result<(), Error> {
+        match message {
+            EventsMessage::Subscribe {
+                last_event_id,
+                respond_to,
+            } => {
+                let replay = replay(
+                    &self.buffer,
+                    self.first_event_id..self.next_event_id,
+                    last_event_id,
+                );
+                let subscription = Subscription {
+                    resync: replay.is_none(),
+                    replay: replay.unwrap_or_default(),
+                    receiver: self.sender.subscribe(),
+                };
+                respond_to.send(subscription).ok();
+            }
+            EventsMessage::Message(Message::Connected { accessory }) => {
+                let structure_id = match self.resolve_structure(accessory.id).await {
+                    Ok(Some(structure_id)) => structure_id,
+                    Err(err) => {
+                        tracing::error!(
+                            accessory_id = %accessory.id,
+                            "resolving structure: {}",
+                            err
+                        );
+                        return Ok(());
+                    }
+                    Ok(None) => {
+                        tracing::debug!(
+                            accessory_id = %accessory.id,
+                            "accessory is not assigned to any structure"
+                        );
+                        return Ok(());
+                    }
+                };
+                self.structures.insert(accessory.id, structure_id);
+                self.publish(structure_id, meta::Event::AccessoryConnected(accessory));
+            }
+            EventsMessage::Message(Message::Disconnected { accessory_id }) => {
+                if let Some(structure_id) = self.structures.remove(&accessory_id) {
+                    self.publish(
+                        structure_id,
+                        meta::Event::AccessoryDisconnected { accessory_id },
+                    );
+                }
+            }
+            EventsMessage::Message(Message::Updated {
+                accessory_id,
+                service_name,
+                characteristic,
+            }) => {
+                if let Some(structure_id) = self.structures.get(&accessory_id).copied() {
+                    self.publish(
+                        structure_id,
+                        meta::Event::CharacteristicUpdated(meta::CharacteristicUpdated {
+                            accessory_id,
+                            service_name,
+                            characteristic,
+                        }),
+                    );
+                }
+            }
+        };
+        Ok(())
+    }
+
+    async fn resolve_structure(
+        &self,
+        accessory_id: accessory::ID,
+    ) -> Result<Option<structure::ID>, store::Error> {
+        let hub_id = match self.master_provider.get_hub_id(accessory_id).await {
+            Some(hub_id) => hub_id,
+            None => return Ok(None),
+        };
+        let hub = self.store.get_hub(&hub_id).await?;
+        Ok(hub.map(|hub| hub.structure_id))
+    }
+
+    fn publish(&mut self, structure_id: structure::ID, event: meta::Event) {
+        let event = StructureEvent {
+            id: self.next_event_id,
+            structure_id,
+            event,
+        };
+        self.next_event_id += 1;
+        if self.buffer.len() == REPLAY_BUFFER_SIZE {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(event.clone());
+        // Sending fails only if there are no subscribers
+        self.sender.send(event).ok();
+    }
+}
+
+/// Returns buffered events that were sent after the specified event.
+///
+/// Returns `None` if some of them are no longer buffered, or the event is not one of the `sent`,
+/// e.g. because it was sent before a restart.
+fn replay(
+    buffer: &VecDeque<StructureEvent>,
+    sent: Range<EventID>,
+    last_event_id: Option<EventID>,
+) -> Option<Vec<StructureEvent>> {
+    let last_event_id = match last_event_id {
+        Some(last_event_id) => last_event_id,
+        None => return Some(Vec::new()),
+    };
+    if !sent.contains(&last_event_id) {
+        return None;
+    }
+    let first_buffered = buffer.front().map_or(sent.end, |event| event.id);
+    if last_event_id + 1 < first_buffered {
+        return None;
+    }
+    let events = buffer
+        .iter()
+        .filter(|event| event.id > last_event_id)
+        .cloned()
+        .collect();
+    Some(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: EventID) -> StructureEvent {
+        StructureEvent {
+            id,
+            structure_id: structure::ID::new_v4(),
+            event: meta::Event::AccessoryDisconnected {
+                accessory_id: accessory::ID::new_v4(),
+            },
+        }
+    }
+
+    #[test]
+    fn replay_after_last_event() {
+        let buffer = (0..5).map(event).collect::<VecDeque<_>>();
+        let ids = |events: Option<Vec<StructureEvent>>| {
+            events.map(|events| events.iter().map(|event| event.id).collect::<Vec<_>>())
+        };
+        assert_eq!(ids(replay(&buffer, 0..5, None)), Some(vec![]));
+        assert_eq!(ids(replay(&buffer, 0..5, Some(2))), Some(vec![3, 4]));
+        assert_eq!(ids(replay(&buffer, 0..5, Some(4))), Some(vec![]));
+    }
+
+    #[test]
+    fn replay_evicted() {
+        let buffer = (3..5).map(event).collect::<VecDeque<_>>();
+        assert!(replay(&buffer, 0..5, Some(2)).is_some());
+        assert!(replay(&buffer, 0..5, Some(1)).is_none());
+    }
+
+    #[test]
+    fn replay_after_restart() {
+        let started_at = Utc::now();
+        let previous = first_event_id(started_at - chrono::Duration::seconds(1));
+        let first = first_event_id(started_at);
+        assert!(previous + 1000 < first);
+
+        // Clients that have seen events from before the restart, or unknown events, must resync
+        let buffer = (first..first + 3).map(event).collect::<VecDeque<_>>();
+        assert!(replay(&buffer, first..first + 3, Some(previous + 1000)).is_none());
+        assert!(replay(&buffer, first..first + 3, Some(first + 3)).is_none());
+        assert_eq!(
+            replay(&buffer, first..first + 3, Some(first)).map(|events| events.len()),
+            Some(2)
+        );
+    }
+}
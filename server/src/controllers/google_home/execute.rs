@@ -0,0 +1,430 @@
+use super::error_code;
+use super::heating_cooling_mode;
+use super::query::update_state;
+use crate::controllers::check_write_permission;
+use crate::providers;
+use crate::providers::ProviderExt;
+use crate::store;
+use futures::future::join_all;
+use google_smart_home::device::commands;
+use google_smart_home::device::Command;
+use google_smart_home::execute::request;
+use google_smart_home::execute::response;
+use google_smart_home::query::response::State;
+use houseflow_config::server::Config;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics;
+use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+use houseflow_types::errors::ControllerError;
+use houseflow_types::errors::ServerError;
+use houseflow_types::user;
+
+pub async fn handle(
+    config: &Config,
+    store: &dyn store::Store,
+    master_provider: &providers::MasterHandle,
+    user_id: &user::ID,
+    request_id: String,
+    accessories: Vec<Accessory>,
+    payload: request::Payload,
+) -> response::Response {
+    let accessories = &accessories;
+    let futures = payload.commands.iter().flat_map(|command| {
+        command.devices.iter().map(move |device| async move {
+            let accessory = accessories
+                .iter()
+                .find(|accessory| accessory.id.to_string() == device.id);
+            match accessory {
+                Some(accessory) => {
+                    let permission = check_write_permission(
+                        config,
+                        store,
+                        master_provider,
+                        user_id,
+                        accessory.id,
+                    )
+                    .await;
+                    match permission {
+                        Ok(()) => {
+                            execute_accessory(master_provider, accessory, &command.execution).await
+                        }
+                        Err(err) => permission_error(accessory, err),
+                    }
+                }
+                None => response::PayloadCommand {
+                    ids: vec![device.id.clone()],
+                    status: response::PayloadCommandStatus::Error,
+                    states: Default::default(),
+                    error_code: Some(String::from("deviceNotFound")),
+                },
+            }
+        })
+    });
+    let commands = join_all(futures).await;
+
+    response::Response {
+        request_id,
+        payload: response::Payload {
+            error_code: None,
+            debug_string: None,
+            commands,
+        },
+    }
+}
+
+/// Rejects the command for the accessory that the user is not allowed to write to
+fn permission_error(accessory: &Accessory, error: ServerError) -> response::PayloadCommand {
+    let (status, error_code) = match error {
+        ServerError::ControllerError(ControllerError::AccessoryNotConnected) => {
+            (response::PayloadCommandStatus::Offline, "deviceOffline")
+        }
+        _ => (response::PayloadCommandStatus::Error, "authFailure"),
+    };
+    response::PayloadCommand {
+        ids: vec![accessory.id.to_string()],
+        status,
+        states: Default::default(),
+        error_code: Some(String::from(error_code)),
+    }
+}
+
+async fn execute_accessory(
+    master_provider: &providers::MasterHandle,
+    accessory: &Accessory,
+    execution: &[request::PayloadCommandExecution],
+) -> response::PayloadCommand {
+    let services = accessory.r#type.services();
+    let mut state = State {
+        online: true,
+        ..Default::default()
+    };
+    for request::PayloadCommandExecution { command } in execution {
+        let writes = match command_characteristics(&services, command) {
+            Some(writes) => writes,
+            None => {
+                return response::PayloadCommand {
+                    ids: vec![accessory.id.to_string()],
+                    status: response::PayloadCommandStatus::Error,
+                    states: Default::default(),
+                    error_code: Some(String::from("functionNotSupported")),
+                }
+            }
+        };
+        for (service_name, characteristic) in writes {
+            let result = master_provider
+                .write_characteristic(accessory.id, service_name, characteristic.clone())
+                .await;
+            if let Err(err) = result {
+                let status = match err {
+                    accessory::Error::NotConnected => response::PayloadCommandStatus::Offline,
+                    _ => response::PayloadCommandStatus::Error,
+                };
+                return response::PayloadCommand {
+                    ids: vec![accessory.id.to_string()],
+                    status,
+                    states: Default::default(),
+                    error_code: Some(error_code(&err)),
+                };
+            }
+            update_state(&mut state, characteristic);
+        }
+    }
+
+    let states = match serde_json::to_value(state) {
+        Ok(serde_json::Value::Object(states)) => states,
+        _ => Default::default(),
+    };
+    response::PayloadCommand {
+        ids: vec![accessory.id.to_string()],
+        status: response::PayloadCommandStatus::Success,
+        states,
+        error_code: None,
+    }
+}
+
+/// Services that can be turned on and off with the OnOff trait
+const ON_OFF_SERVICES: [ServiceName; 4] = [
+    ServiceName::Light,
+    ServiceName::Switch,
+    ServiceName::Outlet,
+    ServiceName::Fan,
+];
+
+/// Returns the characteristic writes that fulfill the command, if any of the services supports it
+fn command_characteristics(
+    services: &[ServiceName],
+    command: &Command,
+) -> Option<Vec<(ServiceName, Characteristic)>> {
+    let supports = |service_name: ServiceName| services.contains(&service_name);
+    let characteristics = match command {
+        Command::OnOff(commands::OnOff { on }) => {
+            let service_name = ON_OFF_SERVICES
+                .into_iter()
+                .find(|service_name| supports(*service_name))?;
+            vec![(
+                service_name,
+                Characteristic::On(characteristics::On { on: *on }),
+            )]
+        }
+        Command::BrightnessAbsolute(commands::BrightnessAbsolute { brightness })
+            if supports(ServiceName::Light) =>
+        {
+            vec![(
+                ServiceName::Light,
+                Characteristic::Brightness(characteristics::Brightness {
+                    percentage: *brightness,
+                }),
+            )]
+        }
+        Command::ColorAbsolute(commands::ColorAbsolute { color })
+            if supports(ServiceName::Light) =>
+        {
+            let (hue, saturation) = match color.value {
+                commands::ColorValue::Temperature { temperature } => {
+                    return Some(vec![(
+                        ServiceName::Light,
+                        Characteristic::ColorTemperature(characteristics::ColorTemperature {
+                            kelvin: temperature,
+                        }),
+                    )])
+                }
+                commands::ColorValue::Rgb { spectrum_rgb } => rgb_hue_saturation(spectrum_rgb),
+                commands::ColorValue::Hsv { ref spectrum_hsv } => {
+                    (spectrum_hsv.hue, spectrum_hsv.saturation)
+                }
+            };
+            vec![
+                (
+                    ServiceName::Light,
+                    Characteristic::Hue(characteristics::Hue { hue: hue as f32 }),
+                ),
+                (
+                    ServiceName::Light,
+                    Characteristic::Saturation(characteristics::Saturation {
+                        saturation: (saturation * 100.0) as f32,
+                    }),
+                ),
+            ]
+        }
+        Command::SetFanSpeed(commands::SetFanSpeed::Percent { fan_speed_percent })
+            if supports(ServiceName::Fan) =>
+        {
+            vec![(
+                ServiceName::Fan,
+                Characteristic::RotationSpeed(characteristics::RotationSpeed {
+                    percentage: *fan_speed_percent,
+                }),
+            )]
+        }
+        Command::OpenClose(commands::OpenClose { open_percent })
+            if supports(ServiceName::GarageDoorOpener) =>
+        {
+            vec![(
+                ServiceName::GarageDoorOpener,
+                Characteristic::TargetDoorState(characteristics::TargetDoorState {
+                    open_percent: *open_percent,
+                }),
+            )]
+        }
+        Command::ThermostatTemperatureSetpoint(commands::ThermostatTemperatureSetpoint {
+            thermostat_temperature_setpoint,
+        }) if supports(ServiceName::Thermostat) => vec![(
+            ServiceName::Thermostat,
+            Characteristic::TargetTemperature(characteristics::TargetTemperature {
+                temperature: *thermostat_temperature_setpoint as f32,
+            }),
+        )],
+        Command::ThermostatSetMode(commands::ThermostatSetMode { thermostat_mode })
+            if supports(ServiceName::Thermostat) =>
+        {
+            vec![(
+                ServiceName::Thermostat,
+                Characteristic::TargetHeatingCoolingState(
+                    characteristics::TargetHeatingCoolingState {
+                        state: heating_cooling_mode(thermostat_mode)?,
+                    },
+                ),
+            )]
+        }
+        _ => return None,
+    };
+    Some(characteristics)
+}
+
+/// Converts the 0xRRGGBB color to hue in degrees and saturation from 0 to 1
+fn rgb_hue_saturation(rgb: u32) -> (f64, f64) {
+    let [red, green, blue] = [16, 8, 0].map(|shift| ((rgb >> shift) & 0xFF) as f64 / 255.0);
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == red {
+        60.0 * ((green - blue) / delta).rem_euclid(6.0)
+    } else if max == green {
+        60.0 * ((blue - red) / delta + 2.0)
+    } else {
+        60.0 * ((red - green) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue, saturation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_off() {
+        let command = Command::OnOff(commands::OnOff { on: true });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Light], &command),
+            Some(vec![(
+                ServiceName::Light,
+                Characteristic::On(characteristics::On { on: true })
+            )])
+        );
+        assert_eq!(
+            command_characteristics(&[ServiceName::GarageDoorOpener], &command),
+            None
+        );
+    }
+
+    #[test]
+    fn open_close() {
+        let command = Command::OpenClose(commands::OpenClose { open_percent: 100 });
+        assert_eq!(
+            command_characteristics(&[ServiceName::GarageDoorOpener], &command),
+            Some(vec![(
+                ServiceName::GarageDoorOpener,
+                Characteristic::TargetDoorState(characteristics::TargetDoorState {
+                    open_percent: 100
+                })
+            )])
+        );
+    }
+
+    #[test]
+    fn thermostat() {
+        let command = Command::ThermostatSetMode(commands::ThermostatSetMode {
+            thermostat_mode: String::from("heat"),
+        });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Thermostat], &command),
+            Some(vec![(
+                ServiceName::Thermostat,
+                Characteristic::TargetHeatingCoolingState(
+                    characteristics::TargetHeatingCoolingState {
+                        state: characteristics::HeatingCoolingMode::Heat
+                    }
+                )
+            )])
+        );
+        let command = Command::ThermostatSetMode(commands::ThermostatSetMode {
+            thermostat_mode: String::from("auto"),
+        });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Thermostat], &command),
+            Some(vec![(
+                ServiceName::Thermostat,
+                Characteristic::TargetHeatingCoolingState(
+                    characteristics::TargetHeatingCoolingState {
+                        state: characteristics::HeatingCoolingMode::Auto
+                    }
+                )
+            )])
+        );
+        let command = Command::ThermostatSetMode(commands::ThermostatSetMode {
+            thermostat_mode: String::from("eco"),
+        });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Thermostat], &command),
+            None
+        );
+    }
+
+    #[test]
+    fn color_absolute() {
+        let command = Command::ColorAbsolute(commands::ColorAbsolute {
+            color: commands::Color {
+                name: None,
+                value: commands::ColorValue::Rgb {
+                    spectrum_rgb: 0x00FF00,
+                },
+            },
+        });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Light], &command),
+            Some(vec![
+                (
+                    ServiceName::Light,
+                    Characteristic::Hue(characteristics::Hue { hue: 120.0 })
+                ),
+                (
+                    ServiceName::Light,
+                    Characteristic::Saturation(characteristics::Saturation { saturation: 100.0 })
+                ),
+            ])
+        );
+        let command = Command::ColorAbsolute(commands::ColorAbsolute {
+            color: commands::Color {
+                name: None,
+                value: commands::ColorValue::Temperature { temperature: 2700 },
+            },
+        });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Light], &command),
+            Some(vec![(
+                ServiceName::Light,
+                Characteristic::ColorTemperature(characteristics::ColorTemperature {
+                    kelvin: 2700
+                })
+            )])
+        );
+    }
+
+    #[test]
+    fn fan() {
+        let command = Command::OnOff(commands::OnOff { on: false });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Fan], &command),
+            Some(vec![(
+                ServiceName::Fan,
+                Characteristic::On(characteristics::On { on: false })
+            )])
+        );
+        let command = Command::SetFanSpeed(commands::SetFanSpeed::Percent {
+            fan_speed_percent: 40,
+        });
+        assert_eq!(
+            command_characteristics(&[ServiceName::Fan], &command),
+            Some(vec![(
+                ServiceName::Fan,
+                Characteristic::RotationSpeed(characteristics::RotationSpeed { percentage: 40 })
+            )])
+        );
+        let command = Command::SetFanSpeed(commands::SetFanSpeed::Setting {
+            fan_speed: String::from("high"),
+        });
+        assert_eq!(command_characteristics(&[ServiceName::Fan], &command), None);
+    }
+
+    #[test]
+    fn permission_error() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Front door"),
+            room_name: String::from("Hall"),
+            r#type: accessory::Type::Houseflow(accessory::manufacturers::Houseflow::Lock),
+        };
+        let command = super::permission_error(
+            &accessory,
+            houseflow_types::errors::AuthError::NoAccessoryPermission.into(),
+        );
+        assert_eq!(command.ids, vec![accessory.id.to_string()]);
+        assert_eq!(command.status, response::PayloadCommandStatus::Error);
+        assert_eq!(command.error_code.as_deref(), Some("authFailure"));
+    }
+}
@@ -0,0 +1,233 @@
+pub mod execute;
+pub mod query;
+pub mod sync;
+
+use super::get_accessory_permission;
+use super::Message;
+use super::Name;
+use crate::extensions;
+use crate::extractors::UserID;
+use crate::providers;
+use anyhow::Error;
+use axum::extract::Extension;
+use axum::Json;
+use futures::future::join_all;
+use google_smart_home::Request;
+use google_smart_home::RequestInput;
+use google_smart_home::Response;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::HeatingCoolingMode;
+use houseflow_types::accessory::characteristics::HeatingCoolingState;
+use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+use houseflow_types::errors::ServerError;
+use std::collections::HashMap;
+use tokio::sync::oneshot;
+
+#[derive(Debug)]
+pub enum GoogleHomeMessage {
+    GetAccessories {
+        respond_to: oneshot::Sender<Vec<Accessory>>,
+    },
+    Message(Message),
+}
+
+impl acu::Message for GoogleHomeMessage {}
+
+pub type Handle = acu::Handle<GoogleHomeMessage, Name>;
+
+pub fn new() -> Handle {
+    let (sender, receiver) = acu::channel(Name::GoogleHome);
+    let mut actor = GoogleHomeController {
+        receiver,
+        accessories: Default::default(),
+    };
+    let handle = Handle { sender };
+    tokio::spawn(async move { actor.run().await });
+    handle
+}
+
+pub struct GoogleHomeController {
+    receiver: acu::Receiver<GoogleHomeMessage, Name>,
+    accessories: HashMap<accessory::ID, Accessory>,
+}
+
+impl GoogleHomeController {
+    async fn run(&mut self) -> Result<(), Error> {
+        while let Some(message) = self.receiver.recv().await {
+            self.handle_message(message).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, message: GoogleHomeMessage) -> Result<(), Error> {
+        match message {
+            GoogleHomeMessage::GetAccessories { respond_to } => {
+                let accessories = self.accessories.values().cloned().collect();
+                respond_to.send(accessories).ok();
+            }
+            GoogleHomeMessage::Message(Message::Connected { accessory }) => {
+                self.accessories.insert(accessory.id, accessory);
+            }
+            GoogleHomeMessage::Message(Message::Disconnected { accessory_id }) => {
+                self.accessories.remove(&accessory_id);
+            }
+            GoogleHomeMessage::Message(Message::Updated {
+                accessory_id: _,
+                service_name: _,
+                characteristic: _,
+            }) => {}
+        };
+        Ok(())
+    }
+}
+
+pub fn app(handle: Handle) -> axum::Router {
+    use axum::routing::post;
+
+    axum::Router::new()
+        .route("/fulfillment", post(fulfillment))
+        .layer(Extension(handle))
+}
+
+/// Maps accessory error to one of the error codes recognized by Google Home
+fn error_code(error: &accessory::Error) -> String {
+    let code = match error {
+        accessory::Error::NotConnected => "deviceOffline",
+        accessory::Error::CharacteristicReadOnly
+        | accessory::Error::CharacteristicWriteOnly
+        | accessory::Error::CharacteristicNotSupported
+        | accessory::Error::ServiceNotSupported => "functionNotSupported",
+        _ => "hardError",
+    };
+    code.to_string()
+}
+
+/// Maps the heating/cooling mode to the thermostat mode of the TemperatureSetting trait
+fn thermostat_mode(mode: HeatingCoolingMode) -> &'static str {
+    match mode {
+        HeatingCoolingMode::Off => "off",
+        HeatingCoolingMode::Heat => "heat",
+        HeatingCoolingMode::Cool => "cool",
+        HeatingCoolingMode::Auto => "auto",
+    }
+}
+
+fn heating_cooling_mode(thermostat_mode: &str) -> Option<HeatingCoolingMode> {
+    let mode = match thermostat_mode {
+        "off" => HeatingCoolingMode::Off,
+        "heat" => HeatingCoolingMode::Heat,
+        "cool" => HeatingCoolingMode::Cool,
+        "auto" => HeatingCoolingMode::Auto,
+        _ => return None,
+    };
+    Some(mode)
+}
+
+/// Maps the heating/cooling state to the active thermostat mode of the TemperatureSetting trait
+fn active_thermostat_mode(state: HeatingCoolingState) -> &'static str {
+    match state {
+        HeatingCoolingState::Off => "none",
+        HeatingCoolingState::Heat => "heat",
+        HeatingCoolingState::Cool => "cool",
+    }
+}
+
+/// Sensor reported through the SensorState trait, with its descriptive states
+struct Sensor {
+    name: &'static str,
+    detected: &'static str,
+    not_detected: &'static str,
+}
+
+impl Sensor {
+    fn available_states(&self) -> Vec<String> {
+        vec![String::from(self.detected), String::from(self.not_detected)]
+    }
+
+    fn state(&self, detected: bool) -> &'static str {
+        if detected {
+            self.detected
+        } else {
+            self.not_detected
+        }
+    }
+}
+
+/// Maps the binary sensor service to the sensor of the SensorState trait
+fn sensor(service_name: ServiceName) -> Option<Sensor> {
+    let (name, detected, not_detected) = match service_name {
+        ServiceName::MotionSensor => ("Motion", "motion detected", "no motion detected"),
+        // Contact is detected when the door or window is closed
+        ServiceName::ContactSensor => ("Contact", "closed", "open"),
+        ServiceName::LeakSensor => ("WaterLeak", "leak", "no leak"),
+        ServiceName::SmokeSensor => ("SmokeLevel", "smoke detected", "no smoke detected"),
+        ServiceName::OccupancySensor => ("Occupancy", "occupied", "unoccupied"),
+        _ => return None,
+    };
+    Some(Sensor {
+        name,
+        detected,
+        not_detected,
+    })
+}
+
+#[tracing::instrument(
+    name = "Fulfillment",
+    skip(config, store, handle, master_provider, request),
+    err
+)]
+pub async fn fulfillment(
+    config: extensions::Config,
+    Extension(store): extensions::Store,
+    Extension(handle): Extension<Handle>,
+    Extension(master_provider): Extension<providers::MasterHandle>,
+    UserID(user_id): UserID,
+    Json(request): Json<Request>,
+) -> Result<Json<Response>, ServerError> {
+    let Request { request_id, inputs } = request;
+    let input = inputs.into_iter().next().ok_or_else(|| {
+        ServerError::ValidationError(String::from("request must contain at least one input"))
+    })?;
+    let accessories = handle
+        .sender
+        .call_with(|respond_to| GoogleHomeMessage::GetAccessories { respond_to })
+        .await;
+    let accessories = {
+        let futures = accessories.into_iter().map(|accessory| async {
+            get_accessory_permission(&*store, &master_provider, &user_id, accessory.id)
+                .await
+                .ok()
+                .map(|_| accessory)
+        });
+        join_all(futures)
+            .await
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+    };
+
+    let response = match input {
+        RequestInput::Sync => Response::Sync(sync::handle(user_id, request_id, accessories)),
+        RequestInput::Query(payload) => {
+            Response::Query(query::handle(&master_provider, request_id, accessories, payload).await)
+        }
+        RequestInput::Execute(payload) => Response::Execute(
+            execute::handle(
+                &config.get(),
+                &*store,
+                &master_provider,
+                &user_id,
+                request_id,
+                accessories,
+                payload,
+            )
+            .await,
+        ),
+        RequestInput::Disconnect => {
+            tracing::info!(%user_id, "user has unlinked Google Home");
+            Response::Disconnect
+        }
+    };
+    Ok(Json(response))
+}
@@ -0,0 +1,363 @@
+use super::active_thermostat_mode;
+use super::error_code;
+use super::sensor;
+use super::thermostat_mode;
+use crate::providers;
+use crate::providers::ProviderExt;
+use futures::future::join_all;
+use google_smart_home::query::request;
+use google_smart_home::query::response;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics;
+use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::accessory::characteristics::CharacteristicName;
+use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+
+pub async fn handle(
+    master_provider: &providers::MasterHandle,
+    request_id: String,
+    accessories: Vec<Accessory>,
+    payload: request::Payload,
+) -> response::Response {
+    let accessories = &accessories;
+    let futures = payload.devices.into_iter().map(|device| async move {
+        let accessory = accessories
+            .iter()
+            .find(|accessory| accessory.id.to_string() == device.id);
+        let state = match accessory {
+            Some(accessory) => query_accessory(master_provider, accessory).await,
+            None => response::PayloadDevice {
+                status: response::PayloadDeviceStatus::Error,
+                error_code: Some(String::from("deviceNotFound")),
+                state: Default::default(),
+            },
+        };
+        (device.id, state)
+    });
+    let devices = join_all(futures).await.into_iter().collect();
+
+    response::Response {
+        request_id,
+        payload: response::Payload {
+            error_code: None,
+            debug_string: None,
+            devices,
+        },
+    }
+}
+
+async fn query_accessory(
+    master_provider: &providers::MasterHandle,
+    accessory: &Accessory,
+) -> response::PayloadDevice {
+    let mut state = response::State {
+        online: true,
+        ..Default::default()
+    };
+    for service_name in accessory.r#type.services() {
+        for characteristic_name in state_characteristics(&service_name) {
+            let result = master_provider
+                .read_characteristic(accessory.id, service_name, *characteristic_name)
+                .await;
+            match result {
+                Ok(characteristic) => update_state(&mut state, characteristic),
+                // Optional characteristics of the service, e.g. color of a light
+                Err(accessory::Error::CharacteristicNotSupported) => continue,
+                Err(accessory::Error::NotConnected) => {
+                    return response::PayloadDevice {
+                        status: response::PayloadDeviceStatus::Offline,
+                        error_code: Some(error_code(&accessory::Error::NotConnected)),
+                        state: Default::default(),
+                    }
+                }
+                Err(err) => {
+                    return response::PayloadDevice {
+                        status: response::PayloadDeviceStatus::Error,
+                        error_code: Some(error_code(&err)),
+                        state,
+                    }
+                }
+            }
+        }
+    }
+
+    response::PayloadDevice {
+        status: response::PayloadDeviceStatus::Success,
+        error_code: None,
+        state,
+    }
+}
+
+/// Characteristics that must be read to report state of the service
+fn state_characteristics(service_name: &ServiceName) -> &'static [CharacteristicName] {
+    match service_name {
+        // Color temperature is read first, so the HSV color takes precedence if supported
+        ServiceName::Light => &[
+            CharacteristicName::On,
+            CharacteristicName::Brightness,
+            CharacteristicName::ColorTemperature,
+            CharacteristicName::Hue,
+            CharacteristicName::Saturation,
+        ],
+        ServiceName::Switch | ServiceName::Outlet => &[CharacteristicName::On],
+        ServiceName::Fan => &[CharacteristicName::On, CharacteristicName::RotationSpeed],
+        ServiceName::GarageDoorOpener => &[CharacteristicName::CurrentDoorState],
+        ServiceName::TemperatureSensor => &[CharacteristicName::CurrentTemperature],
+        ServiceName::HumiditySensor => &[CharacteristicName::CurrentHumidity],
+        ServiceName::Battery => &[],
+        ServiceName::LockMechanism => &[],
+        ServiceName::MotionSensor => &[CharacteristicName::MotionDetected],
+        ServiceName::ContactSensor => &[CharacteristicName::ContactSensorState],
+        ServiceName::LeakSensor => &[CharacteristicName::LeakDetected],
+        ServiceName::SmokeSensor => &[CharacteristicName::SmokeDetected],
+        ServiceName::OccupancySensor => &[CharacteristicName::OccupancyDetected],
+        ServiceName::Thermostat => &[
+            CharacteristicName::CurrentTemperature,
+            CharacteristicName::TargetTemperature,
+            CharacteristicName::CurrentHeatingCoolingState,
+            CharacteristicName::TargetHeatingCoolingState,
+        ],
+    }
+}
+
+pub(super) fn update_state(state: &mut response::State, characteristic: Characteristic) {
+    match characteristic {
+        Characteristic::On(characteristics::On { on }) => state.on = Some(on),
+        Characteristic::Brightness(characteristics::Brightness { percentage }) => {
+            state.brightness = Some(percentage)
+        }
+        Characteristic::ColorTemperature(characteristics::ColorTemperature { kelvin }) => {
+            state.color = Some(response::Color::TemperatureK(kelvin.into()))
+        }
+        Characteristic::Hue(characteristics::Hue { hue }) => {
+            let (_, saturation) = color_hue_saturation(state);
+            state.color = Some(spectrum_hsv(hue.into(), saturation));
+        }
+        Characteristic::Saturation(characteristics::Saturation { saturation }) => {
+            let (hue, _) = color_hue_saturation(state);
+            state.color = Some(spectrum_hsv(hue, f64::from(saturation) / 100.0));
+        }
+        Characteristic::RotationSpeed(characteristics::RotationSpeed { percentage }) => {
+            state.current_fan_speed_percent = Some(percentage)
+        }
+        Characteristic::CurrentDoorState(characteristics::CurrentDoorState { open_percent })
+        | Characteristic::TargetDoorState(characteristics::TargetDoorState { open_percent }) => {
+            state.open_percent = Some(open_percent)
+        }
+        Characteristic::CurrentTemperature(characteristics::CurrentTemperature { temperature }) => {
+            // Sensors without a target state are reported as turned off thermostats
+            if state.thermostat_mode.is_none() {
+                state.thermostat_mode = Some(String::from("off"));
+            }
+            state.thermostat_temperature_ambient = Some(temperature.into());
+        }
+        Characteristic::TargetTemperature(characteristics::TargetTemperature { temperature }) => {
+            state.thermostat_temperature_setpoint = Some(temperature.into())
+        }
+        Characteristic::CurrentHeatingCoolingState(
+            characteristics::CurrentHeatingCoolingState {
+                state: heating_cooling_state,
+            },
+        ) => {
+            state.active_thermostat_mode =
+                Some(String::from(active_thermostat_mode(heating_cooling_state)))
+        }
+        Characteristic::TargetHeatingCoolingState(characteristics::TargetHeatingCoolingState {
+            state: heating_cooling_mode,
+        }) => state.thermostat_mode = Some(String::from(thermostat_mode(heating_cooling_mode))),
+        Characteristic::CurrentHumidity(characteristics::CurrentHumidity { humidity }) => {
+            state.thermostat_humidity_ambient = Some(humidity.into())
+        }
+        Characteristic::MotionDetected(characteristics::MotionDetected { detected }) => {
+            update_sensor_state(state, ServiceName::MotionSensor, detected)
+        }
+        Characteristic::ContactSensorState(characteristics::ContactSensorState { contact }) => {
+            update_sensor_state(state, ServiceName::ContactSensor, contact)
+        }
+        Characteristic::LeakDetected(characteristics::LeakDetected { detected }) => {
+            update_sensor_state(state, ServiceName::LeakSensor, detected)
+        }
+        Characteristic::SmokeDetected(characteristics::SmokeDetected { detected }) => {
+            update_sensor_state(state, ServiceName::SmokeSensor, detected)
+        }
+        Characteristic::OccupancyDetected(characteristics::OccupancyDetected { detected }) => {
+            update_sensor_state(state, ServiceName::OccupancySensor, detected)
+        }
+        _ => {}
+    }
+}
+
+fn update_sensor_state(state: &mut response::State, service_name: ServiceName, detected: bool) {
+    let sensor = match sensor(service_name) {
+        Some(sensor) => sensor,
+        None => return,
+    };
+    let sensor_states = state.current_sensor_state_data.get_or_insert_with(Vec::new);
+    sensor_states.retain(|sensor_state| sensor_state.name != sensor.name);
+    sensor_states.push(response::CurrentSensorState {
+        name: String::from(sensor.name),
+        current_sensor_state: Some(String::from(sensor.state(detected))),
+        raw_value: None,
+    });
+}
+
+/// Hue and saturation of the HSV color reported so far, defaults to fully saturated red
+fn color_hue_saturation(state: &response::State) -> (f64, f64) {
+    match state.color {
+        Some(response::Color::SpectrumHsv {
+            hue, saturation, ..
+        }) => (hue, saturation),
+        _ => (0.0, 1.0),
+    }
+}
+
+fn spectrum_hsv(hue: f64, saturation: f64) -> response::Color {
+    // Brightness of the light is reported separately
+    response::Color::SpectrumHsv {
+        hue,
+        saturation,
+        value: 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use houseflow_types::accessory::characteristics::HeatingCoolingMode;
+    use houseflow_types::accessory::characteristics::HeatingCoolingState;
+
+    #[test]
+    fn door_state() {
+        let mut state = response::State::default();
+        update_state(
+            &mut state,
+            Characteristic::CurrentDoorState(characteristics::CurrentDoorState {
+                open_percent: 100,
+            }),
+        );
+        assert_eq!(state.open_percent, Some(100));
+    }
+
+    #[test]
+    fn temperature_and_humidity() {
+        let mut state = response::State::default();
+        update_state(
+            &mut state,
+            Characteristic::CurrentTemperature(characteristics::CurrentTemperature {
+                temperature: 21.5,
+            }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::CurrentHumidity(characteristics::CurrentHumidity { humidity: 40.0 }),
+        );
+        assert_eq!(state.thermostat_mode.as_deref(), Some("off"));
+        assert_eq!(state.thermostat_temperature_ambient, Some(21.5));
+        assert_eq!(state.thermostat_humidity_ambient, Some(40.0));
+    }
+
+    #[test]
+    fn thermostat() {
+        let mut state = response::State::default();
+        update_state(
+            &mut state,
+            Characteristic::TargetHeatingCoolingState(characteristics::TargetHeatingCoolingState {
+                state: HeatingCoolingMode::Heat,
+            }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::CurrentTemperature(characteristics::CurrentTemperature {
+                temperature: 19.0,
+            }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::TargetTemperature(characteristics::TargetTemperature {
+                temperature: 21.0,
+            }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::CurrentHeatingCoolingState(
+                characteristics::CurrentHeatingCoolingState {
+                    state: HeatingCoolingState::Off,
+                },
+            ),
+        );
+        assert_eq!(state.thermostat_mode.as_deref(), Some("heat"));
+        assert_eq!(state.active_thermostat_mode.as_deref(), Some("none"));
+        assert_eq!(state.thermostat_temperature_ambient, Some(19.0));
+        assert_eq!(state.thermostat_temperature_setpoint, Some(21.0));
+
+        update_state(
+            &mut state,
+            Characteristic::TargetHeatingCoolingState(characteristics::TargetHeatingCoolingState {
+                state: HeatingCoolingMode::Auto,
+            }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::CurrentHeatingCoolingState(
+                characteristics::CurrentHeatingCoolingState {
+                    state: HeatingCoolingState::Cool,
+                },
+            ),
+        );
+        assert_eq!(state.thermostat_mode.as_deref(), Some("auto"));
+        assert_eq!(state.active_thermostat_mode.as_deref(), Some("cool"));
+    }
+
+    #[test]
+    fn color() {
+        let mut state = response::State::default();
+        update_state(
+            &mut state,
+            Characteristic::Brightness(characteristics::Brightness { percentage: 80 }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::ColorTemperature(characteristics::ColorTemperature { kelvin: 2700 }),
+        );
+        assert_eq!(state.brightness, Some(80));
+        assert_eq!(state.color, Some(response::Color::TemperatureK(2700)));
+        update_state(
+            &mut state,
+            Characteristic::Hue(characteristics::Hue { hue: 120.0 }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::Saturation(characteristics::Saturation { saturation: 50.0 }),
+        );
+        assert_eq!(
+            state.color,
+            Some(response::Color::SpectrumHsv {
+                hue: 120.0,
+                saturation: 0.5,
+                value: 1.0,
+            })
+        );
+    }
+
+    #[test]
+    fn sensor_state() {
+        let mut state = response::State::default();
+        update_state(
+            &mut state,
+            Characteristic::MotionDetected(characteristics::MotionDetected { detected: true }),
+        );
+        update_state(
+            &mut state,
+            Characteristic::MotionDetected(characteristics::MotionDetected { detected: false }),
+        );
+        assert_eq!(
+            state.current_sensor_state_data,
+            Some(vec![response::CurrentSensorState {
+                name: String::from("Motion"),
+                current_sensor_state: Some(String::from("no motion detected")),
+                raw_value: None,
+            }])
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use super::sensor;
+use super::thermostat_mode;
+use google_smart_home::device;
+use google_smart_home::sync::response;
+use houseflow_types::accessory;
+use houseflow_types::accessory::characteristics::HeatingCoolingMode;
+use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+use houseflow_types::user;
+
+/// Range of color temperatures supported by HomeKit lights, 500 to 140 mireds
+const COLOR_TEMPERATURE_MIN_K: u64 = 2000;
+const COLOR_TEMPERATURE_MAX_K: u64 = 7000;
+
+pub fn handle(
+    user_id: user::ID,
+    request_id: String,
+    accessories: Vec<Accessory>,
+) -> response::Response {
+    let devices = accessories.into_iter().filter_map(device).collect();
+    response::Response {
+        request_id,
+        payload: response::Payload {
+            agent_user_id: user_id.to_string(),
+            error_code: None,
+            debug_string: None,
+            devices,
+        },
+    }
+}
+
+/// Returns SYNC representation of the accessory, or None if it can't be represented in Google Home
+pub fn device(accessory: Accessory) -> Option<response::PayloadDevice> {
+    let device_type = device_type(&accessory.r#type)?;
+    let services = accessory.r#type.services();
+    let mut traits = Vec::new();
+    for service_name in &services {
+        for device_trait in device_traits(service_name) {
+            if !traits.contains(device_trait) {
+                traits.push(device_trait.clone());
+            }
+        }
+    }
+    let (manufacturer, model) = manufacturer_and_model(&accessory.r#type);
+
+    Some(response::PayloadDevice {
+        id: accessory.id.to_string(),
+        device_type,
+        traits,
+        name: response::PayloadDeviceName {
+            default_names: None,
+            name: accessory.name,
+            nicknames: None,
+        },
+        will_report_state: false,
+        notification_supported_by_agent: false,
+        room_hint: Some(accessory.room_name),
+        device_info: Some(response::PayloadDeviceInfo {
+            manufacturer,
+            model,
+            hw_version: None,
+            sw_version: None,
+        }),
+        attributes: attributes(&services),
+        custom_data: None,
+        other_device_ids: None,
+    })
+}
+
+pub fn device_type(accessory_type: &accessory::Type) -> Option<device::Type> {
+    use accessory::manufacturers::Houseflow;
+    use accessory::manufacturers::XiaomiMijia;
+
+    let device_type = match accessory_type {
+        accessory::Type::XiaomiMijia(XiaomiMijia::HygroThermometer) => device::Type::Thermostat,
+        accessory::Type::Houseflow(Houseflow::Gate) => device::Type::Gate,
+        accessory::Type::Houseflow(Houseflow::Garage) => device::Type::Garage,
+        accessory::Type::Houseflow(Houseflow::Lightbulb) => device::Type::Light,
+        accessory::Type::Houseflow(Houseflow::Thermostat) => device::Type::Thermostat,
+        accessory::Type::Houseflow(Houseflow::MotionSensor)
+        | accessory::Type::Houseflow(Houseflow::ContactSensor)
+        | accessory::Type::Houseflow(Houseflow::LeakSensor)
+        | accessory::Type::Houseflow(Houseflow::OccupancySensor) => device::Type::Sensor,
+        accessory::Type::Houseflow(Houseflow::SmokeSensor) => device::Type::SmokeDetector,
+        accessory::Type::Houseflow(Houseflow::Switch) => device::Type::Switch,
+        accessory::Type::Houseflow(Houseflow::Outlet) => device::Type::Outlet,
+        accessory::Type::Houseflow(Houseflow::Fan) => device::Type::Fan,
+        _ => return None,
+    };
+    Some(device_type)
+}
+
+pub fn device_traits(service_name: &ServiceName) -> &'static [device::Trait] {
+    match service_name {
+        ServiceName::Light => &[
+            device::Trait::OnOff,
+            device::Trait::Brightness,
+            device::Trait::ColorSetting,
+        ],
+        ServiceName::Switch | ServiceName::Outlet => &[device::Trait::OnOff],
+        ServiceName::Fan => &[device::Trait::OnOff, device::Trait::FanSpeed],
+        ServiceName::GarageDoorOpener => &[device::Trait::OpenClose],
+        ServiceName::TemperatureSensor => &[device::Trait::TemperatureSetting],
+        ServiceName::Thermostat => &[device::Trait::TemperatureSetting],
+        // Ambient humidity is reported through the TemperatureSetting trait
+        ServiceName::HumiditySensor => &[],
+        ServiceName::Battery => &[],
+        ServiceName::LockMechanism => &[],
+        ServiceName::MotionSensor
+        | ServiceName::ContactSensor
+        | ServiceName::LeakSensor
+        | ServiceName::SmokeSensor
+        | ServiceName::OccupancySensor => &[device::Trait::SensorState],
+    }
+}
+
+fn attributes(services: &[ServiceName]) -> response::Attributes {
+    let mut attributes = response::Attributes::default();
+    for service_name in services {
+        match service_name {
+            ServiceName::Light => {
+                attributes.color_model = Some(response::ColorModel::Hsv);
+                attributes.color_temperature_range = Some(response::ColorTemperatureRange {
+                    temperature_min_k: COLOR_TEMPERATURE_MIN_K,
+                    temperature_max_k: COLOR_TEMPERATURE_MAX_K,
+                });
+            }
+            ServiceName::Fan => {
+                attributes.supports_fan_speed_percent = Some(true);
+            }
+            ServiceName::GarageDoorOpener => {
+                attributes.discrete_only_open_close = Some(true);
+            }
+            ServiceName::TemperatureSensor => {
+                attributes.available_thermostat_modes = Some(vec![String::from("off")]);
+                attributes.query_only_temperature_setting = Some(true);
+                attributes.thermostat_temperature_unit =
+                    Some(response::ThermostatTemperatureUnit::C);
+            }
+            ServiceName::Thermostat => {
+                let modes = [
+                    HeatingCoolingMode::Off,
+                    HeatingCoolingMode::Heat,
+                    HeatingCoolingMode::Cool,
+                    HeatingCoolingMode::Auto,
+                ];
+                attributes.available_thermostat_modes = Some(
+                    modes
+                        .into_iter()
+                        .map(|mode| String::from(thermostat_mode(mode)))
+                        .collect(),
+                );
+                attributes.thermostat_temperature_unit =
+                    Some(response::ThermostatTemperatureUnit::C);
+            }
+            service_name => {
+                if let Some(sensor) = sensor(*service_name) {
+                    let sensor_state = response::SensorStateSupported {
+                        name: String::from(sensor.name),
+                        descriptive_capabilities: Some(
+                            response::SensorStateDescriptiveCapabilities {
+                                available_states: sensor.available_states(),
+                            },
+                        ),
+                    };
+                    attributes
+                        .sensor_states_supported
+                        .get_or_insert_with(Vec::new)
+                        .push(sensor_state);
+                }
+            }
+        }
+    }
+    attributes
+}
+
+fn manufacturer_and_model(accessory_type: &accessory::Type) -> (Option<String>, Option<String>) {
+    let value = serde_json::to_value(accessory_type).unwrap_or_default();
+    let field = |name: &str| value.get(name).and_then(|v| v.as_str()).map(String::from);
+    (field("manufacturer"), field("model"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use accessory::manufacturers::Houseflow;
+    use accessory::manufacturers::XiaomiMijia;
+
+    #[test]
+    fn lightbulb() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Ceiling light"),
+            room_name: String::from("Bedroom"),
+            r#type: accessory::Type::Houseflow(Houseflow::Lightbulb),
+        };
+        let device = device(accessory.clone()).unwrap();
+        assert_eq!(device.id, accessory.id.to_string());
+        assert_eq!(device.device_type, device::Type::Light);
+        assert_eq!(
+            device.traits,
+            vec![
+                device::Trait::OnOff,
+                device::Trait::Brightness,
+                device::Trait::ColorSetting
+            ]
+        );
+        assert_eq!(
+            device.attributes.color_model,
+            Some(response::ColorModel::Hsv)
+        );
+        assert_eq!(device.name.name, accessory.name);
+        assert_eq!(device.room_hint, Some(accessory.room_name));
+        let device_info = device.device_info.unwrap();
+        assert_eq!(device_info.manufacturer.as_deref(), Some("houseflow"));
+        assert_eq!(device_info.model.as_deref(), Some("lightbulb"));
+    }
+
+    #[test]
+    fn garage() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Garage"),
+            room_name: String::from("Garage"),
+            r#type: accessory::Type::Houseflow(Houseflow::Garage),
+        };
+        let device = device(accessory).unwrap();
+        assert_eq!(device.device_type, device::Type::Garage);
+        assert_eq!(device.traits, vec![device::Trait::OpenClose]);
+        assert_eq!(device.attributes.discrete_only_open_close, Some(true));
+    }
+
+    #[test]
+    fn hygro_thermometer() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Thermometer"),
+            room_name: String::from("Living room"),
+            r#type: accessory::Type::XiaomiMijia(XiaomiMijia::HygroThermometer),
+        };
+        let device = device(accessory).unwrap();
+        assert_eq!(device.device_type, device::Type::Thermostat);
+        assert_eq!(device.traits, vec![device::Trait::TemperatureSetting]);
+        assert_eq!(device.attributes.query_only_temperature_setting, Some(true));
+        assert_eq!(
+            device.attributes.available_thermostat_modes,
+            Some(vec![String::from("off")])
+        );
+    }
+
+    #[test]
+    fn thermostat() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Heating"),
+            room_name: String::from("Living room"),
+            r#type: accessory::Type::Houseflow(Houseflow::Thermostat),
+        };
+        let device = device(accessory).unwrap();
+        assert_eq!(device.device_type, device::Type::Thermostat);
+        assert_eq!(device.traits, vec![device::Trait::TemperatureSetting]);
+        assert_eq!(device.attributes.query_only_temperature_setting, None);
+        assert_eq!(
+            device.attributes.available_thermostat_modes,
+            Some(vec![
+                String::from("off"),
+                String::from("heat"),
+                String::from("cool"),
+                String::from("auto"),
+            ])
+        );
+    }
+
+    #[test]
+    fn smoke_sensor() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Smoke detector"),
+            room_name: String::from("Kitchen"),
+            r#type: accessory::Type::Houseflow(Houseflow::SmokeSensor),
+        };
+        let device = device(accessory).unwrap();
+        assert_eq!(device.device_type, device::Type::SmokeDetector);
+        assert_eq!(device.traits, vec![device::Trait::SensorState]);
+        assert_eq!(
+            device.attributes.sensor_states_supported,
+            Some(vec![response::SensorStateSupported {
+                name: String::from("SmokeLevel"),
+                descriptive_capabilities: Some(response::SensorStateDescriptiveCapabilities {
+                    available_states: vec![
+                        String::from("smoke detected"),
+                        String::from("no smoke detected"),
+                    ],
+                }),
+            }])
+        );
+    }
+
+    #[test]
+    fn fan() {
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Ceiling fan"),
+            room_name: String::from("Bedroom"),
+            r#type: accessory::Type::Houseflow(Houseflow::Fan),
+        };
+        let device = device(accessory).unwrap();
+        assert_eq!(device.device_type, device::Type::Fan);
+        assert_eq!(
+            device.traits,
+            vec![device::Trait::OnOff, device::Trait::FanSpeed]
+        );
+        assert_eq!(device.attributes.supports_fan_speed_percent, Some(true));
+    }
+}
@@ -1,16 +1,36 @@
 pub use super::Handle;
 
+use super::cache;
+use super::cache::CacheMessage;
+use super::check_write_permission;
+use super::events;
+use super::events::EventsMessage;
+use super::get_accessory_permission;
 use super::Message;
 use super::Name;
+use crate::extensions;
+use crate::extractors::UserID;
 use crate::providers;
 use crate::providers::ProviderExt;
 use acu::MasterExt;
 use anyhow::Error;
+use axum::http::HeaderMap;
+use axum::response::sse;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
 use axum::Json;
+use chrono::Utc;
 use futures::future::join_all;
+use futures::stream;
+use futures::Stream;
+use futures::StreamExt;
+use houseflow_config::defaults;
 use houseflow_types::accessory::characteristics::Characteristic;
+use houseflow_types::errors::AuthError;
 use houseflow_types::errors::ControllerError;
 use houseflow_types::errors::ServerError;
+use houseflow_types::meta;
+use houseflow_types::structure;
 
 pub fn new() -> Handle {
     let (sender, receiver) = acu::channel(Name::Master);
@@ -46,7 +66,7 @@ impl MetaController {
     }
 }
 
-pub fn app(handle: Handle) -> axum::Router {
+pub fn app(handle: Handle, cache: cache::Handle, events: events::Handle) -> axum::Router {
     use axum::routing::get;
     use axum::routing::post;
 
@@ -59,23 +79,63 @@ pub fn app(handle: Handle) -> axum::Router {
             "/characteristic/:accessory_id/:service_name",
             post(write_characteristic),
         )
+        .route("/structure/:structure_id", get(structure_state))
+        .route("/accessories", get(get_accessories))
+        .route("/events", get(stream_events))
         .layer(Extension(handle))
+        .layer(Extension(cache))
+        .layer(Extension(events))
 }
 
 use axum::extract::Extension;
 use axum::extract::Path;
+use axum::extract::Query;
 use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
 
 pub async fn read_characteristic(
+    config: extensions::Config,
+    Extension(store): extensions::Store,
     Extension(master_provider): Extension<providers::MasterHandle>,
+    Extension(cache): Extension<cache::Handle>,
+    UserID(user_id): UserID,
     Path((accessory_id, service_name, characteristic_name)): Path<(
         accessory::ID,
         ServiceName,
         CharacteristicName,
     )>,
+    Query(query): Query<meta::read_characteristic::Query>,
 ) -> Result<Json<Characteristic>, ServerError> {
+    get_accessory_permission(&*store, &master_provider, &user_id, accessory_id).await?;
+    if !query.fresh {
+        let state = cache
+            .sender
+            .call_with(|respond_to| CacheMessage::GetCharacteristic {
+                accessory_id,
+                service_name,
+                characteristic_name,
+                respond_to,
+            })
+            .await;
+        let cache_max_age = config
+            .get()
+            .controllers
+            .meta
+            .as_ref()
+            .map(|meta| meta.cache_max_age)
+            .unwrap_or_else(defaults::cache_max_age);
+        if let Some(state) = state {
+            // Negative age means that the clock went backwards, treat the value as fresh
+            let is_fresh = (Utc::now() - state.updated_at)
+                .to_std()
+                .map(|age| age <= cache_max_age)
+                .unwrap_or(true);
+            if is_fresh {
+                return Ok(Json(state.characteristic));
+            }
+        }
+    }
     let slaves: Vec<providers::Handle> = master_provider.slaves().await;
     let futures = slaves
         .iter()
@@ -88,15 +148,35 @@ pub async fn read_characteristic(
     let characteristic = provider
         .read_characteristic(accessory_id, service_name, characteristic_name)
         .await
-        .map_err(ControllerError::AccessoryError)?;
+        .map_err(ControllerError::from)?;
+    cache
+        .sender
+        .notify(CacheMessage::Message(Message::Updated {
+            accessory_id,
+            service_name,
+            characteristic: characteristic.clone(),
+        }))
+        .await;
     Ok(Json(characteristic))
 }
 
 pub async fn write_characteristic(
+    config: extensions::Config,
+    Extension(store): extensions::Store,
     Extension(master_provider): Extension<providers::MasterHandle>,
+    Extension(cache): Extension<cache::Handle>,
+    UserID(user_id): UserID,
     Path((accessory_id, service_name)): Path<(accessory::ID, ServiceName)>,
     Json(characteristic): Json<Characteristic>,
 ) -> Result<(), ServerError> {
+    check_write_permission(
+        &config.get(),
+        &*store,
+        &master_provider,
+        &user_id,
+        accessory_id,
+    )
+    .await?;
     let slaves: Vec<providers::Handle> = master_provider.slaves().await;
     let futures = slaves
         .iter()
@@ -107,8 +187,141 @@ pub async fn write_characteristic(
         .find_map(|(provider, connected)| if connected { Some(provider) } else { None })
         .ok_or(ControllerError::AccessoryNotConnected)?;
     provider
-        .write_characteristic(accessory_id, service_name, characteristic)
+        .write_characteristic(accessory_id, service_name, characteristic.clone())
         .await
-        .map_err(ControllerError::AccessoryError)?;
+        .map_err(ControllerError::from)?;
+    // Accessory might not report the written value, so reads served from the cache would be stale
+    cache
+        .sender
+        .notify(CacheMessage::Message(Message::Updated {
+            accessory_id,
+            service_name,
+            characteristic,
+        }))
+        .await;
     Ok(())
 }
+
+pub async fn structure_state(
+    Extension(store): extensions::Store,
+    Extension(master_provider): Extension<providers::MasterHandle>,
+    Extension(cache): Extension<cache::Handle>,
+    UserID(user_id): UserID,
+    Path(structure_id): Path<structure::ID>,
+) -> Result<Json<meta::structure_state::Response>, ServerError> {
+    if store
+        .get_permission(&structure_id, &user_id)
+        .await?
+        .is_none()
+    {
+        return Err(AuthError::NoStructurePermission.into());
+    }
+    let accessories = cache
+        .sender
+        .call_with(|respond_to| CacheMessage::GetAccessories { respond_to })
+        .await;
+    let store = &store;
+    let master_provider = &master_provider;
+    let futures = accessories.into_iter().map(|state| async move {
+        let hub_id = match master_provider.get_hub_id(state.accessory.id).await {
+            Some(hub_id) => hub_id,
+            None => return Ok(None),
+        };
+        let hub = store.get_hub(&hub_id).await?;
+        let in_structure = hub.map_or(false, |hub| hub.structure_id == structure_id);
+        Ok::<_, ServerError>(in_structure.then(|| state))
+    });
+    let accessories = join_all(futures)
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(Json(accessories))
+}
+
+pub async fn get_accessories(
+    Extension(store): extensions::Store,
+    Extension(master_provider): Extension<providers::MasterHandle>,
+    UserID(user_id): UserID,
+) -> Result<Json<meta::get_accessories::Response>, ServerError> {
+    let accessories = master_provider.get_accessories().await;
+    let store = &store;
+    let user_id = &user_id;
+    let futures = accessories.into_iter().map(|provided| async move {
+        let hub = match store.get_hub(&provided.hub_id).await? {
+            Some(hub) => hub,
+            None => return Ok(None),
+        };
+        let permission = store.get_permission(&hub.structure_id, user_id).await?;
+        Ok::<_, ServerError>(permission.map(|_| meta::AccessoryInfo {
+            services: provided.accessory.r#type.services(),
+            accessory: provided.accessory,
+            connected: provided.connected,
+        }))
+    });
+    let accessories = join_all(futures)
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(Json(accessories))
+}
+
+pub async fn stream_events(
+    Extension(store): extensions::Store,
+    Extension(events): Extension<events::Handle>,
+    UserID(user_id): UserID,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<sse::Event, axum::Error>>> {
+    // Sent by the clients reconnecting to resume the stream
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let subscription = events
+        .sender
+        .call_with(|respond_to| EventsMessage::Subscribe {
+            last_event_id,
+            respond_to,
+        })
+        .await;
+    let received = stream::unfold(subscription.receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(event) => Some((event, receiver)),
+            // Clients that have fallen behind resume from the replay buffer after reconnecting
+            Err(err) => {
+                tracing::debug!("ending event stream: {}", err);
+                None
+            }
+        }
+    });
+    let resync = subscription
+        .resync
+        .then(|| sse::Event::default().json_data(meta::Event::Resync));
+    let stream = stream::iter(subscription.replay)
+        .chain(received)
+        .filter(move |event| {
+            let store = store.clone();
+            let structure_id = event.structure_id;
+            async move {
+                match store.get_permission(&structure_id, &user_id).await {
+                    Ok(permission) => permission.is_some(),
+                    Err(err) => {
+                        tracing::error!("checking permission: {}", err);
+                        false
+                    }
+                }
+            }
+        })
+        .map(|event| {
+            sse::Event::default()
+                .id(event.id.to_string())
+                .json_data(event.event)
+        });
+    let stream = stream::iter(resync).chain(stream);
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
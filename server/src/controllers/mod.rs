@@ -1,15 +1,30 @@
+pub mod cache;
+pub mod events;
+pub mod google_home;
 pub mod meta;
 
+use crate::providers;
+use crate::providers::ProviderExt;
+use crate::store;
 use async_trait::async_trait;
+use houseflow_config::server::Config;
 use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics::Characteristic;
 use houseflow_types::accessory::services::ServiceName;
 use houseflow_types::accessory::Accessory;
+use houseflow_types::errors::AuthError;
+use houseflow_types::errors::ControllerError;
+use houseflow_types::errors::ServerError;
+use houseflow_types::permission::Permission;
+use houseflow_types::user;
 
 #[derive(Debug, Clone, PartialEq, Eq, strum::Display, strum::IntoStaticStr)]
 pub enum Name {
     Master,
     Meta,
+    GoogleHome,
+    Cache,
+    Events,
 }
 
 impl acu::MasterName for Name {
@@ -79,3 +94,49 @@ impl ControllerExt for Handle {
 }
 
 pub type MasterHandle = acu::BroadcasterMasterHandle<Message, Name>;
+
+/// Resolves the structure of the hub that the accessory is connected through,
+/// and returns permission of the user to that structure.
+pub async fn get_accessory_permission(
+    store: &dyn store::Store,
+    master_provider: &providers::MasterHandle,
+    user_id: &user::ID,
+    accessory_id: accessory::ID,
+) -> Result<Permission, ServerError> {
+    let hub_id = master_provider
+        .get_hub_id(accessory_id)
+        .await
+        .ok_or(ControllerError::AccessoryNotConnected)?;
+    let hub = store
+        .get_hub(&hub_id)
+        .await?
+        .ok_or(AuthError::HubNotFound)?;
+    let permission = store
+        .get_permission(&hub.structure_id, user_id)
+        .await?
+        .ok_or(AuthError::NoAccessoryPermission)?;
+    Ok(permission)
+}
+
+/// Checks that the user is allowed to write characteristics of the accessory,
+/// which may require being a manager of the structure.
+pub async fn check_write_permission(
+    config: &Config,
+    store: &dyn store::Store,
+    master_provider: &providers::MasterHandle,
+    user_id: &user::ID,
+    accessory_id: accessory::ID,
+) -> Result<(), ServerError> {
+    let permission =
+        get_accessory_permission(store, master_provider, user_id, accessory_id).await?;
+    let write_requires_manager = config
+        .controllers
+        .meta
+        .as_ref()
+        .map(|meta| meta.write_requires_manager)
+        .unwrap_or(false);
+    if write_requires_manager && !permission.is_manager {
+        return Err(AuthError::NoAccessoryPermission.into());
+    }
+    Ok(())
+}
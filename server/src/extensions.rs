@@ -5,4 +5,7 @@ use std::sync::Arc;
 
 pub type Config = Extension<dynamic::Config<ServerConfig>>;
 pub type Clerk = Extension<Arc<dyn crate::clerk::Clerk>>;
+pub type Store = Extension<Arc<dyn crate::store::Store>>;
+pub type RateLimiter = Extension<Arc<crate::rate_limiter::RateLimiter>>;
+pub type GoogleJwks = Extension<Arc<crate::auth::google_login::JwksCache>>;
 pub type MasterMailer = Extension<crate::mailer::MasterHandle>;
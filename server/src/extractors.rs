@@ -1,4 +1,5 @@
 use crate::extensions;
+use crate::mailer::Locale;
 use async_trait::async_trait;
 use axum::body::Body;
 use axum::http;
@@ -85,3 +86,23 @@ impl axum::extract::FromRequest<Body> for AccessToken {
         ))
     }
 }
+
+/// Locale preferred by the user, picked from the `Accept-Language` header
+pub struct AcceptLanguage(pub Locale);
+
+#[async_trait]
+impl axum::extract::FromRequest<Body> for AcceptLanguage {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request(
+        req: &mut axum::extract::RequestParts<Body>,
+    ) -> Result<Self, Self::Rejection> {
+        let locale = req
+            .headers()
+            .get(http::header::ACCEPT_LANGUAGE)
+            .and_then(|header| header.to_str().ok())
+            .map(Locale::from_accept_language)
+            .unwrap_or_default();
+        Ok(Self(locale))
+    }
+}
@@ -5,26 +5,29 @@ pub mod extensions;
 pub mod extractors;
 pub mod mailer;
 pub mod providers;
+pub mod rate_limiter;
+pub mod store;
 
 use acu::MasterExt;
 use anyhow::Context;
-use arc_swap::ArcSwap;
 use axum::extract::Extension;
 use houseflow_config::dynamic;
 use houseflow_config::server::Config;
 use houseflow_config::server::Network as NetworkConfig;
 use houseflow_config::server::Tls as TlsConfig;
-use houseflow_types::hub::Hub;
+use std::future::Future;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use crate::controllers::cache::CacheMessage;
+use crate::controllers::events::EventsMessage;
+use crate::controllers::google_home::GoogleHomeMessage;
 use crate::providers::lighthouse::LighthouseProviderMessage;
 
-pub type ConfiguredHubs = Arc<ArcSwap<Vec<Hub>>>;
-
 pub struct ArgMailers {
     pub dummy: Option<mailer::dummy::Handle>,
     pub smtp: Option<mailer::smtp::Handle>,
+    pub webhook: Option<mailer::webhook::Handle>,
 }
 
 type CreateFn<M, H> = Option<Box<dyn FnOnce(M) -> H>>;
@@ -41,11 +44,13 @@ type ControllerCreateFn<H> = CreateFn<providers::MasterHandle, H>;
 pub struct ArgControllers {
     // pub dummy: Option<controllers::dum>,
     pub meta: ControllerCreateFn<controllers::meta::Handle>,
+    pub google_home: ControllerCreateFn<controllers::google_home::Handle>,
 }
 
 pub struct Arg {
     pub config: dynamic::Config<Config>,
     pub clerk: Arc<dyn clerk::Clerk>,
+    pub store: Arc<dyn store::Store>,
     pub mailers: ArgMailers,
     pub providers: ArgProviders,
     pub controllers: ArgControllers,
@@ -61,6 +66,7 @@ impl Server {
         Arg {
             config,
             clerk,
+            store,
             mailers,
             providers,
             controllers,
@@ -71,18 +77,58 @@ impl Server {
 
         let router = Router::new()
             .route("/health-check", get(health_check))
-            .nest("/auth", auth::app());
+            .nest("/auth", auth::app())
+            .nest("/oauth", auth::oauth::app());
 
         let master_controller = controllers::MasterHandle::new();
         let master_provider = providers::MasterHandle::new();
 
         let controller_router = async {
-            let ArgControllers { meta } = controllers;
+            let ArgControllers { meta, google_home } = controllers;
             let mut router = Router::new();
+            let cache = controllers::cache::new();
+            {
+                let cache = cache.clone();
+                let handle = forwarding_handle(controllers::Name::Cache, move |message| {
+                    let cache = cache.clone();
+                    async move {
+                        cache.sender.notify(CacheMessage::Message(message)).await;
+                    }
+                });
+                master_controller.push(handle).await;
+            }
             if let Some(meta) = meta {
                 let meta = meta(master_provider.clone());
                 master_controller.push(meta.clone()).await;
-                router = router.nest("/meta", controllers::meta::app(meta));
+                let events = controllers::events::new(store.clone(), master_provider.clone());
+                let handle = {
+                    let events = events.clone();
+                    forwarding_handle(controllers::Name::Events, move |message| {
+                        let events = events.clone();
+                        async move {
+                            events.sender.notify(EventsMessage::Message(message)).await;
+                        }
+                    })
+                };
+                master_controller.push(handle).await;
+                router = router.nest("/meta", controllers::meta::app(meta, cache, events));
+            }
+            if let Some(google_home) = google_home {
+                let google_home = google_home(master_provider.clone());
+                let handle = {
+                    let google_home = google_home.clone();
+                    forwarding_handle(controllers::Name::GoogleHome, move |message| {
+                        let google_home = google_home.clone();
+                        async move {
+                            google_home
+                                .sender
+                                .notify(GoogleHomeMessage::Message(message))
+                                .await;
+                        }
+                    })
+                };
+                master_controller.push(handle).await;
+                router = router.nest("/google-home", controllers::google_home::app(google_home));
             }
             router
         }
@@ -97,20 +143,16 @@ impl Server {
             }
             if let Some(lighthouse) = lighthouse {
                 let lighthouse = lighthouse(master_controller.clone());
-                let sender = {
-                    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+                let handle = {
                     let lighthouse = lighthouse.clone();
-                    tokio::spawn(async move {
-                        while let Some(params) = receiver.recv().await {
+                    forwarding_handle(providers::Name::Lighthouse, move |message| {
+                        let lighthouse = lighthouse.clone();
+                        async move {
                             lighthouse
-                                .call(LighthouseProviderMessage::Message(params))
+                                .call(LighthouseProviderMessage::Message(message))
                                 .await;
                         }
-                    });
-                    sender
-                };
-                let handle = acu::Handle {
-                    sender: acu::Sender::new_from_mpsc(sender, providers::Name::Lighthouse),
+                    })
                 };
                 master_provider.push(handle).await;
                 router = router.nest("/lighthouse", providers::lighthouse::app(lighthouse));
@@ -120,7 +162,11 @@ impl Server {
         .await;
 
         let master_mailer = async {
-            let ArgMailers { dummy, smtp } = mailers;
+            let ArgMailers {
+                dummy,
+                smtp,
+                webhook,
+            } = mailers;
             let master = mailer::MasterHandle::new();
             if let Some(dummy) = dummy {
                 master.push(dummy).await;
@@ -128,6 +174,9 @@ impl Server {
             if let Some(smtp) = smtp {
                 master.push(smtp).await;
             }
+            if let Some(webhook) = webhook {
+                master.push(webhook).await;
+            }
             master
         }
         .await;
@@ -137,6 +186,11 @@ impl Server {
             .nest("/provider", provider_router)
             .layer(Extension(config.clone()))
             .layer(Extension(clerk))
+            .layer(Extension(store))
+            .layer(Extension(Arc::new(rate_limiter::RateLimiter::default())))
+            .layer(Extension(
+                Arc::new(auth::google_login::JwksCache::default()),
+            ))
             .layer(Extension(master_controller))
             .layer(Extension(master_provider))
             .layer(Extension(master_mailer));
@@ -163,6 +217,26 @@ impl Server {
     }
 }
 
+/// Creates a handle that wraps each received message and forwards it to another actor, so that
+/// actors with their own message types can be registered in the master handles
+fn forwarding_handle<M, N, F, Fut>(name: N, forward: F) -> acu::Handle<M, N>
+where
+    M: acu::Message + Send + 'static,
+    N: acu::Name,
+    F: Fn(M) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            forward(message).await;
+        }
+    });
+    acu::Handle {
+        sender: acu::Sender::new_from_mpsc(sender, name),
+    }
+}
+
 #[tracing::instrument(err, skip(router))]
 async fn http_server(
     router: axum::Router,
@@ -173,7 +247,8 @@ async fn http_server(
     }: NetworkConfig,
 ) -> Result<(), std::io::Error> {
     let address = SocketAddr::new(address, port);
-    let fut = axum_server::bind(address).serve(router.clone().into_make_service());
+    let fut = axum_server::bind(address)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>());
     tracing::info!("serving on {}", address);
     fut.await
 }
@@ -194,8 +269,8 @@ async fn https_server(
             .context("invalid TLS configuration")
             .unwrap();
     let address = SocketAddr::new(address, port);
-    let fut =
-        axum_server::bind_rustls(address, rustls_config).serve(router.clone().into_make_service());
+    let fut = axum_server::bind_rustls(address, rustls_config)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>());
     tracing::info!("serving on {}", address);
     fut.await
 }
@@ -232,6 +307,7 @@ mod test_utils {
         pub structures: Vec<Structure>,
         pub permissions: Vec<Permission>,
         pub users: Vec<User>,
+        pub google_home: Option<houseflow_config::server::controllers::GoogleHome>,
     }
 
     pub async fn get_config(
@@ -239,6 +315,7 @@ mod test_utils {
             structures,
             permissions,
             users,
+            google_home,
         }: GetConfig,
     ) -> extensions::Config {
         let config = Config {
@@ -251,15 +328,25 @@ mod test_utils {
             tls: None,
             mailers: Mailers {
                 smtp: None,
+                webhook: None,
                 dummy: Some(mailers::Dummy {}),
             },
-            controllers: Controllers { meta: None },
+            controllers: Controllers {
+                meta: None,
+                google_home,
+            },
             providers: Providers { lighthouse: None },
             logins: Logins {
                 google: Some(GoogleLogin {
                     client_id: String::from("google-login-client-id"),
+                    jwks: JwksSource::Path(std::path::PathBuf::from(concat!(
+                        env!("CARGO_MANIFEST_DIR"),
+                        "/fixtures/google-login/jwks.json"
+                    ))),
                 }),
             },
+            store: Store::default(),
+            rate_limits: RateLimits::default(),
             structures,
             users,
             permissions,
@@ -268,6 +355,16 @@ mod test_utils {
         Extension(config)
     }
 
+    /// Creates a temporary store seeded with the data from the configuration
+    pub async fn get_store(config: &extensions::Config) -> extensions::Store {
+        let store_path =
+            std::env::temp_dir().join(format!("houseflow-store-test-{}", rand::random::<u32>()));
+        let store = Arc::new(crate::store::Sled::new_temporary(store_path).unwrap());
+        let config = config.get().clone();
+        crate::store::seed(&*store, &config).await.unwrap();
+        Extension(store)
+    }
+
     #[derive(Default)]
     pub struct GetClerk {}
 
@@ -278,6 +375,14 @@ mod test_utils {
         Extension(clerk)
     }
 
+    pub fn get_rate_limiter() -> extensions::RateLimiter {
+        Extension(Arc::new(crate::rate_limiter::RateLimiter::default()))
+    }
+
+    pub fn get_google_jwks() -> extensions::GoogleJwks {
+        Extension(Arc::new(crate::auth::google_login::JwksCache::default()))
+    }
+
     #[derive(Default)]
     pub struct GetMasterMailer {
         pub tx: Option<mpsc::UnboundedSender<(lettre::Address, VerificationCode)>>,
@@ -33,7 +33,8 @@ impl DummyMailer {
     async fn handle_message(&mut self, message: Message) {
         match message {
             Message::SendVerificationCode {
-                subject: _,
+                purpose: _,
+                locale: _,
                 to,
                 code,
             } => {
@@ -1,7 +1,10 @@
 pub mod dummy;
 pub mod smtp;
+pub mod templates;
+pub mod webhook;
 
 use houseflow_types::code::VerificationCode;
+use serde::Serialize;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -14,6 +17,46 @@ pub enum Name {
     Master,
     Dummy,
     Smtp,
+    Webhook,
+}
+
+/// Action that the verification code confirms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Purpose {
+    Login,
+    Registration,
+}
+
+/// Language of the messages sent to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Locale {
+    English,
+    Polish,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::English
+    }
+}
+
+impl Locale {
+    /// Picks the locale matching the language tag in RFC5646 format, e.g `pl-PL`
+    pub fn from_language_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        match language.trim().to_ascii_lowercase().as_str() {
+            "pl" => Self::Polish,
+            _ => Self::English,
+        }
+    }
+
+    /// Picks the locale matching the most preferred language of the `Accept-Language` header
+    pub fn from_accept_language(header: &str) -> Self {
+        let tag = header.split([',', ';']).next().unwrap_or_default();
+        Self::from_language_tag(tag)
+    }
 }
 
 impl acu::MasterName for Name {
@@ -25,7 +68,8 @@ impl acu::MasterName for Name {
 #[derive(Debug, Clone)]
 pub enum Message {
     SendVerificationCode {
-        subject: String,
+        purpose: Purpose,
+        locale: Locale,
         to: lettre::Address,
         code: VerificationCode,
     },
@@ -39,7 +83,8 @@ use async_trait::async_trait;
 pub trait MailerExt {
     async fn send_verification_code(
         &self,
-        subject: String,
+        purpose: Purpose,
+        locale: Locale,
         to: lettre::Address,
         code: VerificationCode,
     );
@@ -49,12 +94,18 @@ pub trait MailerExt {
 impl MailerExt for Handle {
     async fn send_verification_code(
         &self,
-        subject: String,
+        purpose: Purpose,
+        locale: Locale,
         to: lettre::Address,
         code: VerificationCode,
     ) {
         self.sender
-            .notify(Message::SendVerificationCode { subject, to, code })
+            .notify(Message::SendVerificationCode {
+                purpose,
+                locale,
+                to,
+                code,
+            })
             .await
     }
 }
@@ -68,3 +119,20 @@ impl From<Error> for houseflow_types::errors::ServerError {
         houseflow_types::errors::InternalError::Mailer(val.to_string()).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale() {
+        assert_eq!(Locale::from_language_tag("pl-PL"), Locale::Polish);
+        assert_eq!(Locale::from_language_tag("en_US"), Locale::English);
+        assert_eq!(Locale::from_language_tag("de"), Locale::English);
+        assert_eq!(
+            Locale::from_accept_language("pl;q=0.9, en;q=0.8"),
+            Locale::Polish
+        );
+        assert_eq!(Locale::from_accept_language(""), Locale::English);
+    }
+}
@@ -1,7 +1,9 @@
 pub use super::Handle;
 
+use super::templates;
 use super::Message;
 use super::Name;
+use lettre::message::MultiPart;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::SmtpTransport;
 use lettre::Transport;
@@ -49,16 +51,24 @@ impl SmtpMailer {
 
     async fn handle_message(&mut self, message: Message) {
         match message {
-            Message::SendVerificationCode { subject, to, code } => {
-                let body = format!(
-                    "Your verification code: {}. It will be valid for next 30 minutes. Hurry up!",
-                    code
-                );
+            Message::SendVerificationCode {
+                purpose,
+                locale,
+                to,
+                code,
+            } => {
+                let email = match templates::verification_code(purpose, locale, &code) {
+                    Ok(email) => email,
+                    Err(err) => {
+                        tracing::error!("rendering verification code email: {}", err);
+                        return;
+                    }
+                };
                 let message = lettre::Message::builder()
                     .from(self.config.from.to_owned())
                     .to(lettre::message::Mailbox::new(None, to))
-                    .subject(subject)
-                    .body(body)
+                    .subject(email.subject)
+                    .multipart(MultiPart::alternative_plain_html(email.text, email.html))
                     .unwrap();
 
                 self.transport.send(&message).unwrap();
@@ -0,0 +1,153 @@
+use super::Locale;
+use super::Purpose;
+use crate::auth::login::VERIFICATION_CODE_DURATION;
+use askama::Template;
+use houseflow_types::code::VerificationCode;
+
+#[derive(Template)]
+#[template(path = "verification-code.txt")]
+struct VerificationCodeText<'a> {
+    heading: &'a str,
+    code: &'a VerificationCode,
+    expiry: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "verification-code.html")]
+struct VerificationCodeHtml<'a> {
+    lang: &'a str,
+    subject: &'a str,
+    heading: &'a str,
+    code: &'a VerificationCode,
+    expiry: &'a str,
+}
+
+/// Email with the verification code, rendered in plain-text and HTML variants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationCodeEmail {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+fn subject(purpose: Purpose, locale: Locale) -> &'static str {
+    match (purpose, locale) {
+        (Purpose::Login, Locale::English) => "Your Houseflow account: Access from a new computer",
+        (Purpose::Login, Locale::Polish) => "Twoje konto Houseflow: Dostęp z nowego komputera",
+        (Purpose::Registration, Locale::English) => {
+            "Your Houseflow account: Confirm your registration"
+        }
+        (Purpose::Registration, Locale::Polish) => "Twoje konto Houseflow: Potwierdź rejestrację",
+    }
+}
+
+fn heading(purpose: Purpose, locale: Locale) -> &'static str {
+    match (purpose, locale) {
+        (Purpose::Login, Locale::English) => "Use the following code to log in to Houseflow:",
+        (Purpose::Login, Locale::Polish) => "Użyj poniższego kodu, aby zalogować się do Houseflow:",
+        (Purpose::Registration, Locale::English) => {
+            "Use the following code to confirm your Houseflow registration:"
+        }
+        (Purpose::Registration, Locale::Polish) => {
+            "Użyj poniższego kodu, aby potwierdzić rejestrację w Houseflow:"
+        }
+    }
+}
+
+fn expiry(locale: Locale) -> String {
+    let minutes = VERIFICATION_CODE_DURATION.as_secs() / 60;
+    match locale {
+        Locale::English => format!(
+            "The code will be valid for the next {} {}.",
+            minutes,
+            if minutes == 1 { "minute" } else { "minutes" }
+        ),
+        Locale::Polish => format!(
+            "Kod będzie ważny przez {} {}.",
+            minutes,
+            minutes_pl(minutes)
+        ),
+    }
+}
+
+/// Polish plural form of "minute" for the given number of minutes
+fn minutes_pl(minutes: u64) -> &'static str {
+    match (minutes % 10, minutes % 100) {
+        _ if minutes == 1 => "minutę",
+        (2..=4, tens) if !(12..=14).contains(&tens) => "minuty",
+        _ => "minut",
+    }
+}
+
+fn lang(locale: Locale) -> &'static str {
+    match locale {
+        Locale::English => "en",
+        Locale::Polish => "pl",
+    }
+}
+
+pub fn verification_code(
+    purpose: Purpose,
+    locale: Locale,
+    code: &VerificationCode,
+) -> Result<VerificationCodeEmail, askama::Error> {
+    let subject = subject(purpose, locale);
+    let heading = heading(purpose, locale);
+    let expiry = &expiry(locale);
+    let text = VerificationCodeText {
+        heading,
+        code,
+        expiry,
+    }
+    .render()?;
+    let html = VerificationCodeHtml {
+        lang: lang(locale),
+        subject,
+        heading,
+        code,
+        expiry,
+    }
+    .render()?;
+    Ok(VerificationCodeEmail {
+        subject: subject.to_owned(),
+        text,
+        html,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verification_code_email() {
+        let code: VerificationCode = rand::random();
+        let email = verification_code(Purpose::Login, Locale::English, &code).unwrap();
+        assert_eq!(email.subject, subject(Purpose::Login, Locale::English));
+        assert!(email.text.contains(&code.to_string()));
+        assert!(email.html.contains(&code.to_string()));
+        assert!(email.html.contains(r#"<html lang="en">"#));
+
+        let email = verification_code(Purpose::Registration, Locale::Polish, &code).unwrap();
+        assert_eq!(
+            email.subject,
+            "Twoje konto Houseflow: Potwierdź rejestrację"
+        );
+        assert!(email
+            .text
+            .contains(heading(Purpose::Registration, Locale::Polish)));
+        assert!(email.text.contains(&expiry(Locale::Polish)));
+    }
+
+    #[test]
+    fn expiry_minutes() {
+        let minutes = VERIFICATION_CODE_DURATION.as_secs() / 60;
+        assert!(expiry(Locale::English).contains(&format!(" {} minute", minutes)));
+        assert!(expiry(Locale::Polish).contains(&format!(" {} minut", minutes)));
+        assert_eq!(minutes_pl(1), "minutę");
+        assert_eq!(minutes_pl(3), "minuty");
+        assert_eq!(minutes_pl(12), "minut");
+        assert_eq!(minutes_pl(22), "minuty");
+        assert_eq!(minutes_pl(30), "minut");
+    }
+}
@@ -0,0 +1,85 @@
+pub use super::Handle;
+
+use super::Locale;
+use super::Message;
+use super::Name;
+use super::Purpose;
+use houseflow_types::code::VerificationCode;
+use serde::Serialize;
+use std::time::Duration;
+use url::Url;
+
+pub struct Config {
+    pub url: Url,
+    /// Time after which a request to the webhook is abandoned
+    pub timeout: Duration,
+}
+
+pub fn new(config: Config) -> Handle {
+    let (sender, receiver) = acu::channel(Name::Webhook);
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .build()
+        .unwrap();
+    let mut actor = WebhookMailer {
+        receiver,
+        config,
+        client,
+    };
+    let handle = Handle { sender };
+    tokio::spawn(async move { actor.run().await });
+    handle
+}
+
+/// Body of the request sent to the webhook
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct VerificationCodeRequest<'a> {
+    purpose: Purpose,
+    locale: Locale,
+    to: &'a lettre::Address,
+    code: &'a VerificationCode,
+}
+
+/// Delivers verification codes by POSTing them as JSON to the configured URL
+pub struct WebhookMailer {
+    receiver: acu::Receiver<Message, Name>,
+    config: Config,
+    client: reqwest::Client,
+}
+
+impl WebhookMailer {
+    async fn run(&mut self) {
+        while let Some(message) = self.receiver.recv().await {
+            self.handle_message(message).await;
+        }
+    }
+
+    async fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::SendVerificationCode {
+                purpose,
+                locale,
+                to,
+                code,
+            } => {
+                let body = VerificationCodeRequest {
+                    purpose,
+                    locale,
+                    to: &to,
+                    code: &code,
+                };
+                let result = self
+                    .client
+                    .post(self.config.url.clone())
+                    .json(&body)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(err) = result {
+                    tracing::error!(%to, "sending verification code to webhook: {}", err);
+                }
+            }
+        }
+    }
+}
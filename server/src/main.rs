@@ -4,11 +4,14 @@ use houseflow_config::server::Config;
 use houseflow_config::server::Controllers;
 use houseflow_config::server::Mailers;
 use houseflow_config::server::Providers;
+use houseflow_config::server::Store as StoreConfig;
 use houseflow_config::Config as _;
 use houseflow_config::Error as ConfigError;
 use houseflow_server::clerk::Clerk;
 use houseflow_server::mailer;
 use houseflow_server::providers;
+use houseflow_server::store;
+use houseflow_server::store::Store;
 use houseflow_server::Arg;
 use houseflow_server::ArgControllers;
 use houseflow_server::ArgMailers;
@@ -44,8 +47,19 @@ async fn main() -> Result<(), anyhow::Error> {
     let clerk = clerk::sled::Clerk::new(defaults::clerk_path())?;
     let clerk = Arc::new(clerk) as Arc<dyn Clerk>;
 
+    let store = match &config.store {
+        StoreConfig::Sled { path } => Arc::new(store::Sled::new(path)?) as Arc<dyn Store>,
+        StoreConfig::Sqlite { path } => Arc::new(store::Sqlite::new(path)?) as Arc<dyn Store>,
+    };
+    store::seed(&*store, &config).await?;
+    store::spawn_janitor(store.clone());
+
     let mailers = {
-        let Mailers { smtp, dummy } = config.mailers.to_owned();
+        let Mailers {
+            smtp,
+            webhook,
+            dummy,
+        } = config.mailers.to_owned();
         ArgMailers {
             dummy: dummy.map(|_| {
                 let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
@@ -68,6 +82,12 @@ async fn main() -> Result<(), anyhow::Error> {
                 };
                 mailer::smtp::new(config)
             }),
+            webhook: webhook.map(|webhook| {
+                mailer::webhook::new(mailer::webhook::Config {
+                    url: webhook.url,
+                    timeout: webhook.timeout,
+                })
+            }),
         }
     };
 
@@ -85,18 +105,25 @@ async fn main() -> Result<(), anyhow::Error> {
     };
 
     let controllers = {
-        let Controllers { meta } = config.controllers.to_owned();
+        let Controllers { meta, google_home } = config.controllers.to_owned();
         ArgControllers {
             meta: match meta {
                 Some(_meta) => Some(Box::new(|_master_controller| controllers::meta::new())),
                 None => None,
             },
+            google_home: match google_home {
+                Some(_google_home) => Some(Box::new(|_master_controller| {
+                    controllers::google_home::new()
+                })),
+                None => None,
+            },
         }
     };
 
     let server = Server::new(Arg {
         config: dynamic::Config::new(config),
         clerk,
+        store,
         mailers,
         providers,
         controllers,
@@ -1,8 +1,8 @@
 use super::Message;
+use super::ProvidedAccessory;
 use crate::controllers;
 use crate::controllers::ControllerExt;
-use crate::ConfiguredHubs;
-use anyhow::Context;
+use crate::extensions;
 use async_trait::async_trait;
 use axum::body::Body;
 use axum::extract::Extension;
@@ -16,12 +16,18 @@ use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics::Characteristic;
 use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
 use houseflow_types::hub;
 use houseflow_types::lighthouse;
+use houseflow_types::password;
+use houseflow_types::requests::wait_for_result;
+use houseflow_types::requests::PendingRequests;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
 use tokio::sync::oneshot;
 
 pub type Server = ezsockets::Server<LighthouseProvider>;
@@ -36,9 +42,13 @@ pub enum LighthouseProviderMessage {
     Message(Message),
 }
 
+/// Accessories currently connected through the hub, shared by its session and the provider
+type ConnectedAccessories = Arc<Mutex<HashSet<accessory::ID>>>;
+
 #[derive(Debug, Clone)]
 pub struct LighthouseProvider {
     sessions: HashMap<hub::ID, Session>,
+    connected_accessories: HashMap<hub::ID, ConnectedAccessories>,
     controller: controllers::MasterHandle,
     config: Config,
 }
@@ -46,6 +56,7 @@ pub struct LighthouseProvider {
 pub fn new(master_controller: controllers::MasterHandle, config: Config) -> Server {
     let (server, _) = Server::create(|_| LighthouseProvider {
         sessions: Default::default(),
+        connected_accessories: Default::default(),
         controller: master_controller,
         config,
     });
@@ -62,7 +73,7 @@ impl ezsockets::ServerExt for LighthouseProvider {
         &mut self,
         socket: ezsockets::Socket,
         _address: std::net::SocketAddr,
-        (hub_id, hub_password_hash): <Self::Session as ezsockets::SessionExt>::Args,
+        hub_id: <Self::Session as ezsockets::SessionExt>::Args,
     ) -> Result<
         ezsockets::Session<
             <Self::Session as ezsockets::SessionExt>::ID,
@@ -70,28 +81,22 @@ impl ezsockets::ServerExt for LighthouseProvider {
         >,
         ezsockets::Error,
     > {
-        assert_eq!(
-            self.config
-                .hubs
-                .iter()
-                .find(|hub| hub.id == hub_id)
-                .unwrap()
-                .password_hash,
-            hub_password_hash
-        );
+        let connected_accessories = ConnectedAccessories::default();
         let session = Session::create(
             |handle| LighthouseSession {
                 session: handle,
                 hub_id,
                 controller: self.controller.clone(),
-                connected_accessories: Default::default(),
-                characteristic_write_results: Default::default(),
-                characteristic_read_results: Default::default(),
+                accessories: Default::default(),
+                connected_accessories: connected_accessories.clone(),
+                requests: Default::default(),
             },
             hub_id,
             socket,
         );
         self.sessions.insert(hub_id, session.clone());
+        self.connected_accessories
+            .insert(hub_id, connected_accessories);
         Ok(session)
     }
 
@@ -99,6 +104,17 @@ impl ezsockets::ServerExt for LighthouseProvider {
         &mut self,
         id: <Self::Session as ezsockets::SessionExt>::ID,
     ) -> Result<(), ezsockets::Error> {
+        // Accessories of the hub are no longer reachable, even though the hub didn't report it
+        let connected_accessories = self
+            .connected_accessories
+            .remove(&id)
+            .map(|connected_accessories| {
+                std::mem::take(&mut *connected_accessories.lock().unwrap())
+            })
+            .unwrap_or_default();
+        for accessory_id in connected_accessories {
+            self.controller.disconnected(accessory_id).await;
+        }
         self.sessions.remove(&id);
         Ok(())
     }
@@ -115,10 +131,14 @@ impl ezsockets::ServerExt for LighthouseProvider {
                     characteristic_name,
                     respond_to,
                 } => {
-                    let hub_session = self
-                        .find_accessory_session(accessory_id)
-                        .await
-                        .context("hub with the accessory is not connected")?;
+                    let request_timeout = self.config.request_timeout;
+                    let hub_session = match self.find_accessory_session(accessory_id).await {
+                        Some((_, hub_session)) => hub_session,
+                        None => {
+                            respond_to.send(Err(accessory::Error::NotConnected)).ok();
+                            return Ok(());
+                        }
+                    };
                     let result = hub_session
                         .call_with(|respond_to| SessionMessage::ReadCharacteristic {
                             accessory_id,
@@ -126,10 +146,11 @@ impl ezsockets::ServerExt for LighthouseProvider {
                             characteristic_name,
                             respond_to,
                         })
-                        .await
-                        .await
-                        .unwrap();
-                    respond_to.send(result).unwrap();
+                        .await;
+                    tokio::spawn(async move {
+                        let result = wait_for_result(result, request_timeout).await;
+                        respond_to.send(result).ok();
+                    });
                 }
                 Message::WriteCharacteristic {
                     accessory_id,
@@ -137,10 +158,14 @@ impl ezsockets::ServerExt for LighthouseProvider {
                     characteristic,
                     respond_to,
                 } => {
-                    let hub_session = self
-                        .find_accessory_session(accessory_id)
-                        .await
-                        .context("hub with the accessory is not connected")?;
+                    let request_timeout = self.config.request_timeout;
+                    let hub_session = match self.find_accessory_session(accessory_id).await {
+                        Some((_, hub_session)) => hub_session,
+                        None => {
+                            respond_to.send(Err(accessory::Error::NotConnected)).ok();
+                            return Ok(());
+                        }
+                    };
                     let result = hub_session
                         .call_with(|respond_to| SessionMessage::WriteCharacteristic {
                             accessory_id,
@@ -148,10 +173,11 @@ impl ezsockets::ServerExt for LighthouseProvider {
                             characteristic,
                             respond_to,
                         })
-                        .await
-                        .await
-                        .unwrap();
-                    respond_to.send(result).unwrap();
+                        .await;
+                    tokio::spawn(async move {
+                        let result = wait_for_result(result, request_timeout).await;
+                        respond_to.send(result).ok();
+                    });
                 }
                 Message::GetAccessories { respond_to } => {
                     let accessories = self.sessions.values().map(|session| {
@@ -176,6 +202,16 @@ impl ezsockets::ServerExt for LighthouseProvider {
                     let is_connected = values.iter().any(|is_connected| *is_connected);
                     respond_to.send(is_connected).unwrap();
                 }
+                Message::GetHubID {
+                    accessory_id,
+                    respond_to,
+                } => {
+                    let hub_id = self
+                        .find_accessory_session(accessory_id)
+                        .await
+                        .map(|(hub_id, _)| hub_id);
+                    respond_to.send(hub_id).unwrap();
+                }
             },
         };
         Ok(())
@@ -183,9 +219,13 @@ impl ezsockets::ServerExt for LighthouseProvider {
 }
 
 impl LighthouseProvider {
-    async fn find_accessory_session(&mut self, accessory_id: accessory::ID) -> Option<&Session> {
-        let values = self.sessions.iter().map(|(_, session)| async move {
+    async fn find_accessory_session(
+        &mut self,
+        accessory_id: accessory::ID,
+    ) -> Option<(hub::ID, &Session)> {
+        let values = self.sessions.iter().map(|(hub_id, session)| async move {
             (
+                *hub_id,
                 session,
                 session
                     .call_with(|respond_to| SessionMessage::IsAccessoryConnected {
@@ -198,7 +238,13 @@ impl LighthouseProvider {
         let accessories = futures::future::join_all(values).await;
         accessories
             .into_iter()
-            .find_map(|(session, is_connected)| if is_connected { Some(session) } else { None })
+            .find_map(|(hub_id, session, is_connected)| {
+                if is_connected {
+                    Some((hub_id, session))
+                } else {
+                    None
+                }
+            })
     }
 }
 
@@ -210,6 +256,9 @@ pub enum ConnectError {
     InvalidAuthorizationHeader(String),
     HubNotFound,
     HubAlreadyConnected,
+    InvalidPassword,
+    InvalidPasswordHash,
+    StoreError,
 }
 
 impl axum::response::IntoResponse for ConnectError {
@@ -218,6 +267,9 @@ impl axum::response::IntoResponse for ConnectError {
             Self::InvalidAuthorizationHeader(_) => StatusCode::BAD_REQUEST,
             Self::HubNotFound => StatusCode::UNAUTHORIZED,
             Self::HubAlreadyConnected => StatusCode::NOT_ACCEPTABLE,
+            Self::InvalidPassword => StatusCode::UNAUTHORIZED,
+            Self::InvalidPasswordHash => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::StoreError => StatusCode::INTERNAL_SERVER_ERROR,
         };
         let mut response = axum::Json(self).into_response();
         *response.status_mut() = status;
@@ -248,19 +300,32 @@ impl axum::extract::FromRequest<Body> for HubCredentials {
 
 pub async fn websocket_handler(
     websocket: ezsockets::axum::Upgrade,
-    Extension(configured_hubs): Extension<ConfiguredHubs>,
+    store: extensions::Store,
     Extension(server): Extension<Server>,
     HubCredentials(hub_id, password): HubCredentials,
 ) -> Result<impl axum::response::IntoResponse, ConnectError> {
-    let hub = configured_hubs
-        .load()
-        .iter()
-        .find(|hub| hub.id == hub_id)
-        .cloned()
+    let hub = store
+        .get_hub(&hub_id)
+        .await
+        .map_err(|err| {
+            tracing::error!(%hub_id, "retrieving hub: {}", err);
+            ConnectError::StoreError
+        })?
         .ok_or(ConnectError::HubNotFound)?;
+    let is_valid =
+        tokio::task::spawn_blocking(move || password::verify(&password, &hub.password_hash))
+            .await
+            .unwrap()
+            .map_err(|err| {
+                tracing::error!(%hub_id, "malformed password hash: {}", err);
+                ConnectError::InvalidPasswordHash
+            })?;
+    if !is_valid {
+        return Err(ConnectError::InvalidPassword);
+    }
     let is_connected = server
         .call_with(|respond_to| LighthouseProviderMessage::IsHubConnected {
-            id: hub.id,
+            id: hub_id,
             respond_to,
         })
         .await;
@@ -268,9 +333,7 @@ pub async fn websocket_handler(
         return Err(ConnectError::HubAlreadyConnected);
     }
 
-    // TODO: Verify password and remove following line
-    tracing::warn!("{} connected without password", hub_id);
-    Ok(websocket.on_upgrade(server, (hub_id, password)))
+    Ok(websocket.on_upgrade(server, hub_id))
 }
 
 pub fn app(server: Server) -> Router {
@@ -284,7 +347,7 @@ pub fn app(server: Server) -> Router {
 #[derive(Debug)]
 pub enum SessionMessage {
     GetAccessories {
-        respond_to: oneshot::Sender<Vec<accessory::ID>>,
+        respond_to: oneshot::Sender<Vec<ProvidedAccessory>>,
     },
     IsAccessoryConnected {
         accessory_id: accessory::ID,
@@ -308,13 +371,10 @@ pub struct LighthouseSession {
     session: ezsockets::Session<hub::ID, SessionMessage>,
     hub_id: hub::ID,
     controller: controllers::MasterHandle,
-    connected_accessories: HashSet<accessory::ID>,
-    characteristic_write_results:
-        HashMap<lighthouse::FrameID, oneshot::Sender<Result<(), accessory::Error>>>,
-    characteristic_read_results: HashMap<
-        lighthouse::FrameID,
-        oneshot::Sender<Result<accessory::characteristics::Characteristic, accessory::Error>>,
-    >,
+    /// Accessories that have connected during the session, including the disconnected ones
+    accessories: HashMap<accessory::ID, Accessory>,
+    connected_accessories: ConnectedAccessories,
+    requests: PendingRequests,
 }
 
 impl LighthouseSession {
@@ -328,7 +388,7 @@ impl LighthouseSession {
 #[async_trait]
 impl ezsockets::SessionExt for LighthouseSession {
     type ID = hub::ID;
-    type Args = (hub::ID, hub::PasswordHash);
+    type Args = hub::ID;
     type Params = SessionMessage;
 
     fn id(&self) -> &Self::ID {
@@ -339,11 +399,18 @@ impl ezsockets::SessionExt for LighthouseSession {
         let frame = serde_json::from_str::<lighthouse::HubFrame>(&text)?;
         match frame {
             lighthouse::HubFrame::AccessoryConnected(accessory) => {
-                self.connected_accessories.insert(accessory.id);
+                self.connected_accessories
+                    .lock()
+                    .unwrap()
+                    .insert(accessory.id);
+                self.accessories.insert(accessory.id, accessory.clone());
                 self.controller.connected(accessory).await;
             }
             lighthouse::HubFrame::AccessoryDisconnected(accessory_id) => {
-                self.connected_accessories.remove(&accessory_id);
+                self.connected_accessories
+                    .lock()
+                    .unwrap()
+                    .remove(&accessory_id);
                 self.controller.disconnected(accessory_id).await;
             }
             lighthouse::HubFrame::UpdateCharacteristic(frame) => {
@@ -351,19 +418,30 @@ impl ezsockets::SessionExt for LighthouseSession {
                     .updated(frame.accessory_id, frame.service_name, frame.characteristic)
                     .await;
             }
+            lighthouse::HubFrame::UpdateCharacteristics(frames) => {
+                for frame in frames {
+                    self.controller
+                        .updated(frame.accessory_id, frame.service_name, frame.characteristic)
+                        .await;
+                }
+            }
             lighthouse::HubFrame::ReadCharacteristicResult(frame) => {
-                self.characteristic_read_results
-                    .remove(&frame.id)
-                    .unwrap()
-                    .send(frame.result.into())
-                    .unwrap();
+                if !self.requests.resolve_read(frame.id, frame.result.into()) {
+                    tracing::warn!(
+                        hub_id = %self.hub_id,
+                        frame_id = frame.id,
+                        "unexpected read characteristic result"
+                    )
+                }
             }
             lighthouse::HubFrame::WriteCharacteristicResult(frame) => {
-                self.characteristic_write_results
-                    .remove(&frame.id)
-                    .unwrap()
-                    .send(frame.result.into())
-                    .unwrap();
+                if !self.requests.resolve_write(frame.id, frame.result.into()) {
+                    tracing::warn!(
+                        hub_id = %self.hub_id,
+                        frame_id = frame.id,
+                        "unexpected write characteristic result"
+                    )
+                }
             }
         };
         Ok(())
@@ -375,19 +453,29 @@ impl ezsockets::SessionExt for LighthouseSession {
 
     async fn call(&mut self, params: Self::Params) -> Result<(), ezsockets::Error> {
         match params {
-            SessionMessage::GetAccessories { respond_to } => respond_to
-                .send(
-                    self.connected_accessories
-                        .clone()
-                        .into_iter()
-                        .collect::<Vec<_>>(),
-                )
-                .unwrap(),
+            SessionMessage::GetAccessories { respond_to } => {
+                let connected_accessories = self.connected_accessories.lock().unwrap();
+                let accessories = self
+                    .accessories
+                    .values()
+                    .map(|accessory| ProvidedAccessory {
+                        accessory: accessory.clone(),
+                        hub_id: self.hub_id,
+                        connected: connected_accessories.contains(&accessory.id),
+                    })
+                    .collect::<Vec<_>>();
+                respond_to.send(accessories).unwrap();
+            }
             SessionMessage::IsAccessoryConnected {
                 accessory_id,
                 respond_to,
             } => respond_to
-                .send(self.connected_accessories.contains(&accessory_id))
+                .send(
+                    self.connected_accessories
+                        .lock()
+                        .unwrap()
+                        .contains(&accessory_id),
+                )
                 .unwrap(),
             SessionMessage::ReadCharacteristic {
                 accessory_id,
@@ -395,9 +483,7 @@ impl ezsockets::SessionExt for LighthouseSession {
                 characteristic_name,
                 respond_to,
             } => {
-                let id = rand::random();
-                let (sender, receiver) = oneshot::channel();
-                self.characteristic_read_results.insert(id, sender);
+                let (id, receiver) = self.requests.read();
                 self.send(lighthouse::ServerFrame::ReadCharacteristic(
                     lighthouse::ReadCharacteristic {
                         id,
@@ -415,9 +501,7 @@ impl ezsockets::SessionExt for LighthouseSession {
                 characteristic,
                 respond_to,
             } => {
-                let id = rand::random();
-                let (sender, receiver) = oneshot::channel();
-                self.characteristic_write_results.insert(id, sender);
+                let (id, receiver) = self.requests.write();
                 self.send(lighthouse::ServerFrame::WriteCharacteristic(
                     lighthouse::WriteCharacteristic {
                         id,
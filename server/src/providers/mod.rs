@@ -4,10 +4,14 @@ pub mod lighthouse;
 pub use dummy::DummyProvider;
 pub use lighthouse::LighthouseProvider;
 
+use acu::MasterExt;
+use futures::future;
 use houseflow_types::accessory;
 use houseflow_types::accessory::characteristics::Characteristic;
 use houseflow_types::accessory::characteristics::CharacteristicName;
 use houseflow_types::accessory::services::ServiceName;
+use houseflow_types::accessory::Accessory;
+use houseflow_types::hub;
 use tokio::sync::oneshot;
 
 #[derive(Debug, Clone, PartialEq, Eq, strum::Display, strum::IntoStaticStr)]
@@ -23,6 +27,14 @@ impl acu::MasterName for Name {
     }
 }
 
+/// Accessory known to the provider, along with the hub it connects through
+#[derive(Debug, Clone)]
+pub struct ProvidedAccessory {
+    pub accessory: Accessory,
+    pub hub_id: hub::ID,
+    pub connected: bool,
+}
+
 #[derive(Debug)]
 pub enum Message {
     ReadCharacteristic {
@@ -38,12 +50,16 @@ pub enum Message {
         respond_to: oneshot::Sender<Result<(), accessory::Error>>,
     },
     GetAccessories {
-        respond_to: oneshot::Sender<Vec<accessory::ID>>,
+        respond_to: oneshot::Sender<Vec<ProvidedAccessory>>,
     },
     IsConnected {
         accessory_id: accessory::ID,
         respond_to: oneshot::Sender<bool>,
     },
+    GetHubID {
+        accessory_id: accessory::ID,
+        respond_to: oneshot::Sender<Option<hub::ID>>,
+    },
 }
 
 impl acu::Message for Message {}
@@ -64,8 +80,10 @@ pub trait ProviderExt {
         service_name: ServiceName,
         characteristic_name: CharacteristicName,
     ) -> Result<Characteristic, accessory::Error>;
-    async fn get_accessories(&self) -> Vec<accessory::ID>;
+    async fn get_accessories(&self) -> Vec<ProvidedAccessory>;
     async fn is_connected(&self, accessory_id: accessory::ID) -> bool;
+    /// Returns ID of the hub that the accessory is connected through
+    async fn get_hub_id(&self, accessory_id: accessory::ID) -> Option<hub::ID>;
 }
 
 pub type Handle = acu::Handle<Message, Name>;
@@ -104,7 +122,7 @@ impl ProviderExt for Handle {
             .await
     }
 
-    async fn get_accessories(&self) -> Vec<accessory::ID> {
+    async fn get_accessories(&self) -> Vec<ProvidedAccessory> {
         self.sender
             .call_with(|respond_to| Message::GetAccessories { respond_to })
             .await
@@ -118,6 +136,81 @@ impl ProviderExt for Handle {
             })
             .await
     }
+
+    async fn get_hub_id(&self, accessory_id: accessory::ID) -> Option<hub::ID> {
+        self.sender
+            .call_with(|respond_to| Message::GetHubID {
+                accessory_id,
+                respond_to,
+            })
+            .await
+    }
 }
 
 pub type MasterHandle = acu::MasterHandle<Message, Name>;
+
+#[async_trait]
+impl ProviderExt for MasterHandle {
+    async fn write_characteristic(
+        &self,
+        accessory_id: accessory::ID,
+        service_name: ServiceName,
+        characteristic: Characteristic,
+    ) -> Result<(), accessory::Error> {
+        let slaves = self.slaves().await;
+        let futures = slaves
+            .iter()
+            .map(|handle| async move { (handle, handle.is_connected(accessory_id).await) });
+        let results = future::join_all(futures).await;
+        let slave = results
+            .iter()
+            .find_map(|(handle, connected)| if *connected { Some(handle) } else { None })
+            .ok_or(accessory::Error::NotConnected)?;
+        slave
+            .write_characteristic(accessory_id, service_name, characteristic)
+            .await
+    }
+
+    async fn read_characteristic(
+        &self,
+        accessory_id: accessory::ID,
+        service_name: ServiceName,
+        characteristic_name: CharacteristicName,
+    ) -> Result<Characteristic, accessory::Error> {
+        let slaves = self.slaves().await;
+        let futures = slaves
+            .iter()
+            .map(|handle| async move { (handle, handle.is_connected(accessory_id).await) });
+        let results = future::join_all(futures).await;
+        let slave = results
+            .iter()
+            .find_map(|(handle, connected)| if *connected { Some(handle) } else { None })
+            .ok_or(accessory::Error::NotConnected)?;
+        slave
+            .read_characteristic(accessory_id, service_name, characteristic_name)
+            .await
+    }
+
+    async fn get_accessories(&self) -> Vec<ProvidedAccessory> {
+        let slaves = self.slaves().await;
+        let futures = slaves.iter().map(|handle| handle.get_accessories());
+        let results = future::join_all(futures).await;
+        results.into_iter().flatten().collect()
+    }
+
+    async fn is_connected(&self, accessory_id: accessory::ID) -> bool {
+        let slaves = self.slaves().await;
+        let futures = slaves
+            .iter()
+            .map(|handle| handle.is_connected(accessory_id));
+        let results = future::join_all(futures).await;
+        results.iter().any(|connected| *connected)
+    }
+
+    async fn get_hub_id(&self, accessory_id: accessory::ID) -> Option<hub::ID> {
+        let slaves = self.slaves().await;
+        let futures = slaves.iter().map(|handle| handle.get_hub_id(accessory_id));
+        let results = future::join_all(futures).await;
+        results.into_iter().flatten().next()
+    }
+}
@@ -0,0 +1,233 @@
+use axum::extract::ConnectInfo;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use houseflow_config::dynamic;
+use houseflow_config::server::Config;
+use houseflow_config::server::RateLimit;
+use houseflow_config::server::RateLimits;
+use houseflow_types::errors::ServerError;
+use houseflow_types::user;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Number of tracked keys above which the idle buckets are dropped
+const CLEANUP_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let refilled = elapsed.as_secs_f64() / limit.period.as_secs_f64();
+        self.tokens = (self.tokens + refilled).min(limit.burst as f64);
+        self.updated_at = now;
+    }
+
+    fn is_full(&self, limit: &RateLimit, now: Instant) -> bool {
+        let mut bucket = self.clone();
+        bucket.refill(limit, now);
+        bucket.tokens >= limit.burst as f64
+    }
+
+    /// Takes a token from the bucket, returns the time after which it will be available otherwise
+    fn take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(limit.period.mul_f64(1.0 - self.tokens))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Lockout {
+    failed_attempts: u32,
+    locked_until: Option<Instant>,
+}
+
+/// Limits requests per IP address and per account, and locks accounts after repeated invalid
+/// verification codes
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    ips: Mutex<HashMap<IpAddr, Bucket>>,
+    accounts: Mutex<HashMap<lettre::Address, Bucket>>,
+    lockouts: Mutex<HashMap<user::ID, Lockout>>,
+}
+
+fn too_many_requests(retry_after: Duration) -> ServerError {
+    let mut seconds = retry_after.as_secs();
+    if retry_after.subsec_nanos() > 0 || seconds == 0 {
+        seconds += 1;
+    }
+    ServerError::TooManyRequests {
+        retry_after: seconds,
+    }
+}
+
+fn take<K: Hash + Eq>(
+    buckets: &Mutex<HashMap<K, Bucket>>,
+    key: K,
+    limit: &RateLimit,
+) -> Result<(), ServerError> {
+    let now = Instant::now();
+    let mut buckets = buckets.lock().unwrap();
+    if buckets.len() >= CLEANUP_THRESHOLD {
+        buckets.retain(|_, bucket| !bucket.is_full(limit, now));
+    }
+    buckets
+        .entry(key)
+        .or_insert_with(|| Bucket::new(limit, now))
+        .take(limit, now)
+        .map_err(too_many_requests)
+}
+
+impl RateLimiter {
+    pub fn check_ip(&self, ip: IpAddr, limit: &RateLimit) -> Result<(), ServerError> {
+        take(&self.ips, ip, limit)
+    }
+
+    pub fn check_account(
+        &self,
+        email: &lettre::Address,
+        limit: &RateLimit,
+    ) -> Result<(), ServerError> {
+        take(&self.accounts, email.to_owned(), limit)
+    }
+
+    /// Returns an error if the account is locked after too many invalid verification codes
+    pub fn check_lockout(&self, user_id: &user::ID) -> Result<(), ServerError> {
+        let now = Instant::now();
+        let mut lockouts = self.lockouts.lock().unwrap();
+        let locked_until = match lockouts
+            .get(user_id)
+            .and_then(|lockout| lockout.locked_until)
+        {
+            Some(locked_until) => locked_until,
+            None => return Ok(()),
+        };
+        if locked_until > now {
+            Err(too_many_requests(locked_until - now))
+        } else {
+            lockouts.remove(user_id);
+            Ok(())
+        }
+    }
+
+    /// Records an invalid verification code, and locks the account once there are too many
+    pub fn record_failed_attempt(&self, user_id: &user::ID, limits: &RateLimits) {
+        let mut lockouts = self.lockouts.lock().unwrap();
+        let lockout = lockouts.entry(*user_id).or_default();
+        lockout.failed_attempts += 1;
+        if lockout.failed_attempts >= limits.lockout_attempts {
+            tracing::warn!(%user_id, "Too many invalid verification codes, locking account");
+            lockout.failed_attempts = 0;
+            lockout.locked_until = Some(Instant::now() + limits.lockout_duration);
+        }
+    }
+
+    /// Forgets the invalid verification codes after a successful verification
+    pub fn reset_failed_attempts(&self, user_id: &user::ID) {
+        self.lockouts.lock().unwrap().remove(user_id);
+    }
+}
+
+/// Middleware limiting the requests per IP address of the client
+pub async fn limit_ip<B>(request: Request<B>, next: Next<B>) -> Result<Response, ServerError> {
+    {
+        let extensions = request.extensions();
+        if let Some(ConnectInfo(address)) = extensions.get::<ConnectInfo<SocketAddr>>() {
+            let config = extensions
+                .get::<dynamic::Config<Config>>()
+                .expect("missing config extension");
+            let limiter = extensions
+                .get::<Arc<RateLimiter>>()
+                .expect("missing rate limiter extension");
+            limiter.check_ip(address.ip(), &config.get().rate_limits.ip)?;
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit() -> RateLimit {
+        RateLimit {
+            burst: 2,
+            period: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn bucket() {
+        let limit = limit();
+        let now = Instant::now();
+        let mut bucket = Bucket::new(&limit, now);
+        assert_eq!(bucket.take(&limit, now), Ok(()));
+        assert_eq!(bucket.take(&limit, now), Ok(()));
+        assert_eq!(bucket.take(&limit, now), Err(Duration::from_secs(10)));
+        let now = now + Duration::from_secs(5);
+        assert_eq!(bucket.take(&limit, now), Err(Duration::from_secs(5)));
+        let now = now + Duration::from_secs(5);
+        assert_eq!(bucket.take(&limit, now), Ok(()));
+        assert!(!bucket.is_full(&limit, now));
+        assert!(bucket.is_full(&limit, now + Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn check_ip() {
+        let limiter = RateLimiter::default();
+        let limit = limit();
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        limiter.check_ip(ip, &limit).unwrap();
+        limiter.check_ip(ip, &limit).unwrap();
+        let err = limiter.check_ip(ip, &limit).unwrap_err();
+        assert!(matches!(
+            err,
+            ServerError::TooManyRequests { retry_after } if retry_after <= 10
+        ));
+        limiter
+            .check_ip(IpAddr::from([127, 0, 0, 2]), &limit)
+            .unwrap();
+    }
+
+    #[test]
+    fn lockout() {
+        let limiter = RateLimiter::default();
+        let limits = RateLimits {
+            lockout_attempts: 2,
+            ..Default::default()
+        };
+        let user_id = user::ID::new_v4();
+        limiter.record_failed_attempt(&user_id, &limits);
+        limiter.check_lockout(&user_id).unwrap();
+        limiter.record_failed_attempt(&user_id, &limits);
+        assert!(matches!(
+            limiter.check_lockout(&user_id),
+            Err(ServerError::TooManyRequests { .. })
+        ));
+        limiter.reset_failed_attempts(&user_id);
+        limiter.check_lockout(&user_id).unwrap();
+    }
+}
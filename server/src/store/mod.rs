@@ -0,0 +1,317 @@
+pub mod sled;
+pub mod sqlite;
+
+pub use self::sled::Store as Sled;
+pub use self::sqlite::Store as Sqlite;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use houseflow_config::server::providers::LighthouseHub as Hub;
+use houseflow_config::server::Config;
+use houseflow_types::errors::AuthError;
+use houseflow_types::hub;
+use houseflow_types::permission::Permission;
+use houseflow_types::structure;
+use houseflow_types::structure::Structure;
+use houseflow_types::token;
+use houseflow_types::user;
+use houseflow_types::user::User;
+use std::sync::Arc;
+use std::time::Duration;
+
+const JANITOR_CLEAN_INTERVAL: Duration = Duration::from_secs(60 * 30); // 30 minutes
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("sled error: {0}")]
+    Sled(#[from] ::sled::Error),
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid data {0}")]
+    InvalidData(String),
+    #[error("user with the same email already exists")]
+    UserAlreadyExists,
+}
+
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// Adds the user, or updates it if a user with the same ID already exists.
+    ///
+    /// Fails with `Error::UserAlreadyExists` if the email is used by another user.
+    async fn add_user(&self, user: &User) -> Result<(), Error>;
+    async fn get_user(&self, user_id: &user::ID) -> Result<Option<User>, Error>;
+    async fn get_user_by_email(&self, email: &lettre::Address) -> Result<Option<User>, Error>;
+    /// Adds the user that has registered, but has not confirmed the registration yet,
+    /// or updates it if a pending user with the same ID already exists.
+    ///
+    /// The pending user is not returned after it expires.
+    async fn add_pending_user(&self, user: &User, expires_at: DateTime<Utc>) -> Result<(), Error>;
+    async fn get_pending_user(&self, user_id: &user::ID) -> Result<Option<User>, Error>;
+    async fn get_pending_user_by_email(
+        &self,
+        email: &lettre::Address,
+    ) -> Result<Option<User>, Error>;
+    async fn remove_pending_user(&self, user_id: &user::ID) -> Result<bool, Error>;
+    async fn remove_expired_pending_users(&self) -> Result<(), Error>;
+    /// Adds the structure, or updates it if a structure with the same ID already exists
+    async fn add_structure(&self, structure: &Structure) -> Result<(), Error>;
+    async fn get_structure(&self, structure_id: &structure::ID)
+        -> Result<Option<Structure>, Error>;
+    /// Adds the permission, or updates it if the user already has a permission to the structure
+    async fn add_permission(&self, permission: &Permission) -> Result<(), Error>;
+    async fn get_permission(
+        &self,
+        structure_id: &structure::ID,
+        user_id: &user::ID,
+    ) -> Result<Option<Permission>, Error>;
+    /// Adds the hub, or updates it if a hub with the same ID already exists
+    async fn add_hub(&self, hub: &Hub) -> Result<(), Error>;
+    async fn get_hub(&self, hub_id: &hub::ID) -> Result<Option<Hub>, Error>;
+    /// Revokes the refresh token, so it can no longer be used.
+    ///
+    /// Returns false if it has been already revoked before
+    async fn revoke_refresh_token(&self, token_id: &token::ID) -> Result<bool, Error>;
+    async fn is_refresh_token_revoked(&self, token_id: &token::ID) -> Result<bool, Error>;
+    /// Revokes all refresh tokens that belong to the family
+    async fn revoke_refresh_token_family(&self, family_id: &token::ID) -> Result<(), Error>;
+    async fn is_refresh_token_family_revoked(&self, family_id: &token::ID) -> Result<bool, Error>;
+    /// Revokes all refresh tokens of the user issued before the specified time
+    async fn revoke_user_refresh_tokens(
+        &self,
+        user_id: &user::ID,
+        issued_before: DateTime<Utc>,
+    ) -> Result<(), Error>;
+    /// Returns the time before which all refresh tokens of the user are revoked
+    async fn get_user_refresh_tokens_revoked_at(
+        &self,
+        user_id: &user::ID,
+    ) -> Result<Option<DateTime<Utc>>, Error>;
+    /// Marks the authorization code as used, returns false if it has been already used before
+    async fn use_authorization_code(&self, code_id: &token::ID) -> Result<bool, Error>;
+}
+
+/// Periodically removes the expired pending users from the store
+pub fn spawn_janitor(store: Arc<dyn Store>) -> tokio::task::JoinHandle<Result<(), Error>> {
+    tokio::spawn(async move {
+        loop {
+            store.remove_expired_pending_users().await?;
+            tokio::time::sleep(JANITOR_CLEAN_INTERVAL).await;
+        }
+    })
+}
+
+/// Adds structures, users, permissions and hubs defined in the configuration to the store
+pub async fn seed(store: &dyn Store, config: &Config) -> Result<(), Error> {
+    for structure in &config.structures {
+        store.add_structure(structure).await?;
+    }
+    for user in &config.users {
+        store.add_user(user).await?;
+    }
+    for permission in &config.permissions {
+        store.add_permission(permission).await?;
+    }
+    if let Some(lighthouse) = &config.providers.lighthouse {
+        for hub in &lighthouse.hubs {
+            store.add_hub(hub).await?;
+        }
+    }
+    Ok(())
+}
+
+impl From<Error> for houseflow_types::errors::ServerError {
+    fn from(val: Error) -> Self {
+        match val {
+            Error::UserAlreadyExists => AuthError::UserAlreadyExists.into(),
+            val => houseflow_types::errors::InternalError::Store(val.to_string()).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn get_user(email: &str) -> User {
+        User {
+            id: user::ID::new_v4(),
+            username: String::from("john"),
+            email: email.parse().unwrap(),
+            admin: false,
+        }
+    }
+
+    /// Checks behaviour shared by all of the store implementations
+    pub(super) async fn store(store: &dyn Store) {
+        let user = get_user("john@email.com");
+        assert_eq!(store.get_user(&user.id).await.unwrap(), None);
+        store.add_user(&user).await.unwrap();
+        assert_eq!(store.get_user(&user.id).await.unwrap(), Some(user.clone()));
+        assert_eq!(
+            store.get_user_by_email(&user.email).await.unwrap(),
+            Some(user.clone())
+        );
+
+        let other_user = get_user("john@email.com");
+        assert!(matches!(
+            store.add_user(&other_user).await,
+            Err(Error::UserAlreadyExists)
+        ));
+
+        let updated_user = User {
+            email: "john@other-email.com".parse().unwrap(),
+            ..user.clone()
+        };
+        store.add_user(&updated_user).await.unwrap();
+        assert_eq!(store.get_user_by_email(&user.email).await.unwrap(), None);
+        assert_eq!(
+            store.get_user_by_email(&updated_user.email).await.unwrap(),
+            Some(updated_user.clone())
+        );
+        store.add_user(&other_user).await.unwrap();
+
+        let pending_user = get_user("john@pending-email.com");
+        let expires_at = Utc::now() + chrono::Duration::minutes(30);
+        store
+            .add_pending_user(&pending_user, expires_at)
+            .await
+            .unwrap();
+        assert_eq!(store.get_user(&pending_user.id).await.unwrap(), None);
+        assert_eq!(
+            store.get_pending_user(&pending_user.id).await.unwrap(),
+            Some(pending_user.clone())
+        );
+        assert_eq!(
+            store
+                .get_pending_user_by_email(&pending_user.email)
+                .await
+                .unwrap(),
+            Some(pending_user.clone())
+        );
+        assert_eq!(
+            store.get_pending_user_by_email(&user.email).await.unwrap(),
+            None
+        );
+        store.remove_expired_pending_users().await.unwrap();
+        assert_eq!(
+            store.get_pending_user(&pending_user.id).await.unwrap(),
+            Some(pending_user.clone())
+        );
+        assert!(store.remove_pending_user(&pending_user.id).await.unwrap());
+        assert!(!store.remove_pending_user(&pending_user.id).await.unwrap());
+        assert_eq!(
+            store.get_pending_user(&pending_user.id).await.unwrap(),
+            None
+        );
+
+        let expired_user = get_user("john@expired-email.com");
+        let expired_at = Utc::now() - chrono::Duration::minutes(1);
+        store
+            .add_pending_user(&expired_user, expired_at)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_pending_user(&expired_user.id).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            store
+                .get_pending_user_by_email(&expired_user.email)
+                .await
+                .unwrap(),
+            None
+        );
+        store.remove_expired_pending_users().await.unwrap();
+        assert!(!store.remove_pending_user(&expired_user.id).await.unwrap());
+
+        let structure = Structure {
+            id: structure::ID::new_v4(),
+            name: String::from("Home"),
+        };
+        store.add_structure(&structure).await.unwrap();
+        assert_eq!(
+            store.get_structure(&structure.id).await.unwrap(),
+            Some(structure.clone())
+        );
+
+        let permission = Permission {
+            structure_id: structure.id,
+            user_id: user.id,
+            is_manager: false,
+        };
+        store.add_permission(&permission).await.unwrap();
+        let permission = Permission {
+            is_manager: true,
+            ..permission
+        };
+        store.add_permission(&permission).await.unwrap();
+        assert_eq!(
+            store.get_permission(&structure.id, &user.id).await.unwrap(),
+            Some(permission)
+        );
+        assert_eq!(
+            store
+                .get_permission(&structure.id, &other_user.id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let hub = Hub {
+            id: hub::ID::new_v4(),
+            name: String::from("Hub"),
+            password_hash: String::from("password-hash"),
+            structure_id: structure.id,
+        };
+        store.add_hub(&hub).await.unwrap();
+        assert_eq!(store.get_hub(&hub.id).await.unwrap(), Some(hub));
+        assert_eq!(store.get_hub(&hub::ID::new_v4()).await.unwrap(), None);
+
+        let token_id = token::ID::new_v4();
+        assert!(!store.is_refresh_token_revoked(&token_id).await.unwrap());
+        assert!(store.revoke_refresh_token(&token_id).await.unwrap());
+        assert!(!store.revoke_refresh_token(&token_id).await.unwrap());
+        assert!(store.is_refresh_token_revoked(&token_id).await.unwrap());
+        assert!(!store
+            .is_refresh_token_family_revoked(&token_id)
+            .await
+            .unwrap());
+        store.revoke_refresh_token_family(&token_id).await.unwrap();
+        assert!(store
+            .is_refresh_token_family_revoked(&token_id)
+            .await
+            .unwrap());
+
+        let revoked_at = Utc.timestamp_millis(1_600_000_000_123);
+        assert_eq!(
+            store
+                .get_user_refresh_tokens_revoked_at(&user.id)
+                .await
+                .unwrap(),
+            None
+        );
+        store
+            .revoke_user_refresh_tokens(&user.id, revoked_at)
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .get_user_refresh_tokens_revoked_at(&user.id)
+                .await
+                .unwrap(),
+            Some(revoked_at)
+        );
+
+        let code_id = token::ID::new_v4();
+        assert!(store.use_authorization_code(&code_id).await.unwrap());
+        assert!(!store.use_authorization_code(&code_id).await.unwrap());
+        assert!(store
+            .use_authorization_code(&token::ID::new_v4())
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,295 @@
+use super::Error;
+use super::Hub;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use houseflow_types::hub;
+use houseflow_types::permission::Permission;
+use houseflow_types::structure;
+use houseflow_types::structure::Structure;
+use houseflow_types::token;
+use houseflow_types::user;
+use houseflow_types::user::User;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use sled::transaction::ConflictableTransactionError;
+use sled::transaction::TransactionError;
+use sled::Transactional;
+
+#[derive(Clone)]
+pub struct Store {
+    database: sled::Db,
+    users: sled::Tree,
+    /// Email of the user -> ID of the user
+    user_emails: sled::Tree,
+    pending_users: sled::Tree,
+    structures: sled::Tree,
+    permissions: sled::Tree,
+    hubs: sled::Tree,
+    revoked_refresh_tokens: sled::Tree,
+    revoked_refresh_token_families: sled::Tree,
+    /// ID of the user -> UNIX timestamp before which all refresh tokens of the user are revoked
+    user_refresh_tokens_revoked_at: sled::Tree,
+    used_authorization_codes: sled::Tree,
+}
+
+impl Store {
+    pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let config = sled::Config::new().path(path);
+        Self::with_config(config)
+    }
+
+    pub fn new_temporary(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let config = sled::Config::new().path(path).temporary(true);
+        Self::with_config(config)
+    }
+
+    pub fn with_config(config: sled::Config) -> Result<Self, Error> {
+        let database = config.open()?;
+        Ok(Self {
+            users: database.open_tree("users")?,
+            user_emails: database.open_tree("user-emails")?,
+            pending_users: database.open_tree("pending-users")?,
+            structures: database.open_tree("structures")?,
+            permissions: database.open_tree("permissions")?,
+            hubs: database.open_tree("hubs")?,
+            revoked_refresh_tokens: database.open_tree("revoked-refresh-tokens")?,
+            revoked_refresh_token_families: database.open_tree("revoked-refresh-token-families")?,
+            user_refresh_tokens_revoked_at: database.open_tree("user-refresh-tokens-revoked-at")?,
+            used_authorization_codes: database.open_tree("used-authorization-codes")?,
+            database,
+        })
+    }
+
+    async fn insert(
+        &self,
+        tree: &sled::Tree,
+        key: impl AsRef<[u8]>,
+        value: &impl Serialize,
+    ) -> Result<(), Error> {
+        tree.insert(key, serde_json::to_vec(value)?)?;
+        self.database.flush_async().await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PendingUser {
+    user: User,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    expires_at: DateTime<Utc>,
+}
+
+impl PendingUser {
+    fn has_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+fn get<T: DeserializeOwned>(tree: &sled::Tree, key: impl AsRef<[u8]>) -> Result<Option<T>, Error> {
+    match tree.get(key)? {
+        Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+        None => Ok(None),
+    }
+}
+
+fn permission_key(structure_id: &structure::ID, user_id: &user::ID) -> Vec<u8> {
+    [&structure_id.as_bytes()[..], &user_id.as_bytes()[..]].concat()
+}
+
+#[async_trait]
+impl super::Store for Store {
+    async fn add_user(&self, user: &User) -> Result<(), Error> {
+        let value = serde_json::to_vec(user)?;
+        let email = user.email.to_string();
+        let result = (&self.users, &self.user_emails).transaction(|(users, user_emails)| {
+            if let Some(user_id) = user_emails.get(email.as_bytes())? {
+                if user_id.as_ref() != user.id.as_bytes() {
+                    return Err(ConflictableTransactionError::Abort(
+                        Error::UserAlreadyExists,
+                    ));
+                }
+            }
+            // Release the previous email if the user has changed it
+            if let Some(previous) = users.get(user.id.as_bytes())? {
+                let previous: User = serde_json::from_slice(&previous)
+                    .map_err(|err| ConflictableTransactionError::Abort(err.into()))?;
+                user_emails.remove(previous.email.to_string().as_bytes())?;
+            }
+            users.insert(&user.id.as_bytes()[..], value.as_slice())?;
+            user_emails.insert(email.as_bytes(), &user.id.as_bytes()[..])?;
+            Ok(())
+        });
+        match result {
+            Ok(()) => {}
+            Err(TransactionError::Abort(err)) => return Err(err),
+            Err(TransactionError::Storage(err)) => return Err(err.into()),
+        };
+        self.database.flush_async().await?;
+        Ok(())
+    }
+
+    async fn get_user(&self, user_id: &user::ID) -> Result<Option<User>, Error> {
+        get(&self.users, user_id.as_bytes())
+    }
+
+    async fn get_user_by_email(&self, email: &lettre::Address) -> Result<Option<User>, Error> {
+        match self.user_emails.get(email.to_string().as_bytes())? {
+            Some(user_id) => get(&self.users, user_id),
+            None => Ok(None),
+        }
+    }
+
+    async fn add_pending_user(&self, user: &User, expires_at: DateTime<Utc>) -> Result<(), Error> {
+        let pending_user = PendingUser {
+            user: user.clone(),
+            expires_at,
+        };
+        self.insert(&self.pending_users, user.id.as_bytes(), &pending_user)
+            .await
+    }
+
+    async fn get_pending_user(&self, user_id: &user::ID) -> Result<Option<User>, Error> {
+        let pending_user: Option<PendingUser> = get(&self.pending_users, user_id.as_bytes())?;
+        Ok(pending_user
+            .filter(|pending_user| !pending_user.has_expired())
+            .map(|pending_user| pending_user.user))
+    }
+
+    async fn get_pending_user_by_email(
+        &self,
+        email: &lettre::Address,
+    ) -> Result<Option<User>, Error> {
+        for entry in self.pending_users.iter() {
+            let (_, value) = entry?;
+            let pending_user: PendingUser = serde_json::from_slice(&value)?;
+            if pending_user.user.email == *email && !pending_user.has_expired() {
+                return Ok(Some(pending_user.user));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn remove_pending_user(&self, user_id: &user::ID) -> Result<bool, Error> {
+        let removed = self.pending_users.remove(user_id.as_bytes())?.is_some();
+        self.database.flush_async().await?;
+        Ok(removed)
+    }
+
+    async fn remove_expired_pending_users(&self) -> Result<(), Error> {
+        for entry in self.pending_users.iter() {
+            let (key, value) = entry?;
+            let pending_user: PendingUser = serde_json::from_slice(&value)?;
+            if pending_user.has_expired() {
+                self.pending_users.remove(key)?;
+            }
+        }
+        self.database.flush_async().await?;
+        Ok(())
+    }
+
+    async fn add_structure(&self, structure: &Structure) -> Result<(), Error> {
+        self.insert(&self.structures, structure.id.as_bytes(), structure)
+            .await
+    }
+
+    async fn get_structure(
+        &self,
+        structure_id: &structure::ID,
+    ) -> Result<Option<Structure>, Error> {
+        get(&self.structures, structure_id.as_bytes())
+    }
+
+    async fn add_permission(&self, permission: &Permission) -> Result<(), Error> {
+        let key = permission_key(&permission.structure_id, &permission.user_id);
+        self.insert(&self.permissions, key, permission).await
+    }
+
+    async fn get_permission(
+        &self,
+        structure_id: &structure::ID,
+        user_id: &user::ID,
+    ) -> Result<Option<Permission>, Error> {
+        get(&self.permissions, permission_key(structure_id, user_id))
+    }
+
+    async fn add_hub(&self, hub: &Hub) -> Result<(), Error> {
+        self.insert(&self.hubs, hub.id.as_bytes(), hub).await
+    }
+
+    async fn get_hub(&self, hub_id: &hub::ID) -> Result<Option<Hub>, Error> {
+        get(&self.hubs, hub_id.as_bytes())
+    }
+
+    async fn revoke_refresh_token(&self, token_id: &token::ID) -> Result<bool, Error> {
+        let previous = self
+            .revoked_refresh_tokens
+            .insert(token_id.as_bytes(), serde_json::to_vec(&())?)?;
+        self.database.flush_async().await?;
+        Ok(previous.is_none())
+    }
+
+    async fn is_refresh_token_revoked(&self, token_id: &token::ID) -> Result<bool, Error> {
+        Ok(self
+            .revoked_refresh_tokens
+            .contains_key(token_id.as_bytes())?)
+    }
+
+    async fn revoke_refresh_token_family(&self, family_id: &token::ID) -> Result<(), Error> {
+        self.insert(
+            &self.revoked_refresh_token_families,
+            family_id.as_bytes(),
+            &(),
+        )
+        .await
+    }
+
+    async fn is_refresh_token_family_revoked(&self, family_id: &token::ID) -> Result<bool, Error> {
+        Ok(self
+            .revoked_refresh_token_families
+            .contains_key(family_id.as_bytes())?)
+    }
+
+    async fn revoke_user_refresh_tokens(
+        &self,
+        user_id: &user::ID,
+        issued_before: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        self.insert(
+            &self.user_refresh_tokens_revoked_at,
+            user_id.as_bytes(),
+            &issued_before.timestamp_millis(),
+        )
+        .await
+    }
+
+    async fn get_user_refresh_tokens_revoked_at(
+        &self,
+        user_id: &user::ID,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let timestamp: Option<i64> = get(&self.user_refresh_tokens_revoked_at, user_id.as_bytes())?;
+        Ok(timestamp.map(|timestamp| Utc.timestamp_millis(timestamp)))
+    }
+
+    async fn use_authorization_code(&self, code_id: &token::ID) -> Result<bool, Error> {
+        let previous = self
+            .used_authorization_codes
+            .insert(code_id.as_bytes(), &b""[..])?;
+        self.database.flush_async().await?;
+        Ok(previous.is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn store() {
+        let path = std::env::temp_dir().join(format!("houseflow-store-{}", rand::random::<u32>()));
+        let store = Store::new_temporary(path).unwrap();
+        super::super::tests::store(&store).await;
+    }
+}
@@ -0,0 +1,438 @@
+use super::Error;
+use super::Hub;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use houseflow_types::hub;
+use houseflow_types::permission::Permission;
+use houseflow_types::structure;
+use houseflow_types::structure::Structure;
+use houseflow_types::token;
+use houseflow_types::user;
+use houseflow_types::user::User;
+use rusqlite::params;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY NOT NULL,
+    username TEXT NOT NULL,
+    email TEXT UNIQUE NOT NULL,
+    admin INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pending_users (
+    id TEXT PRIMARY KEY NOT NULL,
+    username TEXT NOT NULL,
+    email TEXT NOT NULL,
+    admin INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS structures (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+    structure_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    is_manager INTEGER NOT NULL,
+    PRIMARY KEY (structure_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS hubs (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    password_hash TEXT NOT NULL,
+    structure_id TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS revoked_refresh_tokens (
+    id TEXT PRIMARY KEY NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS revoked_refresh_token_families (
+    id TEXT PRIMARY KEY NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS user_refresh_tokens_revoked_at (
+    user_id TEXT PRIMARY KEY NOT NULL,
+    revoked_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS used_authorization_codes (
+    id TEXT PRIMARY KEY NOT NULL
+);
+"#;
+
+#[derive(Clone)]
+pub struct Store {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn new_in_memory() -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    pub fn with_connection(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+}
+
+fn parse_id<T>(id: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    T::from_str(id).map_err(|err| Error::InvalidData(format!("invalid id `{}`: {}", id, err)))
+}
+
+fn parse_user((id, username, email, admin): (String, String, String, bool)) -> Result<User, Error> {
+    Ok(User {
+        id: parse_id(&id)?,
+        username,
+        email: lettre::Address::from_str(&email)
+            .map_err(|err| Error::InvalidData(format!("invalid email `{}`: {}", email, err)))?,
+        admin,
+    })
+}
+
+#[async_trait]
+impl super::Store for Store {
+    async fn add_user(&self, user: &User) -> Result<(), Error> {
+        let result = self.connection.lock().unwrap().execute(
+            "INSERT INTO users (id, username, email, admin) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (id) DO UPDATE SET
+             username = excluded.username, email = excluded.email, admin = excluded.admin",
+            params![
+                user.id.to_string(),
+                user.username,
+                user.email.to_string(),
+                user.admin
+            ],
+        );
+        match result {
+            Ok(_) => Ok(()),
+            // The only constraint that can be violated is uniqueness of the email
+            Err(rusqlite::Error::SqliteFailure(err, _))
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                Err(Error::UserAlreadyExists)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn get_user(&self, user_id: &user::ID) -> Result<Option<User>, Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT id, username, email, admin FROM users WHERE id = ?1",
+                params![user_id.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?
+            .map(parse_user)
+            .transpose()
+    }
+
+    async fn get_user_by_email(&self, email: &lettre::Address) -> Result<Option<User>, Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT id, username, email, admin FROM users WHERE email = ?1",
+                params![email.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?
+            .map(parse_user)
+            .transpose()
+    }
+
+    async fn add_pending_user(&self, user: &User, expires_at: DateTime<Utc>) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO pending_users (id, username, email, admin, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (id) DO UPDATE SET
+             username = excluded.username, email = excluded.email, admin = excluded.admin,
+             expires_at = excluded.expires_at",
+            params![
+                user.id.to_string(),
+                user.username,
+                user.email.to_string(),
+                user.admin,
+                expires_at.timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn get_pending_user(&self, user_id: &user::ID) -> Result<Option<User>, Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT id, username, email, admin FROM pending_users
+                 WHERE id = ?1 AND expires_at > ?2",
+                params![user_id.to_string(), Utc::now().timestamp()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?
+            .map(parse_user)
+            .transpose()
+    }
+
+    async fn get_pending_user_by_email(
+        &self,
+        email: &lettre::Address,
+    ) -> Result<Option<User>, Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT id, username, email, admin FROM pending_users
+                 WHERE email = ?1 AND expires_at > ?2",
+                params![email.to_string(), Utc::now().timestamp()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?
+            .map(parse_user)
+            .transpose()
+    }
+
+    async fn remove_pending_user(&self, user_id: &user::ID) -> Result<bool, Error> {
+        let removed = self.connection.lock().unwrap().execute(
+            "DELETE FROM pending_users WHERE id = ?1",
+            params![user_id.to_string()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    async fn remove_expired_pending_users(&self) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "DELETE FROM pending_users WHERE expires_at <= ?1",
+            params![Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    async fn add_structure(&self, structure: &Structure) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO structures (id, name) VALUES (?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET name = excluded.name",
+            params![structure.id.to_string(), structure.name],
+        )?;
+        Ok(())
+    }
+
+    async fn get_structure(
+        &self,
+        structure_id: &structure::ID,
+    ) -> Result<Option<Structure>, Error> {
+        let name = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT name FROM structures WHERE id = ?1",
+                params![structure_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(name.map(|name| Structure {
+            id: *structure_id,
+            name,
+        }))
+    }
+
+    async fn add_permission(&self, permission: &Permission) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO permissions (structure_id, user_id, is_manager) VALUES (?1, ?2, ?3)
+             ON CONFLICT (structure_id, user_id) DO UPDATE SET is_manager = excluded.is_manager",
+            params![
+                permission.structure_id.to_string(),
+                permission.user_id.to_string(),
+                permission.is_manager
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn get_permission(
+        &self,
+        structure_id: &structure::ID,
+        user_id: &user::ID,
+    ) -> Result<Option<Permission>, Error> {
+        let is_manager = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT is_manager FROM permissions WHERE structure_id = ?1 AND user_id = ?2",
+                params![structure_id.to_string(), user_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(is_manager.map(|is_manager| Permission {
+            structure_id: *structure_id,
+            user_id: *user_id,
+            is_manager,
+        }))
+    }
+
+    async fn add_hub(&self, hub: &Hub) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO hubs (id, name, password_hash, structure_id) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (id) DO UPDATE SET
+             name = excluded.name,
+             password_hash = excluded.password_hash,
+             structure_id = excluded.structure_id",
+            params![
+                hub.id.to_string(),
+                hub.name,
+                hub.password_hash,
+                hub.structure_id.to_string()
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn get_hub(&self, hub_id: &hub::ID) -> Result<Option<Hub>, Error> {
+        let row = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT name, password_hash, structure_id FROM hubs WHERE id = ?1",
+                params![hub_id.to_string()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        row.map(|(name, password_hash, structure_id)| {
+            Ok(Hub {
+                id: *hub_id,
+                name,
+                password_hash,
+                structure_id: parse_id(&structure_id)?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn revoke_refresh_token(&self, token_id: &token::ID) -> Result<bool, Error> {
+        let inserted = self.connection.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO revoked_refresh_tokens (id) VALUES (?1)",
+            params![token_id.to_string()],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    async fn is_refresh_token_revoked(&self, token_id: &token::ID) -> Result<bool, Error> {
+        let revoked = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT 1 FROM revoked_refresh_tokens WHERE id = ?1",
+                params![token_id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(revoked)
+    }
+
+    async fn revoke_refresh_token_family(&self, family_id: &token::ID) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO revoked_refresh_token_families (id) VALUES (?1)",
+            params![family_id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    async fn is_refresh_token_family_revoked(&self, family_id: &token::ID) -> Result<bool, Error> {
+        let revoked = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT 1 FROM revoked_refresh_token_families WHERE id = ?1",
+                params![family_id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(revoked)
+    }
+
+    async fn revoke_user_refresh_tokens(
+        &self,
+        user_id: &user::ID,
+        issued_before: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO user_refresh_tokens_revoked_at (user_id, revoked_at) VALUES (?1, ?2)
+             ON CONFLICT (user_id) DO UPDATE SET revoked_at = excluded.revoked_at",
+            params![user_id.to_string(), issued_before.timestamp_millis()],
+        )?;
+        Ok(())
+    }
+
+    async fn get_user_refresh_tokens_revoked_at(
+        &self,
+        user_id: &user::ID,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let timestamp: Option<i64> = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT revoked_at FROM user_refresh_tokens_revoked_at WHERE user_id = ?1",
+                params![user_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(timestamp.map(|timestamp| Utc.timestamp_millis(timestamp)))
+    }
+
+    async fn use_authorization_code(&self, code_id: &token::ID) -> Result<bool, Error> {
+        let inserted = self.connection.lock().unwrap().execute(
+            "INSERT OR IGNORE INTO used_authorization_codes (id) VALUES (?1)",
+            params![code_id.to_string()],
+        )?;
+        Ok(inserted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn store() {
+        let store = Store::new_in_memory().unwrap();
+        super::super::tests::store(&store).await;
+    }
+}
@@ -28,6 +28,36 @@ pub enum Type {
     Houseflow(manufacturers::Houseflow),
 }
 
+impl Type {
+    /// Names of the services that are implemented by accessories of this type
+    pub fn services(&self) -> Vec<services::ServiceName> {
+        use manufacturers::Houseflow;
+        use manufacturers::XiaomiMijia;
+        use services::ServiceName;
+
+        match self {
+            Self::XiaomiMijia(XiaomiMijia::HygroThermometer) => vec![
+                ServiceName::TemperatureSensor,
+                ServiceName::HumiditySensor,
+                ServiceName::Battery,
+            ],
+            Self::Houseflow(Houseflow::Gate) => vec![ServiceName::GarageDoorOpener],
+            Self::Houseflow(Houseflow::Garage) => vec![ServiceName::GarageDoorOpener],
+            Self::Houseflow(Houseflow::Lightbulb) => vec![ServiceName::Light],
+            Self::Houseflow(Houseflow::Thermostat) => vec![ServiceName::Thermostat],
+            Self::Houseflow(Houseflow::Lock) => vec![ServiceName::LockMechanism],
+            Self::Houseflow(Houseflow::MotionSensor) => vec![ServiceName::MotionSensor],
+            Self::Houseflow(Houseflow::ContactSensor) => vec![ServiceName::ContactSensor],
+            Self::Houseflow(Houseflow::LeakSensor) => vec![ServiceName::LeakSensor],
+            Self::Houseflow(Houseflow::SmokeSensor) => vec![ServiceName::SmokeSensor],
+            Self::Houseflow(Houseflow::OccupancySensor) => vec![ServiceName::OccupancySensor],
+            Self::Houseflow(Houseflow::Switch) => vec![ServiceName::Switch],
+            Self::Houseflow(Houseflow::Outlet) => vec![ServiceName::Outlet],
+            Self::Houseflow(Houseflow::Fan) => vec![ServiceName::Fan],
+        }
+    }
+}
+
 pub mod manufacturers {
     use serde::Deserialize;
     use serde::Serialize;
@@ -46,6 +76,16 @@ pub mod manufacturers {
         Gate,
         Garage,
         Lightbulb,
+        Thermostat,
+        Lock,
+        MotionSensor,
+        ContactSensor,
+        LeakSensor,
+        SmokeSensor,
+        OccupancySensor,
+        Switch,
+        Outlet,
+        Fan,
     }
 }
 
@@ -66,6 +106,12 @@ pub enum Error {
     /// Accessory does not support the specified service
     #[error("service is not supported")]
     ServiceNotSupported,
+    /// Accessory did not respond in time
+    #[error("request timed out")]
+    Timeout,
+    /// Accessory failed to read the value of the characteristic
+    #[error("reading characteristic failed")]
+    ReadFailed,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, strum::Display)]
@@ -133,6 +179,16 @@ pub mod services {
         GarageDoorOpener(GarageDoorOpener),
         Light(Light),
         Battery(Battery),
+        Thermostat(Thermostat),
+        LockMechanism(LockMechanism),
+        MotionSensor(MotionSensor),
+        ContactSensor(ContactSensor),
+        LeakSensor(LeakSensor),
+        SmokeSensor(SmokeSensor),
+        OccupancySensor(OccupancySensor),
+        Switch(Switch),
+        Outlet(Outlet),
+        Fan(Fan),
     }
 
     impl ServiceName {
@@ -159,12 +215,83 @@ pub mod services {
     pub struct Light {
         pub on: characteristics::On,
         pub brightness: Option<characteristics::Brightness>,
+        pub hue: Option<characteristics::Hue>,
+        pub saturation: Option<characteristics::Saturation>,
+        pub color_temperature: Option<characteristics::ColorTemperature>,
     }
 
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct Battery {
         pub battery_level: characteristics::BatteryLevel,
     }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Thermostat {
+        pub current_temperature: characteristics::CurrentTemperature,
+        pub target_temperature: characteristics::TargetTemperature,
+        pub current_heating_cooling_state: characteristics::CurrentHeatingCoolingState,
+        pub target_heating_cooling_state: characteristics::TargetHeatingCoolingState,
+        pub temperature_display_units: characteristics::TemperatureDisplayUnits,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct LockMechanism {
+        pub lock_current_state: characteristics::LockCurrentState,
+        pub lock_target_state: characteristics::LockTargetState,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct MotionSensor {
+        pub motion_detected: characteristics::MotionDetected,
+        pub status_tampered: Option<characteristics::StatusTampered>,
+        pub status_low_battery: Option<characteristics::StatusLowBattery>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct ContactSensor {
+        pub contact_sensor_state: characteristics::ContactSensorState,
+        pub status_tampered: Option<characteristics::StatusTampered>,
+        pub status_low_battery: Option<characteristics::StatusLowBattery>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct LeakSensor {
+        pub leak_detected: characteristics::LeakDetected,
+        pub status_tampered: Option<characteristics::StatusTampered>,
+        pub status_low_battery: Option<characteristics::StatusLowBattery>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SmokeSensor {
+        pub smoke_detected: characteristics::SmokeDetected,
+        pub status_tampered: Option<characteristics::StatusTampered>,
+        pub status_low_battery: Option<characteristics::StatusLowBattery>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct OccupancySensor {
+        pub occupancy_detected: characteristics::OccupancyDetected,
+        pub status_tampered: Option<characteristics::StatusTampered>,
+        pub status_low_battery: Option<characteristics::StatusLowBattery>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Switch {
+        pub on: characteristics::On,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Outlet {
+        pub on: characteristics::On,
+        pub outlet_in_use: characteristics::OutletInUse,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Fan {
+        pub on: characteristics::On,
+        pub rotation_speed: Option<characteristics::RotationSpeed>,
+        pub rotation_direction: Option<characteristics::RotationDirection>,
+    }
 }
 
 pub mod characteristics {
@@ -174,6 +301,7 @@ pub mod characteristics {
 
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, EnumDiscriminants)]
     #[strum_discriminants(derive(
+        Hash,
         Serialize,
         Deserialize,
         strum::Display,
@@ -187,12 +315,32 @@ pub mod characteristics {
     #[strum(serialize_all = "kebab-case")]
     pub enum Characteristic {
         On(On),
+        Brightness(Brightness),
+        Hue(Hue),
+        Saturation(Saturation),
+        ColorTemperature(ColorTemperature),
         CurrentTemperature(CurrentTemperature),
         CurrentHumidity(CurrentHumidity),
         CurrentDoorState(CurrentDoorState),
         TargetDoorState(TargetDoorState),
         BatteryLevel(BatteryLevel),
         ChargingState(ChargingState),
+        TargetTemperature(TargetTemperature),
+        CurrentHeatingCoolingState(CurrentHeatingCoolingState),
+        TargetHeatingCoolingState(TargetHeatingCoolingState),
+        TemperatureDisplayUnits(TemperatureDisplayUnits),
+        LockCurrentState(LockCurrentState),
+        LockTargetState(LockTargetState),
+        MotionDetected(MotionDetected),
+        ContactSensorState(ContactSensorState),
+        LeakDetected(LeakDetected),
+        SmokeDetected(SmokeDetected),
+        OccupancyDetected(OccupancyDetected),
+        StatusTampered(StatusTampered),
+        StatusLowBattery(StatusLowBattery),
+        OutletInUse(OutletInUse),
+        RotationSpeed(RotationSpeed),
+        RotationDirection(RotationDirection),
     }
 
     impl CharacteristicName {
@@ -235,6 +383,27 @@ pub mod characteristics {
         pub percentage: u8,
     }
 
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct Hue {
+        /// Hue of the color in degrees, from 0 to 360
+        pub hue: f32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct Saturation {
+        /// Saturation of the color in percents, from 0 to 100
+        pub saturation: f32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct ColorTemperature {
+        /// Color temperature of white light in kelvins
+        pub kelvin: u16,
+    }
+
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     #[serde(rename_all = "kebab-case")]
     pub struct BatteryLevel {
@@ -248,4 +417,165 @@ pub mod characteristics {
         Charging,
         NotChargeable,
     }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct TargetTemperature {
+        pub temperature: f32,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum HeatingCoolingState {
+        Off,
+        Heat,
+        Cool,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum HeatingCoolingMode {
+        Off,
+        Heat,
+        Cool,
+        /// Heats or cools as needed
+        Auto,
+    }
+
+    /// What the thermostat is currently doing
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct CurrentHeatingCoolingState {
+        pub state: HeatingCoolingState,
+    }
+
+    /// Mode the thermostat should operate in
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct TargetHeatingCoolingState {
+        pub state: HeatingCoolingMode,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum TemperatureUnits {
+        Celsius,
+        Fahrenheit,
+    }
+
+    /// Units in which the temperatures are displayed, the values are always in Celsius
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct TemperatureDisplayUnits {
+        pub units: TemperatureUnits,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum LockState {
+        Unsecured,
+        Secured,
+        /// Lock couldn't reach the target state
+        Jammed,
+        Unknown,
+    }
+
+    /// State that the lock should reach
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum LockTarget {
+        Unsecured,
+        Secured,
+    }
+
+    impl From<LockTarget> for LockState {
+        fn from(target: LockTarget) -> Self {
+            match target {
+                LockTarget::Unsecured => Self::Unsecured,
+                LockTarget::Secured => Self::Secured,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct LockCurrentState {
+        pub state: LockState,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct LockTargetState {
+        pub state: LockTarget,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct MotionDetected {
+        pub detected: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct ContactSensorState {
+        /// Whether the contact is detected, e.g. the door or window is closed
+        pub contact: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct LeakDetected {
+        pub detected: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct SmokeDetected {
+        pub detected: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct OccupancyDetected {
+        pub detected: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct StatusTampered {
+        pub tampered: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct StatusLowBattery {
+        pub low_battery: bool,
+    }
+
+    /// Whether something is plugged into the outlet and draws power
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct OutletInUse {
+        pub in_use: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct RotationSpeed {
+        /// Speed of the fan in percents of the maximum speed
+        pub percentage: u8,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum Direction {
+        Clockwise,
+        CounterClockwise,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct RotationDirection {
+        pub direction: Direction,
+    }
 }
@@ -19,3 +19,12 @@ pub enum Response {
     },
     VerificationCodeSent,
 }
+
+/// Form sent by Google Identity Services to the login URI after the user signs in
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GoogleRequest {
+    /// ID token issued by Google
+    pub credential: String,
+    /// CSRF token, must match the value of `g_csrf_token` cookie
+    pub g_csrf_token: String,
+}
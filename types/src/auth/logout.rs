@@ -1,8 +1,13 @@
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Request {}
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Request {
+    /// Revoke all refresh tokens of the user, instead of only the one that was sent
+    #[serde(default)]
+    pub all_sessions: bool,
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Response {}
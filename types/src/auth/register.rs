@@ -1,21 +1,22 @@
+use crate::code::VerificationCode;
 use crate::user;
 use serde::Deserialize;
 use serde::Serialize;
 use validator::Validate;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "kebab-case")]
 pub struct Request {
-    #[validate(email)]
-    pub email: String,
+    pub email: lettre::Address,
+    #[validate(length(min = 1, max = 64))]
     pub username: String,
-    #[validate(length(min = 8))]
-    pub password: String,
+    /// Code sent to the email, confirms the registration if present
+    pub verification_code: Option<VerificationCode>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Response {
-    pub user_id: user::ID,
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum Response {
+    Registered { user_id: user::ID },
+    VerificationCodeSent,
 }
-
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, thiserror::Error)]
-pub enum Error {}
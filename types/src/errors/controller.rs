@@ -11,3 +11,12 @@ pub enum Error {
     #[error("request timeout")]
     Timeout,
 }
+
+impl From<accessory::Error> for Error {
+    fn from(err: accessory::Error) -> Self {
+        match err {
+            accessory::Error::Timeout => Self::Timeout,
+            err => Self::AccessoryError(err),
+        }
+    }
+}
@@ -7,6 +7,8 @@ pub enum Error {
     Clerk(String),
     #[error("mailer: {0}")]
     Mailer(String),
+    #[error("store: {0}")]
+    Store(String),
     #[error("other: {0}")]
     Other(String),
     #[error("rendering template: {0}")]
@@ -24,8 +24,12 @@ use serde::Serialize;
 pub enum ServerError {
     #[error("internal error: {0}")]
     InternalError(#[from] InternalError),
-    #[error("too many requests, please slow down")]
-    TooManyRequests,
+    #[error("too many requests, retry after {retry_after} seconds")]
+    TooManyRequests {
+        /// Time in seconds after which the request can be retried
+        #[serde(rename = "retry-after")]
+        retry_after: u64,
+    },
     #[error("validation error: {0}")]
     ValidationError(String),
     #[error("auth error: {0}")]
@@ -45,7 +49,7 @@ impl axum::response::IntoResponse for ServerError {
         use axum::http::StatusCode;
 
         let status = match self {
-            Self::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
             Self::ValidationError(_) => StatusCode::BAD_REQUEST,
             Self::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::AuthError(ref err) => match err {
@@ -62,7 +66,7 @@ impl axum::response::IntoResponse for ServerError {
                 AuthError::InvalidGoogleJwt(_) => StatusCode::UNAUTHORIZED,
                 AuthError::InvalidCsrfToken => StatusCode::UNAUTHORIZED,
             },
-            Self::OAuthError(_) => StatusCode::BAD_REQUEST,
+            Self::OAuthError(err) => return err.into_response(),
             Self::ControllerError(ref err) => match err {
                 ControllerError::AccessoryNotConnected => StatusCode::NOT_ACCEPTABLE,
                 ControllerError::Timeout => StatusCode::REQUEST_TIMEOUT,
@@ -72,14 +76,25 @@ impl axum::response::IntoResponse for ServerError {
                     accessory::Error::CharacteristicNotSupported => StatusCode::BAD_REQUEST,
                     accessory::Error::ServiceNotSupported => StatusCode::BAD_REQUEST,
                     accessory::Error::NotConnected => StatusCode::SERVICE_UNAVAILABLE,
+                    accessory::Error::Timeout => StatusCode::GATEWAY_TIMEOUT,
+                    accessory::Error::ReadFailed => StatusCode::BAD_GATEWAY,
                 },
             },
             Self::ProviderError(ref err) => match err {
                 ProviderError::AlreadyConnected => StatusCode::NOT_ACCEPTABLE,
             },
         };
+        let retry_after = match self {
+            Self::TooManyRequests { retry_after } => Some(retry_after),
+            _ => None,
+        };
         let mut response = axum::Json(self).into_response();
         *response.status_mut() = status;
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, retry_after.into());
+        }
 
         response
     }
@@ -32,3 +32,49 @@ pub enum Error {
     #[error("unsupported grant type, description: {0:?}")]
     UnsupportedGrantType(Option<String>),
 }
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for Error {
+    fn into_response(self) -> axum::response::Response {
+        use axum::http::StatusCode;
+
+        /// Error response as described in RFC 6749 section 5.2
+        #[derive(Serialize)]
+        struct ErrorResponse {
+            error: &'static str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            error_description: Option<String>,
+        }
+
+        let (status, error, error_description) = match self {
+            Self::InvalidRequest(description) => {
+                (StatusCode::BAD_REQUEST, "invalid_request", description)
+            }
+            Self::InvalidClient(description) => {
+                (StatusCode::UNAUTHORIZED, "invalid_client", description)
+            }
+            Self::InvalidGrant(description) => {
+                (StatusCode::BAD_REQUEST, "invalid_grant", description)
+            }
+            Self::InvalidScope(description) => {
+                (StatusCode::BAD_REQUEST, "invalid_scope", description)
+            }
+            Self::UnauthorizedClient(description) => {
+                (StatusCode::BAD_REQUEST, "unauthorized_client", description)
+            }
+            Self::UnsupportedGrantType(description) => (
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                description,
+            ),
+        };
+        let mut response = axum::Json(ErrorResponse {
+            error,
+            error_description,
+        })
+        .into_response();
+        *response.status_mut() = status;
+
+        response
+    }
+}
@@ -58,3 +58,31 @@ pub struct CharateristicWriteResult {
     pub id: FrameID,
     pub result: accessory::Result<()>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accessory::characteristics;
+
+    #[test]
+    fn write_characteristic() {
+        let frame = HubFrame::WriteCharacteristic(WriteCharacteristic {
+            id: 7,
+            service_name: ServiceName::Fan,
+            characteristic: Characteristic::RotationDirection(characteristics::RotationDirection {
+                direction: characteristics::Direction::CounterClockwise,
+            }),
+        });
+        let json = serde_json::json!({
+            "type": "write-characteristic",
+            "id": 7,
+            "service-name": "fan",
+            "characteristic": {
+                "name": "rotation-direction",
+                "direction": "counter-clockwise",
+            },
+        });
+        assert_eq!(serde_json::to_value(&frame).unwrap(), json);
+        assert_eq!(serde_json::from_value::<HubFrame>(json).unwrap(), frame);
+    }
+}
@@ -38,6 +38,8 @@ impl axum::response::IntoResponse for Error {
                 accessory::Error::CharacteristicNotSupported => StatusCode::BAD_REQUEST,
                 accessory::Error::ServiceNotSupported => StatusCode::BAD_REQUEST,
                 accessory::Error::NotConnected => StatusCode::SERVICE_UNAVAILABLE,
+                accessory::Error::Timeout => StatusCode::GATEWAY_TIMEOUT,
+                accessory::Error::ReadFailed => StatusCode::BAD_GATEWAY,
             },
         };
         let mut response = axum::Json(self).into_response();
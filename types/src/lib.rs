@@ -19,6 +19,15 @@ pub mod lighthouse;
 #[cfg(feature = "meta")]
 pub mod meta;
 
+#[cfg(feature = "oauth")]
+pub mod oauth;
+
+#[cfg(feature = "password")]
+pub mod password;
+
+#[cfg(feature = "requests")]
+pub mod requests;
+
 #[cfg(feature = "token")]
 pub mod token;
 
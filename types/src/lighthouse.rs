@@ -22,6 +22,8 @@ pub enum HubFrame {
     AccessoryConnected(Accessory),
     AccessoryDisconnected(accessory::ID),
     UpdateCharacteristic(UpdateCharacteristic),
+    /// Batch of characteristic updates, at most one per characteristic
+    UpdateCharacteristics(Vec<UpdateCharacteristic>),
     ReadCharacteristicResult(ReadCharacteristicResult),
     WriteCharacteristicResult(WriteCharacteristicResult),
 }
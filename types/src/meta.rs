@@ -1 +1,155 @@
+use crate::accessory;
+use crate::accessory::characteristics::Characteristic;
+use crate::accessory::services::ServiceName;
+use crate::accessory::Accessory;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
 
+/// Last known value of the characteristic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CharacteristicState {
+    pub service_name: ServiceName,
+    pub characteristic: Characteristic,
+    /// Time when the value was received from the accessory
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Last known state of the accessory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AccessoryState {
+    pub accessory: Accessory,
+    pub characteristics: Vec<CharacteristicState>,
+}
+
+pub mod read_characteristic {
+    use serde::Deserialize;
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub struct Query {
+        /// Read the value from the accessory, even if there is a fresh one in the cache
+        #[serde(default)]
+        pub fresh: bool,
+    }
+}
+
+pub mod structure_state {
+    pub type Response = Vec<super::AccessoryState>;
+}
+
+/// Accessory known to the server, along with its connection state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AccessoryInfo {
+    #[serde(flatten)]
+    pub accessory: Accessory,
+    /// Names of the services implemented by the accessory
+    pub services: Vec<ServiceName>,
+    /// Whether the accessory is currently connected to its hub
+    pub connected: bool,
+}
+
+pub mod get_accessories {
+    pub type Response = Vec<super::AccessoryInfo>;
+}
+
+/// Event streamed to the subscribed clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Event {
+    AccessoryConnected(Accessory),
+    AccessoryDisconnected {
+        #[serde(rename = "accessory-id")]
+        accessory_id: accessory::ID,
+    },
+    CharacteristicUpdated(CharacteristicUpdated),
+    /// Some of the events since the last one seen by the client can't be replayed,
+    /// so the client has to fetch the state of the accessories again
+    Resync,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CharacteristicUpdated {
+    pub accessory_id: accessory::ID,
+    pub service_name: ServiceName,
+    pub characteristic: Characteristic,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accessory::characteristics;
+
+    #[test]
+    fn event() {
+        let accessory_id = accessory::ID::new_v4();
+        let event = Event::AccessoryDisconnected { accessory_id };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "accessory-disconnected",
+                "accessory-id": accessory_id,
+            })
+        );
+
+        let event = Event::CharacteristicUpdated(CharacteristicUpdated {
+            accessory_id,
+            service_name: ServiceName::Light,
+            characteristic: Characteristic::On(characteristics::On { on: true }),
+        });
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "characteristic-updated",
+                "accessory-id": accessory_id,
+                "service-name": "light",
+                "characteristic": {
+                    "name": "on",
+                    "on": true,
+                },
+            })
+        );
+
+        assert_eq!(
+            serde_json::to_value(&Event::Resync).unwrap(),
+            serde_json::json!({ "type": "resync" })
+        );
+    }
+
+    #[test]
+    fn accessory_info() {
+        use crate::accessory::manufacturers::Houseflow;
+
+        let accessory = Accessory {
+            id: accessory::ID::new_v4(),
+            name: String::from("Ceiling light"),
+            room_name: String::from("Bedroom"),
+            r#type: accessory::Type::Houseflow(Houseflow::Lightbulb),
+        };
+        let info = AccessoryInfo {
+            services: accessory.r#type.services(),
+            accessory: accessory.clone(),
+            connected: true,
+        };
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": accessory.id,
+                "name": "Ceiling light",
+                "room-name": "Bedroom",
+                "manufacturer": "houseflow",
+                "model": "lightbulb",
+                "services": ["light"],
+                "connected": true,
+            })
+        );
+        assert_eq!(serde_json::from_value::<AccessoryInfo>(json).unwrap(), info);
+    }
+}
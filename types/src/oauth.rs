@@ -0,0 +1,111 @@
+use crate::code::VerificationCode;
+use chrono::Duration;
+use serde::Deserialize;
+use serde::Serialize;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseType {
+    Code,
+}
+
+/// Query parameters of the authorization request, described in RFC 6749 section 4.1.1
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationRequestQuery {
+    /// The client ID you assigned to the client.
+    pub client_id: String,
+    /// The URL to which you send the response to this request.
+    pub redirect_uri: Url,
+    /// A bookkeeping value that is passed back to the client unchanged in the redirect URI.
+    pub state: String,
+    /// The type of value to return in the response.
+    pub response_type: ResponseType,
+    /// Optional, space-delimited set of scope strings that specify the data the client is requesting authorization for.
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Optional, the user's language in RFC5646 format.
+    #[serde(default)]
+    pub user_locale: Option<String>,
+}
+
+/// Form sent from the authorization page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuthorizationRequestForm {
+    pub email: lettre::Address,
+    #[serde(default)]
+    pub verification_code: Option<VerificationCode>,
+}
+
+/// Parameters of the access token request, described in RFC 6749 sections 4.1.3 and 6
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenRequest {
+    /// The client ID you assigned to the client.
+    pub client_id: String,
+    /// The client secret you assigned to the client.
+    pub client_secret: String,
+    #[serde(flatten)]
+    pub grant: TokenRequestGrant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "grant_type", rename_all = "snake_case")]
+pub enum TokenRequestGrant {
+    /// Exchange of an authorization code for access and refresh tokens
+    AuthorizationCode {
+        /// The authorization code received from the authorization endpoint.
+        code: String,
+        /// The redirect URL used in the initial authorization request.
+        #[serde(default)]
+        redirect_uri: Option<Url>,
+    },
+    /// Exchange of a refresh token for an access token
+    RefreshToken {
+        /// The refresh token received from the previous token exchange.
+        refresh_token: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenType {
+    Bearer,
+}
+
+/// Response of the access token request, described in RFC 6749 section 5.1
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenResponse {
+    /// The type of the token, always `Bearer`.
+    pub token_type: TokenType,
+    /// The access token for the user.
+    pub access_token: String,
+    /// The refresh token for the user, returned only on the authorization code exchange.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// The number of seconds until the access token expires.
+    #[serde(with = "crate::serde_token_expiration")]
+    pub expires_in: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_response() {
+        let response = TokenResponse {
+            token_type: TokenType::Bearer,
+            access_token: String::from("access-token"),
+            refresh_token: None,
+            expires_in: Some(Duration::minutes(10)),
+        };
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 600,
+            })
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::PasswordHash;
+use argon2::password_hash::PasswordHasher;
+use argon2::password_hash::PasswordVerifier;
+use argon2::password_hash::SaltString;
+use argon2::Argon2;
+
+pub use argon2::password_hash::Error;
+
+/// Hashes the password with Argon2id and a random salt, returns the hash in the PHC string format
+pub fn hash(password: &str) -> Result<String, Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+    Ok(hash.to_string())
+}
+
+/// Verifies the password against hash in the PHC string format
+///
+/// Returns `Ok(false)` if the password doesn't match, and `Err` if the hash is malformed.
+pub fn verify(password: &str, hash: &str) -> Result<bool, Error> {
+    let hash = PasswordHash::new(hash)?;
+    match Argon2::default().verify_password(password.as_bytes(), &hash) {
+        Ok(()) => Ok(true),
+        Err(Error::Password) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_and_verify() {
+        let hash = hash("some-password").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify("some-password", &hash).unwrap());
+        assert!(!verify("other-password", &hash).unwrap());
+    }
+
+    #[test]
+    fn malformed_hash() {
+        assert!(verify("some-password", "some-password-hash").is_err());
+    }
+}
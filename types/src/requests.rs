@@ -0,0 +1,135 @@
+use crate::accessory;
+use crate::accessory::characteristics::Characteristic;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// ID of a request frame, the same in the hive and lighthouse protocols
+pub type FrameID = u16;
+
+pub type ReadResult = Result<Characteristic, accessory::Error>;
+pub type WriteResult = Result<(), accessory::Error>;
+
+/// Characteristic read and write requests sent over a session, waiting for their result frames
+#[derive(Debug, Default)]
+pub struct PendingRequests {
+    next_frame_id: FrameID,
+    read_results: HashMap<FrameID, oneshot::Sender<ReadResult>>,
+    write_results: HashMap<FrameID, oneshot::Sender<WriteResult>>,
+}
+
+impl PendingRequests {
+    /// Adds a read request, returns ID for its frame and the receiver of its result
+    pub fn read(&mut self) -> (FrameID, oneshot::Receiver<ReadResult>) {
+        let id = self.allocate_frame_id();
+        let (sender, receiver) = oneshot::channel();
+        self.read_results.insert(id, sender);
+        (id, receiver)
+    }
+
+    /// Adds a write request, returns ID for its frame and the receiver of its result
+    pub fn write(&mut self) -> (FrameID, oneshot::Receiver<WriteResult>) {
+        let id = self.allocate_frame_id();
+        let (sender, receiver) = oneshot::channel();
+        self.write_results.insert(id, sender);
+        (id, receiver)
+    }
+
+    /// Resolves the read request, returns false if there is no request with such ID
+    pub fn resolve_read(&mut self, id: FrameID, result: ReadResult) -> bool {
+        match self.read_results.remove(&id) {
+            Some(sender) => {
+                sender.send(result).ok();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resolves the write request, returns false if there is no request with such ID
+    pub fn resolve_write(&mut self, id: FrameID, result: WriteResult) -> bool {
+        match self.write_results.remove(&id) {
+            Some(sender) => {
+                sender.send(result).ok();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Allocates ID for a new request frame, skipping IDs of the requests that are still pending.
+    ///
+    /// Requests abandoned by the caller, e.g. after a timeout, are cleaned up first.
+    fn allocate_frame_id(&mut self) -> FrameID {
+        self.read_results.retain(|_, sender| !sender.is_closed());
+        self.write_results.retain(|_, sender| !sender.is_closed());
+        loop {
+            let id = self.next_frame_id;
+            self.next_frame_id = self.next_frame_id.wrapping_add(1);
+            if !self.read_results.contains_key(&id) && !self.write_results.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+}
+
+/// Waits for the result of a request sent over a session.
+///
+/// Resolves to `accessory::Error::Timeout` if there is no response in time,
+/// and to `accessory::Error::NotConnected` if the session was closed in the meantime.
+pub async fn wait_for_result<T>(
+    receiver: oneshot::Receiver<Result<T, accessory::Error>>,
+    request_timeout: Duration,
+) -> Result<T, accessory::Error> {
+    match tokio::time::timeout(request_timeout, receiver).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(accessory::Error::NotConnected),
+        Err(_) => Err(accessory::Error::Timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_for_result_timeout() {
+        let (_sender, receiver) = oneshot::channel::<Result<(), accessory::Error>>();
+        let result = wait_for_result(receiver, Duration::from_millis(10)).await;
+        assert_eq!(result, Err(accessory::Error::Timeout));
+    }
+
+    #[tokio::test]
+    async fn wait_for_result_session_closed() {
+        let (sender, receiver) = oneshot::channel::<Result<(), accessory::Error>>();
+        drop(sender);
+        let result = wait_for_result(receiver, Duration::from_secs(1)).await;
+        assert_eq!(result, Err(accessory::Error::NotConnected));
+    }
+
+    #[tokio::test]
+    async fn wait_for_result_ok() {
+        let (sender, receiver) = oneshot::channel::<Result<(), accessory::Error>>();
+        sender.send(Ok(())).unwrap();
+        let result = wait_for_result(receiver, Duration::from_secs(1)).await;
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn allocate_frame_id() {
+        let mut requests = PendingRequests {
+            next_frame_id: FrameID::MAX,
+            ..Default::default()
+        };
+        let (pending_id, _pending) = requests.write();
+        let (abandoned_id, abandoned) = requests.read();
+        assert_eq!((pending_id, abandoned_id), (FrameID::MAX, 0));
+        drop(abandoned);
+
+        // Wraps around, skipping the pending request, but reusing ID of the abandoned one
+        requests.next_frame_id = FrameID::MAX;
+        assert_eq!(requests.read().0, 0);
+        assert!(requests.resolve_write(pending_id, Ok(())));
+        assert!(!requests.resolve_write(pending_id, Ok(())));
+    }
+}
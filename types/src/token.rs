@@ -1,5 +1,7 @@
+use crate::client::Client;
 use crate::errors::TokenError as Error;
 use chrono::DateTime;
+use chrono::SubsecRound;
 use chrono::Utc;
 use jsonwebtoken::{
     decode, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
@@ -8,6 +10,7 @@ use serde::de;
 use serde::ser;
 use serde::Deserialize;
 use serde::Serialize;
+use url::Url;
 use uuid::Uuid;
 
 #[derive(Clone, PartialEq)]
@@ -46,6 +49,9 @@ impl<C: TokenClaims> std::ops::Deref for Token<C> {
     }
 }
 
+/// Unique ID of a refresh token or an authorization code, used to revoke it
+pub type ID = Uuid;
+
 pub type AccessToken = Token<AccessTokenClaims>;
 pub type RefreshToken = Token<RefreshTokenClaims>;
 pub type AuthorizationCode = Token<AuthorizationCodeClaims>;
@@ -67,7 +73,13 @@ impl TokenClaims for AccessTokenClaims {}
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuthorizationCodeClaims {
+    /// Unique ID of the code, used to make sure it is exchanged only once
+    pub jti: ID,
     pub sub: Uuid,
+    /// ID of the client the code has been issued to
+    pub client_id: String,
+    /// Redirect URI from the authorization request, must match the one in the token request
+    pub redirect_uri: Url,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub exp: DateTime<Utc>,
 }
@@ -76,13 +88,74 @@ impl TokenClaims for AuthorizationCodeClaims {}
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RefreshTokenClaims {
+    pub jti: ID,
+    /// ID shared by the token and all tokens it was rotated into
+    pub family: ID,
     pub sub: Uuid,
+    /// Client to which the token was issued, the only one that can use it
+    pub client: Client,
+    /// Time at which the token was issued, with millisecond precision
+    #[serde(with = "ts_seconds_millis")]
+    pub iat: DateTime<Utc>,
     #[serde(with = "chrono::serde::ts_seconds_option")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub exp: Option<DateTime<Utc>>,
 }
 
+impl RefreshTokenClaims {
+    /// Creates claims of a new token with random ID, issued now
+    pub fn new(sub: Uuid, client: Client, exp: Option<DateTime<Utc>>) -> Self {
+        let jti = ID::new_v4();
+        Self {
+            jti,
+            family: jti,
+            sub,
+            client,
+            iat: Utc::now().trunc_subsecs(3),
+            exp,
+        }
+    }
+
+    /// Creates claims of a token that replaces this one, within the same family and with the
+    /// refresh token duration of the same client
+    pub fn rotate(&self) -> Self {
+        let exp = self
+            .client
+            .refresh_token_duration()
+            .map(|duration| Utc::now() + duration);
+        Self {
+            family: self.family,
+            ..Self::new(self.sub, self.client, exp)
+        }
+    }
+}
+
+/// (De)serializes time as a fractional number of seconds since the epoch, keeping the milliseconds.
+///
+/// JWT allows non-integer NumericDate values, and tokens with an integer number of seconds are
+/// still accepted.
+mod ts_seconds_millis {
+    use chrono::DateTime;
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use serde::Deserialize;
+
+    pub fn serialize<S: serde::Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(time.timestamp_millis() as f64 / 1000.0)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let seconds = f64::deserialize(deserializer)?;
+        Ok(Utc.timestamp_millis((seconds * 1000.0).round() as i64))
+    }
+}
+
 impl TokenClaims for RefreshTokenClaims {
     // that's temporary untill https://github.com/Keats/jsonwebtoken/issues/239 resolves
     fn validation(token: &str) -> Validation {
@@ -163,6 +236,7 @@ impl<C: TokenClaims> Token<C> {
 mod tests {
     use super::*;
     use chrono::SubsecRound;
+    use chrono::TimeZone;
     fn get_key() -> Vec<u8> {
         use rand::RngCore;
         let mut bytes = [0; 32];
@@ -233,10 +307,11 @@ mod tests {
         #[test]
         fn valid_with_exp() {
             let key = get_key();
-            let payload = RefreshTokenClaims {
-                sub: Uuid::new_v4(),
-                exp: Some(Utc::now().round_subsecs(0) + chrono::Duration::hours(1)),
-            };
+            let payload = RefreshTokenClaims::new(
+                Uuid::new_v4(),
+                Client::Internal,
+                Some(Utc::now().round_subsecs(0) + chrono::Duration::hours(1)),
+            );
             let token = RefreshToken::new(&key, payload).unwrap();
             let encoded = token.encode();
             let decoded = RefreshToken::decode(&key, &encoded).unwrap();
@@ -247,10 +322,7 @@ mod tests {
         #[test]
         fn valid_without_exp() {
             let key = get_key();
-            let payload = RefreshTokenClaims {
-                sub: Uuid::new_v4(),
-                exp: None,
-            };
+            let payload = RefreshTokenClaims::new(Uuid::new_v4(), Client::Internal, None);
             let token = RefreshToken::new(&key, payload).unwrap();
             let encoded = token.encode();
             dbg!(&encoded);
@@ -263,10 +335,11 @@ mod tests {
         fn expired() {
             let key = get_key();
             let expired_by = chrono::Duration::hours(1);
-            let payload = RefreshTokenClaims {
-                sub: Uuid::new_v4(),
-                exp: Some(Utc::now() - expired_by),
-            };
+            let payload = RefreshTokenClaims::new(
+                Uuid::new_v4(),
+                Client::Internal,
+                Some(Utc::now() - expired_by),
+            );
             let token = Token::new(&key, payload).unwrap();
             let encoded = token.encode();
             let err = RefreshToken::decode(&key, &encoded).unwrap_err();
@@ -282,10 +355,11 @@ mod tests {
         fn invalid_signature() {
             let valid_key = get_key();
             let invalid_key = get_key();
-            let payload = RefreshTokenClaims {
-                sub: Uuid::new_v4(),
-                exp: Some(Utc::now().round_subsecs(0) + chrono::Duration::hours(1)),
-            };
+            let payload = RefreshTokenClaims::new(
+                Uuid::new_v4(),
+                Client::Internal,
+                Some(Utc::now().round_subsecs(0) + chrono::Duration::hours(1)),
+            );
             let token = RefreshToken::new(&valid_key, payload).unwrap();
             let encoded = token.encode();
             let err = RefreshToken::decode(&invalid_key, &encoded).unwrap_err();
@@ -296,5 +370,33 @@ mod tests {
                 }
             );
         }
+
+        #[test]
+        fn issued_at_millis() {
+            let claims = RefreshTokenClaims::new(Uuid::new_v4(), Client::Internal, None);
+            let serialized = serde_json::to_value(&claims).unwrap();
+            let deserialized: RefreshTokenClaims = serde_json::from_value(serialized).unwrap();
+            assert_eq!(claims, deserialized);
+
+            // Issue time in whole seconds, as serialized before the millisecond precision, is still
+            // accepted
+            let mut serialized = serde_json::to_value(&claims).unwrap();
+            serialized["iat"] = serde_json::json!(1_600_000_000);
+            let deserialized: RefreshTokenClaims = serde_json::from_value(serialized).unwrap();
+            assert_eq!(deserialized.iat, Utc.timestamp(1_600_000_000, 0));
+        }
+
+        #[test]
+        fn rotate() {
+            let claims = RefreshTokenClaims::new(Uuid::new_v4(), Client::Internal, None);
+            let rotated = claims.rotate();
+            assert_ne!(rotated.jti, claims.jti);
+            assert_eq!(rotated.family, claims.family);
+            assert_eq!(rotated.client, Client::Internal);
+            assert!(rotated.exp.is_some());
+
+            let claims = RefreshTokenClaims::new(Uuid::new_v4(), Client::GoogleHome, None);
+            assert_eq!(claims.rotate().exp, None);
+        }
     }
 }